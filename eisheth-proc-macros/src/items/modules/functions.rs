@@ -27,9 +27,12 @@ pub fn make_function_definition(
                 })
                 .map(|x| x.ty.clone());
             let return_type = &f.signature.return_type;
+            let abi = f
+                .abi
+                .as_ref()
+                .map_or_else(|| quote! { extern "C" }, |abi| quote! { #abi });
 
-            let signature_for_cast =
-                quote! { unsafe extern "C" fn(#(#argument_types),*) #return_type };
+            let signature_for_cast = quote! { unsafe #abi fn(#(#argument_types),*) #return_type };
 
             let signature = make_function_signature(visibility, name, &f.signature);
             quote! {
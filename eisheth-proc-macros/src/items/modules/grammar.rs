@@ -1,5 +1,5 @@
 use syn::{
-    BareFnArg, Ident, Lit, LitInt, Path, ReturnType, Token, Type, braced, parenthesized,
+    Abi, BareFnArg, Ident, Lit, LitInt, Path, ReturnType, Token, Type, braced, parenthesized,
     parse::Parse,
     punctuated::Punctuated,
     token::{Brace, Caret, Colon, Comma, Dot, Paren},
@@ -103,6 +103,9 @@ impl Parse for FunctionDefinitionKind {
 
 pub struct RuntimeFunctionSignature {
     _runtime: keywords::runtime,
+    /// The ABI of the runtime function, e.g. `extern "C-unwind"` for functions which may panic,
+    /// `extern "C"` unless specified.
+    pub abi: Option<Abi>,
     pub signature: FunctionSignature,
 }
 
@@ -110,6 +113,13 @@ impl Parse for RuntimeFunctionSignature {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
         Ok(Self {
             _runtime: input.parse()?,
+            abi: {
+                if input.peek(Token![extern]) {
+                    Some(input.parse()?)
+                } else {
+                    None
+                }
+            },
             signature: input.parse()?,
         })
     }
@@ -122,7 +122,11 @@ fn make_define_function<'a>(
             package_builder: &mut ::eisheth::package::builder::PackageBuilder,
             #(#imported_arguments),*
         ) -> Definition {
+            let debug_info = package_builder.is_debug_info_enabled();
             let mut module = package_builder.add_module(#name_str).unwrap();
+            if debug_info {
+                module.enable_debug_info(file!(), "");
+            }
             #(#import_definitions);*
             #(#item_definitions);*
 
@@ -133,6 +137,85 @@ fn make_define_function<'a>(
     }
 }
 
+/// Modules without imports don't depend on a package, so they can be built once and attached to
+/// any number of packages.
+fn make_standalone<'a>(
+    module_name: &Ident,
+    items: impl Iterator<Item = &'a Item> + Clone,
+) -> proc_macro2::TokenStream {
+    let name_str = module_name.to_string();
+
+    let item_names = items.clone().filter_map(|x| match &x.kind {
+        grammar::ItemKind::Function(f) => Some(&f.name),
+        grammar::ItemKind::Global(g) => Some(&g.name),
+        grammar::ItemKind::GlobalInitializer(_) | grammar::ItemKind::GlobalFinalizer(_) => None,
+    });
+
+    let item_mappings = items.clone().filter_map(|x| match &x.kind {
+        grammar::ItemKind::Function(f) => {
+            let name = &f.name;
+
+            Some(quote! { #name: mapping.function(self.definition.#name) })
+        }
+        grammar::ItemKind::Global(g) => {
+            let name = &g.name;
+
+            Some(quote! { #name: mapping.global(self.definition.#name) })
+        }
+        grammar::ItemKind::GlobalInitializer(_) | grammar::ItemKind::GlobalFinalizer(_) => None,
+    });
+
+    let item_definitions = items.map(|x| match &x.kind {
+        grammar::ItemKind::Function(f) => make_function_definition(x.visibility, f),
+        grammar::ItemKind::Global(g) => make_global_declaration(x.visibility, g),
+        grammar::ItemKind::GlobalInitializer(gid) => make_global_initializer(gid),
+        grammar::ItemKind::GlobalFinalizer(gfd) => make_global_finalizer(gfd),
+    });
+
+    quote! {
+        pub struct Standalone {
+            module: ::eisheth::module::built::Module,
+            definition: Definition,
+        }
+
+        impl Standalone {
+            pub const fn module(&self) -> &::eisheth::module::built::Module {
+                &self.module
+            }
+
+            /// # Panics
+            /// If the package already contains a module with the same name.
+            pub fn attach(
+                &self,
+                package_builder: &mut ::eisheth::package::builder::PackageBuilder,
+            ) -> Definition {
+                let mapping = package_builder.attach_module(&self.module).unwrap();
+
+                Definition {
+                    #(#item_mappings),*
+                }
+            }
+        }
+
+        /// # Errors
+        /// Will return an error if the module fails verification.
+        pub fn define_standalone(
+        ) -> Result<Standalone, ::eisheth::module::builder::errors::ModuleBuildError> {
+            let mut module = ::eisheth::module::builder::ModuleBuilder::standalone(#name_str);
+            #(#item_definitions);*
+
+            let (_, module) = module.build()?;
+
+            Ok(Standalone {
+                module,
+                definition: Definition {
+                    #(#item_names),*
+                },
+            })
+        }
+    }
+}
+
 fn make_imported_definition_struct<'a>(
     items: impl Iterator<Item = &'a Item> + Clone,
 ) -> proc_macro2::TokenStream {
@@ -190,11 +273,21 @@ pub fn define_module_inner(tokens: TokenStream) -> TokenStream {
             .flat_map(|x| x.imports.iter()),
     );
     let imported_defintion_struct = make_imported_definition_struct(content.items.iter());
+    let standalone = if content
+        .imported_modules
+        .as_ref()
+        .is_none_or(|x| x.imports.is_empty())
+    {
+        make_standalone(&content.name, content.items.iter())
+    } else {
+        quote! {}
+    };
 
     quote! {
         #definition_struct
         #define_function
         #imported_defintion_struct
+        #standalone
     }
     .into()
 }
@@ -0,0 +1,7 @@
+fn main() {
+    println!("cargo::rerun-if-changed=src/jit/guard.c");
+
+    cc::Build::new()
+        .file("src/jit/guard.c")
+        .compile("eisheth_guard");
+}
@@ -2,6 +2,7 @@ use std::{
     cell::RefCell,
     ffi::{CStr, c_void},
     fmt::Display,
+    sync::Arc,
 };
 
 use llvm_sys::{
@@ -11,11 +12,11 @@ use llvm_sys::{
 };
 
 thread_local! {
-    pub static DIAGNOSTIC_HANDLER: DiagnosticHandler = const {DiagnosticHandler::new()};
+    pub(crate) static DIAGNOSTIC_HANDLER: DiagnosticHandler = const {DiagnosticHandler::new()};
 }
 
-#[derive(Debug)]
-enum DiagnosticSeverity {
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DiagnosticSeverity {
     Error,
     Warning,
     Remark,
@@ -48,26 +49,159 @@ impl Display for DiagnosticSeverity {
     }
 }
 
-#[derive(Debug)]
+/// The place in the source the diagnostic refers to, available when the IR has debug locations.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DiagnosticLocation {
+    pub file: String,
+    pub line: u32,
+    pub column: u32,
+}
+
+impl Display for DiagnosticLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.line, self.column)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Diagnostic {
     severity: DiagnosticSeverity,
     message: String,
+    module: Option<String>,
+    function: Option<String>,
+    location: Option<DiagnosticLocation>,
+}
+
+impl Diagnostic {
+    fn parse(severity: DiagnosticSeverity, description: &str, module: Option<String>) -> Self {
+        let (location, message) = split_location(description);
+        let function = find_function(message);
+
+        Self {
+            severity,
+            message: message.to_string(),
+            module,
+            function,
+            location,
+        }
+    }
+
+    #[must_use]
+    pub const fn severity(&self) -> DiagnosticSeverity {
+        self.severity
+    }
+
+    /// The description of the diagnostic, without the location.
+    #[must_use]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The module that was being verified, optimized or linked when LLVM reported the diagnostic.
+    #[must_use]
+    pub fn module(&self) -> Option<&str> {
+        self.module.as_deref()
+    }
+
+    /// The function the diagnostic is about, if LLVM mentions it in the description.
+    #[must_use]
+    pub fn function(&self) -> Option<&str> {
+        self.function.as_deref()
+    }
+
+    #[must_use]
+    pub const fn location(&self) -> Option<&DiagnosticLocation> {
+        self.location.as_ref()
+    }
 }
 
 impl Display for Diagnostic {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}: {}", self.severity, self.message)
+        write!(f, "{}: ", self.severity)?;
+
+        if let Some(module) = &self.module {
+            write!(f, "[{module}] ")?;
+        }
+
+        if let Some(location) = &self.location {
+            write!(f, "{location}: ")?;
+        }
+
+        write!(f, "{}", self.message)
     }
 }
 
-pub struct DiagnosticHandler {
+/// LLVM prefixes the descriptions of diagnostics with debug locations with `file:line:column: `,
+/// or `<unknown>:0:0: ` when there is no debug location.
+fn split_location(description: &str) -> (Option<DiagnosticLocation>, &str) {
+    let parse = || {
+        let mut parts = description.splitn(4, ':');
+        let file = parts.next()?;
+        let line = parts.next()?.parse().ok()?;
+        let column = parts.next()?.parse().ok()?;
+        let message = parts.next()?.strip_prefix(' ')?;
+
+        Some((file, line, column, message))
+    };
+
+    match parse() {
+        Some(("<unknown>", _, _, message)) => (None, message),
+        Some((file, line, column, message)) => (
+            Some(DiagnosticLocation {
+                file: file.to_string(),
+                line,
+                column,
+            }),
+            message,
+        ),
+        None => (None, description),
+    }
+}
+
+/// Finds the function in descriptions like `in function foo void (): ...` or
+/// `... in function 'foo'`.
+pub(crate) fn find_function(message: &str) -> Option<String> {
+    let (_, rest) = message.split_once("in function ")?;
+    let rest = rest.strip_prefix('\'').unwrap_or(rest);
+    let name: String = rest
+        .chars()
+        .take_while(|x| x.is_alphanumeric() || matches!(x, '_' | '.' | '$'))
+        .collect();
+
+    (!name.is_empty()).then_some(name)
+}
+
+/// Receives the diagnostics reported by LLVM.
+///
+/// Useful to show the warnings and remarks, which are otherwise only reported along with errors.
+/// Diagnostics can be reported from the threads building the package, so the sink has to be
+/// thread-safe.
+///
+/// The sink gets the diagnostics in addition to the thread's own handler, rather than instead of
+/// it, as the handler still collects them into the errors they belong to, e.g.
+/// [`crate::module::builder::errors::ModuleBuildError`].
+pub trait DiagnosticSink: Send + Sync {
+    fn report(&self, diagnostic: &Diagnostic);
+}
+
+impl<T: Fn(&Diagnostic) + Send + Sync> DiagnosticSink for T {
+    fn report(&self, diagnostic: &Diagnostic) {
+        self(diagnostic);
+    }
+}
+
+pub(crate) struct DiagnosticHandler {
     diagnostics: RefCell<Vec<Diagnostic>>,
+    module: RefCell<Option<String>>,
+    sink: RefCell<Option<Arc<dyn DiagnosticSink>>>,
 }
 
 impl DiagnosticHandler {
     const fn new() -> Self {
         Self {
             diagnostics: RefCell::new(vec![]),
+            module: RefCell::new(None),
+            sink: RefCell::new(None),
         }
     }
 
@@ -76,6 +210,37 @@ impl DiagnosticHandler {
     }
 }
 
+/// Attributes the diagnostics reported on this thread to the module, and forwards them to the
+/// sink, until dropped. Diagnostics collected before the scope was created are set aside, so
+/// [`DiagnosticHandler::take_diagnostics`] only returns the ones from within the scope.
+pub(crate) struct DiagnosticScope {
+    diagnostics: Vec<Diagnostic>,
+    module: Option<String>,
+    sink: Option<Arc<dyn DiagnosticSink>>,
+}
+
+impl DiagnosticScope {
+    pub(crate) fn new(module: &str, sink: Option<Arc<dyn DiagnosticSink>>) -> Self {
+        DIAGNOSTIC_HANDLER.with(|handler| Self {
+            diagnostics: handler.diagnostics.take(),
+            module: handler.module.replace(Some(module.to_string())),
+            sink: handler.sink.replace(sink),
+        })
+    }
+}
+
+impl Drop for DiagnosticScope {
+    fn drop(&mut self) {
+        DIAGNOSTIC_HANDLER.with(|handler| {
+            handler
+                .diagnostics
+                .replace(std::mem::take(&mut self.diagnostics));
+            handler.module.replace(self.module.take());
+            handler.sink.replace(self.sink.take());
+        });
+    }
+}
+
 pub(super) extern "C" fn handle_diagnostic(
     diagnostic_info: LLVMDiagnosticInfoRef,
     _context: *mut c_void,
@@ -85,16 +250,23 @@ pub(super) extern "C" fn handle_diagnostic(
     // SAFETY: LLVM will always call this with a valid pointer
     let severity = unsafe { LLVMGetDiagInfoSeverity(diagnostic_info) };
 
-    let diagnostic = Diagnostic {
-        // SAFETY: We just received the pointer from a function that returns a C-string
-        message: unsafe { CStr::from_ptr(message).to_str().unwrap().to_string() },
-        severity: severity.into(),
-    };
+    // SAFETY: We just received the pointer from a function that returns a C-string
+    let description = unsafe { CStr::from_ptr(message).to_string_lossy().into_owned() };
 
     // SAFETY: We just received the message, copied the contents and keep no references
     unsafe { LLVMDisposeMessage(message) };
 
     DIAGNOSTIC_HANDLER.with(|handler| {
+        let diagnostic = Diagnostic::parse(
+            severity.into(),
+            &description,
+            handler.module.borrow().clone(),
+        );
+
+        if let Some(sink) = handler.sink.borrow().as_ref() {
+            sink.report(&diagnostic);
+        }
+
         handler.diagnostics.borrow_mut().push(diagnostic);
     });
 }
@@ -8,13 +8,15 @@ use llvm_sys::{
 use crate::context::diagnostic::handle_diagnostic;
 
 thread_local! {
-    pub static LLVM_CONTEXT: Context = Context::new();
+    pub(crate) static LLVM_CONTEXT: Context = Context::new();
 }
 
-pub struct Context(LLVMContextRef);
+pub(crate) struct Context(LLVMContextRef);
 
 impl Context {
-    fn new() -> Self {
+    /// Creates a context which is not shared with anything else, for use on threads other than
+    /// the one building the modules, or by objects that have to be independent of the thread.
+    pub(crate) fn new() -> Self {
         // SAFETY: There are no documented global state requirements for this function, nor ways to
         // fail
         let context = unsafe { LLVMContextCreate() };
@@ -0,0 +1,385 @@
+use std::ffi::c_char;
+
+use llvm_sys::{
+    LLVMModuleFlagBehavior,
+    core::{LLVMAddModuleFlag, LLVMConstInt, LLVMInt32TypeInContext, LLVMValueAsMetadata},
+    debuginfo::{
+        LLVMCreateDIBuilder, LLVMDIBuilderCreateAutoVariable, LLVMDIBuilderCreateBasicType,
+        LLVMDIBuilderCreateCompileUnit, LLVMDIBuilderCreateDebugLocation,
+        LLVMDIBuilderCreateExpression, LLVMDIBuilderCreateFile, LLVMDIBuilderCreateFunction,
+        LLVMDIBuilderCreateParameterVariable, LLVMDIBuilderCreatePointerType,
+        LLVMDIBuilderCreateSubroutineType, LLVMDIBuilderFinalize,
+        LLVMDIBuilderInsertDbgValueRecordAtEnd, LLVMDIBuilderInsertDeclareRecordAtEnd,
+        LLVMDIFlagZero, LLVMDWARFEmissionKind, LLVMDWARFSourceLanguage, LLVMDebugMetadataVersion,
+        LLVMDisposeDIBuilder, LLVMSetSubprogram,
+    },
+    prelude::{LLVMBasicBlockRef, LLVMDIBuilderRef, LLVMMetadataRef, LLVMModuleRef, LLVMValueRef},
+};
+
+use crate::context::LLVM_CONTEXT;
+
+const DWARF_VERSION: u64 = 4;
+
+/// How the debugger should interpret the bits of a [`DebugType`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DebugTypeEncoding {
+    Address,
+    Boolean,
+    Float,
+    Signed,
+    Unsigned,
+}
+
+impl DebugTypeEncoding {
+    /// The `DW_ATE_*` constant of the encoding.
+    const fn as_dwarf(self) -> u32 {
+        match self {
+            Self::Address => 0x1,
+            Self::Boolean => 0x2,
+            Self::Float => 0x4,
+            Self::Signed => 0x5,
+            Self::Unsigned => 0x7,
+        }
+    }
+}
+
+/// A type as seen by the debugger. Created by the module, and empty if the module has no debug
+/// info, so the same code can build modules with and without it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DebugType(Option<LLVMMetadataRef>);
+
+impl DebugType {
+    pub(crate) const fn empty() -> Self {
+        Self(None)
+    }
+}
+
+/// A local variable or an argument of a function, as seen by the debugger. Empty if the function
+/// has no debug info.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DebugVariable {
+    variable: Option<LLVMMetadataRef>,
+    line: u32,
+}
+
+impl DebugVariable {
+    pub(crate) const fn empty() -> Self {
+        Self {
+            variable: None,
+            line: 0,
+        }
+    }
+}
+
+/// The debug info of a function, which is the scope of its instructions and variables.
+#[derive(Clone, Copy)]
+pub(crate) struct Subprogram {
+    reference: LLVMMetadataRef,
+    line: u32,
+}
+
+impl Subprogram {
+    /// # Safety
+    /// The subprogram must be valid.
+    pub(crate) unsafe fn location(self, line: u32, column: u32) -> LLVMMetadataRef {
+        LLVM_CONTEXT.with(|context| {
+            // SAFETY: The context is valid for the thread, the caller guarantees the subprogram
+            // is valid
+            unsafe {
+                LLVMDIBuilderCreateDebugLocation(
+                    context.as_llvm_ref(),
+                    line,
+                    column,
+                    self.reference,
+                    std::ptr::null_mut(),
+                )
+            }
+        })
+    }
+
+    pub(crate) const fn line(self) -> u32 {
+        self.line
+    }
+}
+
+/// Owns the LLVM debug info builder of a module, along with its compile unit.
+pub(crate) struct DebugInfoBuilder {
+    builder: LLVMDIBuilderRef,
+    file: LLVMMetadataRef,
+    compile_unit: LLVMMetadataRef,
+}
+
+impl DebugInfoBuilder {
+    /// Creates the compile unit, and marks the module as containing debug info.
+    ///
+    /// # Safety
+    /// The module must be valid, and outlive the builder.
+    pub(crate) unsafe fn new(module: LLVMModuleRef, file: &str, directory: &str) -> Self {
+        let producer = concat!("eisheth ", env!("CARGO_PKG_VERSION"));
+
+        // SAFETY: The caller guarantees the module is valid, all the strings are passed along
+        // with their lengths, and LLVM copies them
+        unsafe {
+            let builder = LLVMCreateDIBuilder(module);
+            let file = LLVMDIBuilderCreateFile(
+                builder,
+                file.as_ptr().cast::<c_char>(),
+                file.len(),
+                directory.as_ptr().cast::<c_char>(),
+                directory.len(),
+            );
+            let compile_unit = LLVMDIBuilderCreateCompileUnit(
+                builder,
+                LLVMDWARFSourceLanguage::LLVMDWARFSourceLanguageC,
+                file,
+                producer.as_ptr().cast::<c_char>(),
+                producer.len(),
+                0,
+                c"".as_ptr(),
+                0,
+                0,
+                c"".as_ptr(),
+                0,
+                LLVMDWARFEmissionKind::LLVMDWARFEmissionKindFull,
+                0,
+                0,
+                0,
+                c"".as_ptr(),
+                0,
+                c"".as_ptr(),
+                0,
+            );
+
+            add_module_flag(
+                module,
+                "Debug Info Version",
+                u64::from(LLVMDebugMetadataVersion()),
+            );
+            add_module_flag(module, "Dwarf Version", DWARF_VERSION);
+
+            Self {
+                builder,
+                file,
+                compile_unit,
+            }
+        }
+    }
+
+    /// # Safety
+    /// The function must be valid, and belong to the module of the builder.
+    pub(crate) unsafe fn attach_subprogram(
+        &self,
+        function: LLVMValueRef,
+        name: &str,
+        line: u32,
+        is_local: bool,
+    ) -> Subprogram {
+        // SAFETY: The builder and the file are valid for as long as self is, the caller
+        // guarantees the function is valid, and the name is passed along with its length
+        let reference = unsafe {
+            let r#type = LLVMDIBuilderCreateSubroutineType(
+                self.builder,
+                self.file,
+                std::ptr::null_mut(),
+                0,
+                LLVMDIFlagZero,
+            );
+            let reference = LLVMDIBuilderCreateFunction(
+                self.builder,
+                self.compile_unit,
+                name.as_ptr().cast::<c_char>(),
+                name.len(),
+                name.as_ptr().cast::<c_char>(),
+                name.len(),
+                self.file,
+                line,
+                r#type,
+                i32::from(is_local),
+                1,
+                line,
+                LLVMDIFlagZero,
+                0,
+            );
+            LLVMSetSubprogram(function, reference);
+
+            reference
+        };
+
+        Subprogram { reference, line }
+    }
+
+    pub(crate) fn basic_type(
+        &self,
+        name: &str,
+        size_in_bits: u64,
+        encoding: DebugTypeEncoding,
+    ) -> DebugType {
+        // SAFETY: The builder is valid for as long as self is, the name is passed along with its
+        // length
+        DebugType(Some(unsafe {
+            LLVMDIBuilderCreateBasicType(
+                self.builder,
+                name.as_ptr().cast::<c_char>(),
+                name.len(),
+                size_in_bits,
+                encoding.as_dwarf(),
+                LLVMDIFlagZero,
+            )
+        }))
+    }
+
+    pub(crate) fn pointer_type(&self, pointee: DebugType, size_in_bits: u64) -> DebugType {
+        let Some(pointee) = pointee.0 else {
+            return DebugType(None);
+        };
+
+        // SAFETY: The builder is valid for as long as self is, the pointee was created by a
+        // builder of the same context
+        DebugType(Some(unsafe {
+            LLVMDIBuilderCreatePointerType(
+                self.builder,
+                pointee,
+                size_in_bits,
+                0,
+                0,
+                c"".as_ptr(),
+                0,
+            )
+        }))
+    }
+
+    /// Describes a local variable, or an argument when `argument` is set to its index.
+    ///
+    /// # Safety
+    /// The subprogram must have been created by this builder.
+    pub(crate) unsafe fn variable(
+        &self,
+        subprogram: Subprogram,
+        name: &str,
+        argument: Option<u32>,
+        r#type: DebugType,
+        line: u32,
+    ) -> DebugVariable {
+        let Some(r#type) = r#type.0 else {
+            return DebugVariable::empty();
+        };
+
+        let variable = argument.map_or_else(
+            // SAFETY: The builder and the file are valid for as long as self is, the caller
+            // guarantees the subprogram is valid, and the name is passed along with its length
+            || unsafe {
+                LLVMDIBuilderCreateAutoVariable(
+                    self.builder,
+                    subprogram.reference,
+                    name.as_ptr().cast::<c_char>(),
+                    name.len(),
+                    self.file,
+                    line,
+                    r#type,
+                    1,
+                    LLVMDIFlagZero,
+                    0,
+                )
+            },
+            // SAFETY: As above, and the arguments are numbered from 1
+            |argument| unsafe {
+                LLVMDIBuilderCreateParameterVariable(
+                    self.builder,
+                    subprogram.reference,
+                    name.as_ptr().cast::<c_char>(),
+                    name.len(),
+                    argument + 1,
+                    self.file,
+                    line,
+                    r#type,
+                    1,
+                    LLVMDIFlagZero,
+                )
+            },
+        );
+
+        DebugVariable {
+            variable: Some(variable),
+            line,
+        }
+    }
+
+    /// Tells the debugger where the variable lives, either in memory at the address of `value`
+    /// when `is_address` is set, or directly in the value.
+    ///
+    /// # Safety
+    /// The subprogram must have been created by this builder, the value and the block must be
+    /// valid and belong to the function of the subprogram.
+    pub(crate) unsafe fn describe(
+        &self,
+        subprogram: Subprogram,
+        variable: DebugVariable,
+        value: LLVMValueRef,
+        is_address: bool,
+        block: LLVMBasicBlockRef,
+    ) {
+        let Some(variable_reference) = variable.variable else {
+            return;
+        };
+
+        // SAFETY: The builder is valid for as long as self is, the caller guarantees the rest of
+        // the references are valid
+        unsafe {
+            let expression = LLVMDIBuilderCreateExpression(self.builder, std::ptr::null_mut(), 0);
+            let location = subprogram.location(variable.line, 0);
+
+            if is_address {
+                LLVMDIBuilderInsertDeclareRecordAtEnd(
+                    self.builder,
+                    value,
+                    variable_reference,
+                    expression,
+                    location,
+                    block,
+                );
+            } else {
+                LLVMDIBuilderInsertDbgValueRecordAtEnd(
+                    self.builder,
+                    value,
+                    variable_reference,
+                    expression,
+                    location,
+                    block,
+                );
+            }
+        }
+    }
+
+    /// Resolves the debug info, has to be called before the module is verified.
+    pub(crate) fn finalize(&self) {
+        // SAFETY: The builder is valid for as long as self is
+        unsafe { LLVMDIBuilderFinalize(self.builder) };
+    }
+}
+
+impl Drop for DebugInfoBuilder {
+    fn drop(&mut self) {
+        // SAFETY: We own the builder, and nothing else refers to it
+        unsafe { LLVMDisposeDIBuilder(self.builder) };
+    }
+}
+
+/// # Safety
+/// The module must be valid.
+unsafe fn add_module_flag(module: LLVMModuleRef, key: &str, value: u64) {
+    LLVM_CONTEXT.with(|context| {
+        // SAFETY: The context is valid for the thread, the caller guarantees the module is valid,
+        // and the key is passed along with its length
+        unsafe {
+            let value = LLVMConstInt(LLVMInt32TypeInContext(context.as_llvm_ref()), value, 0);
+
+            LLVMAddModuleFlag(
+                module,
+                LLVMModuleFlagBehavior::LLVMModuleFlagBehaviorWarning,
+                key.as_ptr().cast::<c_char>(),
+                key.len(),
+                LLVMValueAsMetadata(value),
+            );
+        }
+    });
+}
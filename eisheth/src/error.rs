@@ -0,0 +1,100 @@
+use std::{
+    ffi::{CString, c_char},
+    str::FromStr as _,
+};
+
+use llvm_sys::{
+    LLVMTypeKind::LLVMVoidTypeKind,
+    core::{LLVMGetTypeKind, LLVMSetValueName2, LLVMTypeOf},
+    prelude::LLVMValueRef,
+};
+use thiserror::Error;
+
+use crate::{
+    jit::{JitCallError, JitInitializationError},
+    module::{
+        bitcode::BitcodeError,
+        builder::errors::{ImportError, ModuleBuildError},
+    },
+    package::{
+        builder::{AddModuleError, PackageBuildError},
+        optimization::OptimizationError,
+    },
+    target_machine::{EmitError, builder::TargetMachineError},
+};
+
+/// Any of the errors returned by eisheth, for the callers that don't need to tell them apart.
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{0:?} cannot be converted into a C-string")]
+    InvalidName(String),
+    #[error("The function \"{0}\" is not declared in this module")]
+    UnknownFunction(String),
+    #[error("The global \"{0}\" is not declared in this module")]
+    UnknownGlobal(String),
+    #[error("The descriptor comes from a different module")]
+    DifferentModule,
+    #[error("Expected {expected} fields, got {actual}")]
+    FieldCount { expected: usize, actual: usize },
+    #[error("Expected {expected} elements, got {actual}")]
+    ElementCount { expected: usize, actual: usize },
+    #[error("Element {index} should be {expected}, got {actual}")]
+    ElementType {
+        index: usize,
+        expected: String,
+        actual: String,
+    },
+    #[error("Index {index} does not select an element of {aggregate}")]
+    ElementIndex { index: usize, aggregate: String },
+    #[error("The resolver \"{0}\" has to return a pointer")]
+    ResolverType(String),
+    #[error("Expected a constant of type {expected}, got {actual}")]
+    ConstantType { expected: String, actual: String },
+    #[error(transparent)]
+    Import(#[from] ImportError),
+    #[error(transparent)]
+    AddModule(#[from] AddModuleError),
+    #[error(transparent)]
+    ModuleBuild(#[from] Box<ModuleBuildError>),
+    #[error(transparent)]
+    PackageBuild(#[from] PackageBuildError),
+    #[error(transparent)]
+    Optimization(#[from] OptimizationError),
+    #[error(transparent)]
+    Bitcode(#[from] BitcodeError),
+    #[error(transparent)]
+    Jit(#[from] JitInitializationError),
+    #[error(transparent)]
+    JitCall(#[from] JitCallError),
+    #[error(transparent)]
+    TargetMachine(#[from] TargetMachineError),
+    #[error(transparent)]
+    Emit(#[from] EmitError),
+}
+
+impl From<ModuleBuildError> for Error {
+    fn from(value: ModuleBuildError) -> Self {
+        Self::ModuleBuild(Box::new(value))
+    }
+}
+
+pub fn to_c_string(name: &str) -> Result<CString, Error> {
+    CString::from_str(name).map_err(|_| Error::InvalidName(name.to_string()))
+}
+
+/// Names an instruction or a block. Local names don't have to be C-strings, so unlike the names of
+/// functions and globals, this never fails. Constants and void values cannot be named, so those
+/// are left as-is.
+///
+/// # Safety
+/// The value must be valid.
+pub unsafe fn set_local_name(value: LLVMValueRef, name: &str) {
+    // SAFETY: The caller guarantees the value is valid
+    if name.is_empty() || unsafe { LLVMGetTypeKind(LLVMTypeOf(value)) } == LLVMVoidTypeKind {
+        return;
+    }
+
+    // SAFETY: The caller guarantees the value is valid, the name pointer is valid for the given
+    // length, and LLVM copies it
+    unsafe { LLVMSetValueName2(value, name.as_ptr().cast::<c_char>(), name.len()) };
+}
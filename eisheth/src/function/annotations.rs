@@ -0,0 +1,155 @@
+use llvm_sys::{
+    LLVMTypeKind,
+    core::{
+        LLVMGetMDKindIDInContext, LLVMIsALoadInst, LLVMIsAStoreInst, LLVMMetadataAsValue,
+        LLVMSetMetadata,
+    },
+    prelude::LLVMValueRef,
+};
+
+use super::{
+    instruction_builder::{InstructionBuilder, Store},
+    validation::{self, ValidationErrorKind},
+};
+use crate::{
+    context::LLVM_CONTEXT,
+    metadata::{AliasScope, MemoryAnnotation, Metadata},
+    value::{DynamicValue, Value},
+};
+
+/// A load or a store, which can be annotated, see [`InstructionBuilder::annotate`].
+pub trait MemoryAccess {
+    /// The instruction, or `None` if it was invalid, and thus not built.
+    fn instruction(&self) -> Option<LLVMValueRef>;
+}
+
+impl MemoryAccess for DynamicValue {
+    fn instruction(&self) -> Option<LLVMValueRef> {
+        Some(self.as_llvm_ref())
+    }
+}
+
+impl MemoryAccess for Store {
+    fn instruction(&self) -> Option<LLVMValueRef> {
+        self.0
+    }
+}
+
+impl InstructionBuilder<'_> {
+    /// Attaches the annotation to the load or store, e.g. the result of [`Self::load`]. Invalid
+    /// annotations, like a `!range` of another type than the loaded value, are recorded in the
+    /// module, and not attached.
+    pub fn annotate<TAccess: MemoryAccess>(&self, access: &TAccess, annotation: &MemoryAnnotation) {
+        let Some(instruction) = access.instruction() else {
+            return;
+        };
+
+        // SAFETY: The instruction comes from a safe wrapper, and the annotation is checked to
+        // apply to it before being attached
+        unsafe {
+            match self.annotation_metadata(instruction, annotation) {
+                Ok((kind, metadata)) => attach(instruction, kind, metadata),
+                Err(kind) => self.report("annotation", kind),
+            }
+        }
+    }
+
+    /// # Safety
+    /// The instruction must be valid.
+    unsafe fn annotation_metadata(
+        &self,
+        instruction: LLVMValueRef,
+        annotation: &MemoryAnnotation,
+    ) -> Result<(&'static str, Metadata), ValidationErrorKind> {
+        // SAFETY: The caller guarantees the instruction is valid, the bounds of the range come
+        // from safe wrappers
+        unsafe {
+            self.check_owner(instruction)?;
+
+            let is_load = !LLVMIsALoadInst(instruction).is_null();
+            if !is_load && LLVMIsAStoreInst(instruction).is_null() {
+                return Err(ValidationErrorKind::NotAMemoryAccess);
+            }
+
+            let expect_load = |kind| {
+                if is_load {
+                    Ok(())
+                } else {
+                    Err(ValidationErrorKind::NotALoad(kind))
+                }
+            };
+
+            match annotation {
+                MemoryAnnotation::Tbaa(tag) => Ok(("tbaa", tag.metadata())),
+                MemoryAnnotation::AliasScopes(scopes) => {
+                    Ok(("alias.scope", AliasScope::list(scopes)))
+                }
+                MemoryAnnotation::NoAlias(scopes) => Ok(("noalias", AliasScope::list(scopes))),
+                MemoryAnnotation::Range { low, high } => {
+                    expect_load("range")?;
+                    validation::expect_kind(
+                        instruction,
+                        LLVMTypeKind::LLVMIntegerTypeKind,
+                        ValidationErrorKind::NotAnInteger,
+                    )?;
+
+                    for bound in [low, high] {
+                        let (loaded, bound) = (
+                            validation::type_of(instruction),
+                            validation::type_of(bound.as_llvm_ref()),
+                        );
+
+                        if loaded != bound {
+                            return Err(ValidationErrorKind::OperandTypeMismatch {
+                                left: validation::type_name(loaded),
+                                right: validation::type_name(bound),
+                            });
+                        }
+                    }
+
+                    Ok((
+                        "range",
+                        Metadata::node(&[Metadata::constant(*low), Metadata::constant(*high)]),
+                    ))
+                }
+                MemoryAnnotation::NonNull => {
+                    expect_load("nonnull")?;
+                    validation::expect_kind(
+                        instruction,
+                        LLVMTypeKind::LLVMPointerTypeKind,
+                        ValidationErrorKind::NotAPointer,
+                    )?;
+
+                    Ok(("nonnull", Metadata::node(&[])))
+                }
+                MemoryAnnotation::InvariantLoad => {
+                    expect_load("invariant.load")?;
+
+                    Ok(("invariant.load", Metadata::node(&[])))
+                }
+            }
+        }
+    }
+}
+
+/// # Safety
+/// The instruction must be valid.
+unsafe fn attach(instruction: LLVMValueRef, kind: &str, metadata: Metadata) {
+    LLVM_CONTEXT.with(|context| {
+        // SAFETY: The caller guarantees the instruction is valid, the kind is passed along with
+        // its length, and the metadata belongs to the context
+        unsafe {
+            let kind_id = LLVMGetMDKindIDInContext(
+                context.as_llvm_ref(),
+                kind.as_ptr().cast(),
+                u32::try_from(kind.len()).unwrap_or(u32::MAX),
+            );
+
+            LLVMSetMetadata(
+                instruction,
+                kind_id,
+                LLVMMetadataAsValue(context.as_llvm_ref(), metadata.as_llvm_ref()),
+            );
+        }
+    });
+}
@@ -1,11 +1,10 @@
-use std::{ffi::CString, str::FromStr};
-
-use llvm_sys::core::LLVMAppendBasicBlock;
+use llvm_sys::core::{LLVMAppendBasicBlock, LLVMBasicBlockAsValue};
 
 use super::{
     builder::FunctionBuilder,
     instruction_builder::{InstructionBuilder, TerminatorToken},
 };
+use crate::error::set_local_name;
 
 #[must_use]
 pub struct FunctionBlock<'module> {
@@ -14,12 +13,11 @@ pub struct FunctionBlock<'module> {
 }
 
 impl<'module> FunctionBlock<'module> {
-    /// # Panics
-    /// This function will panic if the name cannot be converted into a `CString`
     pub fn new(function_builder: &'module FunctionBuilder<'module>, name: &str) -> Self {
-        let name = CString::from_str(name).unwrap();
-        // SAFETY: we know the function is a valid ref and name is a valid null-terminated C-string
-        let block = unsafe { LLVMAppendBasicBlock(function_builder.as_llvm_ref(), name.as_ptr()) };
+        // SAFETY: we know the function is a valid ref and the empty name is a valid C-string
+        let block = unsafe { LLVMAppendBasicBlock(function_builder.as_llvm_ref(), c"".as_ptr()) };
+        // SAFETY: The block was just created, so it's valid
+        unsafe { set_local_name(LLVMBasicBlockAsValue(block), name) };
 
         Self {
             function_builder,
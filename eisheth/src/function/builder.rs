@@ -1,15 +1,24 @@
-use std::{ffi::CString, marker::PhantomData, str::FromStr as _};
+use std::{
+    ffi::{CStr, CString},
+    marker::PhantomData,
+    str::FromStr as _,
+};
 
 use llvm_sys::{
     LLVMLinkage,
-    core::{LLVMAddFunction, LLVMGetParam, LLVMSetLinkage},
+    core::{
+        LLVMAddFunction, LLVMDisposeMessage, LLVMGetParam, LLVMPrintValueToString, LLVMSetLinkage,
+        LLVMSetPersonalityFn,
+    },
     prelude::LLVMValueRef,
 };
 
 use super::{block::FunctionBlock, declaration::FunctionSignature};
 use crate::{
     Visibility,
-    module::{AnyModule, builder::ModuleBuilder},
+    debug_info::{DebugType, DebugVariable, Subprogram},
+    error::Error,
+    module::{AnyModule, DeclaredFunctionDescriptor, builder::ModuleBuilder},
     types::{self, Type},
     value::{ConstValue, DynamicValue},
 };
@@ -45,12 +54,27 @@ impl<'module> FunctionReference<'module> {
         // SAFETY: The reference is valid, we own it
         unsafe { ConstValue::new(self.reference) }
     }
+
+    /// The IR of just this function, or its declaration if it's not defined in the module.
+    #[must_use]
+    pub fn dump_ir(&self) -> String {
+        // SAFETY: The reference is valid for as long as the module is borrowed, LLVM returns a
+        // C-string we own, which we copy and dispose
+        unsafe {
+            let raw_string = LLVMPrintValueToString(self.reference);
+            let ir = CStr::from_ptr(raw_string).to_string_lossy().into_owned();
+            LLVMDisposeMessage(raw_string);
+
+            ir
+        }
+    }
 }
 
 pub struct FunctionBuilder<'module> {
     function: LLVMValueRef,
     r#type: types::Function,
     module: &'module ModuleBuilder,
+    subprogram: Option<Subprogram>,
 }
 
 impl<'module> FunctionBuilder<'module> {
@@ -75,10 +99,23 @@ impl<'module> FunctionBuilder<'module> {
         // SAFETY: We just created the function, and the linkage is one of the correct enum values
         unsafe { LLVMSetLinkage(function, linkage) };
 
+        let subprogram = module.debug_info().map(|debug_info| {
+            // SAFETY: We just created the function in the module of the debug info builder
+            unsafe {
+                debug_info.attach_subprogram(
+                    function,
+                    declaration.name(),
+                    declaration.line(),
+                    declaration.visibility() == Visibility::Internal,
+                )
+            }
+        });
+
         Self {
             function,
             r#type: declaration.r#type(),
             module,
+            subprogram,
         }
     }
 
@@ -103,6 +140,75 @@ impl<'module> FunctionBuilder<'module> {
         Some(unsafe { DynamicValue::new(argument) })
     }
 
+    /// # Panics
+    /// Will panic if the personality function is not declared in the module, see
+    /// [`Self::try_set_personality`].
+    pub fn set_personality(&self, personality: DeclaredFunctionDescriptor) {
+        self.try_set_personality(personality).unwrap();
+    }
+
+    /// Sets the function which decides where the unwinding stops in this function, required by
+    /// the landing pads. Usually an external function, e.g. `__gcc_personality_v0` which only runs
+    /// cleanups, see [`ModuleBuilder::declare_external_function`].
+    ///
+    /// # Errors
+    /// Will return an error if the personality function is not declared in the module.
+    pub fn try_set_personality(
+        &self,
+        personality: DeclaredFunctionDescriptor,
+    ) -> Result<(), Error> {
+        let personality = self.module.try_get_function(personality)?;
+
+        // SAFETY: Both functions come from safe wrappers, and belong to the same module
+        unsafe { LLVMSetPersonalityFn(self.function, personality.as_llvm_ref()) };
+
+        Ok(())
+    }
+
+    /// Describes a local variable of the function for the debugger, see
+    /// [`crate::function::instruction_builder::InstructionBuilder::debug_declare`]. The variable
+    /// is empty if the module has no debug info.
+    #[must_use]
+    pub fn debug_variable(&self, name: &str, r#type: DebugType, line: u32) -> DebugVariable {
+        self.describe_variable(name, None, r#type, line)
+    }
+
+    /// Describes the argument at `index` for the debugger. The variable is empty if the module has
+    /// no debug info.
+    ///
+    /// # Panics
+    /// Will panic if the argument index does not fit in a u32.
+    #[must_use]
+    pub fn debug_argument(
+        &self,
+        index: usize,
+        name: &str,
+        r#type: DebugType,
+        line: u32,
+    ) -> DebugVariable {
+        self.describe_variable(name, Some(u32::try_from(index).unwrap()), r#type, line)
+    }
+
+    fn describe_variable(
+        &self,
+        name: &str,
+        argument: Option<u32>,
+        r#type: DebugType,
+        line: u32,
+    ) -> DebugVariable {
+        match (self.module.debug_info(), self.subprogram) {
+            // SAFETY: The subprogram was created by the debug info builder of the module
+            (Some(debug_info), Some(subprogram)) => unsafe {
+                debug_info.variable(subprogram, name, argument, r#type, line)
+            },
+            _ => DebugVariable::empty(),
+        }
+    }
+
+    pub(crate) const fn subprogram(&self) -> Option<Subprogram> {
+        self.subprogram
+    }
+
     pub(crate) const fn build(self) -> LLVMValueRef {
         self.function
     }
@@ -111,6 +217,10 @@ impl<'module> FunctionBuilder<'module> {
         self.function
     }
 
+    pub(crate) const fn r#type(&self) -> types::Function {
+        self.r#type
+    }
+
     pub(crate) const fn module(&self) -> &'module ModuleBuilder {
         self.module
     }
@@ -4,6 +4,7 @@ pub struct FunctionSignature {
     name: String,
     r#type: types::Function,
     visibility: Visibility,
+    line: u32,
 }
 
 impl FunctionSignature {
@@ -12,9 +13,17 @@ impl FunctionSignature {
             name: name.into(),
             r#type,
             visibility,
+            line: 0,
         }
     }
 
+    /// Sets the line the function is defined at, for the debug info.
+    #[must_use]
+    pub const fn at_line(mut self, line: u32) -> Self {
+        self.line = line;
+        self
+    }
+
     pub(crate) fn name(&self) -> &str {
         &self.name
     }
@@ -26,4 +35,8 @@ impl FunctionSignature {
     pub(crate) const fn visibility(&self) -> Visibility {
         self.visibility
     }
+
+    pub(crate) const fn line(&self) -> u32 {
+        self.line
+    }
 }
@@ -0,0 +1,134 @@
+use llvm_sys::{
+    LLVMInlineAsmDialect,
+    core::{LLVMBuildCall2, LLVMGetInlineAsm, LLVMGetReturnType},
+};
+
+use super::instruction_builder::InstructionBuilder;
+use crate::{
+    error::set_local_name,
+    types::{self, Type},
+    value::{DynamicValue, Value, ValueReference},
+};
+
+/// The syntax the inline assembly is written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum AssemblyDialect {
+    #[default]
+    Att,
+    Intel,
+}
+
+impl AssemblyDialect {
+    const fn as_llvm(self) -> LLVMInlineAsmDialect {
+        match self {
+            Self::Att => LLVMInlineAsmDialect::LLVMInlineAsmDialectATT,
+            Self::Intel => LLVMInlineAsmDialect::LLVMInlineAsmDialectIntel,
+        }
+    }
+}
+
+/// A snippet of assembly called by [`InstructionBuilder::inline_asm`].
+///
+/// The constraints follow the LLVM syntax, e.g. `"=r,r,~{memory}"` for an output register, an
+/// input register, and clobbered memory. Operands are referred to as `$0`, `$1` and so on in the
+/// assembly, and literal dollar signs are written as `$$`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InlineAssembly {
+    assembly: String,
+    constraints: String,
+    side_effects: bool,
+    align_stack: bool,
+    dialect: AssemblyDialect,
+}
+
+impl InlineAssembly {
+    #[must_use]
+    pub fn new(assembly: &str, constraints: &str) -> Self {
+        Self {
+            assembly: assembly.to_string(),
+            constraints: constraints.to_string(),
+            side_effects: false,
+            align_stack: false,
+            dialect: AssemblyDialect::Att,
+        }
+    }
+
+    /// Keeps the assembly even if its outputs are unused, and prevents moving it around, as
+    /// needed for e.g. `pause` or reading the timestamp counter.
+    #[must_use]
+    pub const fn side_effects(mut self, enabled: bool) -> Self {
+        self.side_effects = enabled;
+        self
+    }
+
+    /// Aligns the stack before the assembly runs, for assembly that calls functions.
+    #[must_use]
+    pub const fn align_stack(mut self, enabled: bool) -> Self {
+        self.align_stack = enabled;
+        self
+    }
+
+    #[must_use]
+    pub const fn dialect(mut self, dialect: AssemblyDialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+}
+
+impl InstructionBuilder<'_> {
+    /// Runs the assembly, as if it was a function of the given type called with the arguments.
+    /// The arguments are validated against the type, while the constraints are checked by the
+    /// LLVM verifier when the module is built.
+    ///
+    /// # Panics
+    /// Will panic if there are more than `u32::MAX` arguments.
+    pub fn inline_asm(
+        &self,
+        assembly: &InlineAssembly,
+        r#type: types::Function,
+        arguments: &[&dyn ValueReference],
+        name: &str,
+    ) -> DynamicValue {
+        let function_type = r#type.as_llvm_ref();
+        let mut arguments: Vec<_> = arguments
+            .iter()
+            .map(|x| x.value(self.module()).as_llvm_ref())
+            .collect();
+
+        // SAFETY: The function type and the arguments come from safe wrappers
+        if let Err(kind) = unsafe { self.check_arguments(function_type, &arguments) } {
+            self.report("inline asm", kind);
+
+            // SAFETY: The function type comes from a safe wrapper
+            return self.placeholder(unsafe { LLVMGetReturnType(function_type) });
+        }
+
+        // SAFETY: The strings are passed along with their lengths, and the function type and the
+        // arguments were checked above
+        unsafe {
+            let asm = LLVMGetInlineAsm(
+                function_type,
+                assembly.assembly.as_ptr().cast(),
+                assembly.assembly.len(),
+                assembly.constraints.as_ptr().cast(),
+                assembly.constraints.len(),
+                i32::from(assembly.side_effects),
+                i32::from(assembly.align_stack),
+                assembly.dialect.as_llvm(),
+                0,
+            );
+
+            let result = LLVMBuildCall2(
+                self.builder(),
+                function_type,
+                asm,
+                arguments.as_mut_ptr(),
+                u32::try_from(arguments.len()).unwrap(),
+                c"".as_ptr(),
+            );
+            set_local_name(result, name);
+
+            DynamicValue::new(result)
+        }
+    }
+}
@@ -1,27 +1,43 @@
-use std::{ffi::CString, marker::PhantomData, str::FromStr};
+use std::{ffi::CStr, marker::PhantomData};
 
 use llvm_sys::{
+    LLVMTypeKind,
     core::{
-        LLVMBuildAdd, LLVMBuildArrayMalloc, LLVMBuildCall2, LLVMBuildLoad2, LLVMBuildMalloc,
-        LLVMBuildRet, LLVMBuildRetVoid, LLVMBuildStore, LLVMCreateBuilderInContext,
-        LLVMDisposeBuilder, LLVMPositionBuilderAtEnd,
+        LLVMAddClause, LLVMBuildAdd, LLVMBuildArrayMalloc, LLVMBuildCall2, LLVMBuildFreeze,
+        LLVMBuildInvoke2, LLVMBuildLandingPad, LLVMBuildLoad2, LLVMBuildMalloc, LLVMBuildResume,
+        LLVMBuildRet, LLVMBuildRetVoid, LLVMBuildStore, LLVMBuildUnreachable, LLVMCountParamTypes,
+        LLVMCreateBuilderInContext, LLVMDisposeBuilder, LLVMGetBasicBlockName, LLVMGetParamTypes,
+        LLVMGetPoison, LLVMGetReturnType, LLVMGetValueName2, LLVMPositionBuilderAtEnd,
+        LLVMSetCleanup, LLVMSetCurrentDebugLocation2, LLVMStructTypeInContext,
     },
-    prelude::LLVMBuilderRef,
+    prelude::{LLVMBasicBlockRef, LLVMBuilderRef, LLVMTypeRef, LLVMValueRef},
 };
 
-use super::{block::FunctionBlock, builder::FunctionBuilder};
+use super::{
+    block::FunctionBlock,
+    builder::FunctionBuilder,
+    validation::{self, ValidationError, ValidationErrorKind},
+};
 use crate::{
     context::LLVM_CONTEXT,
+    debug_info::DebugVariable,
+    error::set_local_name,
     module::{DeclaredFunctionDescriptor, builder::ModuleBuilder},
-    types::{OpaqueType, Type},
-    value::{ConstOrDynamicValue, DynamicValue, Value, ValueReference},
+    types::{OpaqueType, RepresentedAs, Type},
+    value::{ConstOrDynamicValue, ConstValue, DynamicValue, Value, ValueReference},
 };
 
 #[non_exhaustive]
 pub struct TerminatorToken;
 
+/// A built store, which can be annotated, see [`InstructionBuilder::annotate`]. Empty if the
+/// store was invalid, and thus not built.
+#[derive(Debug, Clone, Copy)]
+pub struct Store(pub(super) Option<LLVMValueRef>);
+
 pub struct InstructionBuilder<'module> {
     builder: LLVMBuilderRef,
+    block: LLVMBasicBlockRef,
     function_builder: &'module FunctionBuilder<'module>,
     _phantom: PhantomData<&'module FunctionBlock<'module>>,
 }
@@ -34,158 +50,687 @@ impl<'module> InstructionBuilder<'module> {
         // SAFETY: we've just constructed the builder so it's valid, the block also must be
         unsafe { LLVMPositionBuilderAtEnd(builder, block.as_llvm_ref()) };
 
-        Self {
+        let instruction_builder = Self {
             builder,
+            block: block.as_llvm_ref(),
             function_builder: block.function_builder(),
             _phantom: PhantomData,
+        };
+
+        // Functions with debug info need a location on every call that could be inlined, so the
+        // instructions start at the line of the function
+        if let Some(subprogram) = instruction_builder.function_builder.subprogram() {
+            instruction_builder.set_location(subprogram.line(), 0);
         }
+
+        instruction_builder
     }
 
-    /// # Panics
-    /// Can panic if the name cannot be converted to a `CString`
+    /// Sets the source location of the instructions built from now on. Does nothing if the
+    /// function has no debug info.
+    pub fn set_location(&self, line: u32, column: u32) {
+        let Some(subprogram) = self.function_builder.subprogram() else {
+            return;
+        };
+
+        // SAFETY: The builder is valid, and the subprogram belongs to the function being built
+        unsafe { LLVMSetCurrentDebugLocation2(self.builder, subprogram.location(line, column)) };
+    }
+
+    /// Tells the debugger the variable is stored at the `address`, usually an `alloca`. Does
+    /// nothing if the variable is empty. If the address is not a pointer, the error is recorded in
+    /// the module.
+    pub fn debug_declare<TAddress: ValueReference>(
+        &self,
+        variable: DebugVariable,
+        address: &TAddress,
+    ) {
+        let address = address.value(self.module()).as_llvm_ref();
+
+        // SAFETY: The address comes from a safe wrapper, so it's valid
+        let validation = unsafe {
+            self.check_owner(address).and_then(|()| {
+                validation::expect_kind(
+                    address,
+                    LLVMTypeKind::LLVMPointerTypeKind,
+                    ValidationErrorKind::NotAPointer,
+                )
+            })
+        };
+
+        if let Err(kind) = validation {
+            self.report("debug_declare", kind);
+
+            return;
+        }
+
+        self.describe_variable(variable, address, true);
+    }
+
+    /// Tells the debugger the variable holds the `value` from this point on. Does nothing if the
+    /// variable is empty.
+    pub fn debug_value<TValue: ValueReference>(&self, variable: DebugVariable, value: &TValue) {
+        let value = value.value(self.module()).as_llvm_ref();
+
+        // SAFETY: The value comes from a safe wrapper, so it's valid
+        if let Err(kind) = unsafe { self.check_owner(value) } {
+            self.report("debug_value", kind);
+
+            return;
+        }
+
+        self.describe_variable(variable, value, false);
+    }
+
+    fn describe_variable(&self, variable: DebugVariable, value: LLVMValueRef, is_address: bool) {
+        let (Some(debug_info), Some(subprogram)) = (
+            self.module().debug_info(),
+            self.function_builder.subprogram(),
+        ) else {
+            return;
+        };
+
+        // SAFETY: The subprogram was created by the debug info builder of the module, the value
+        // was checked to belong to this function, and the block is the one being built
+        unsafe { debug_info.describe(subprogram, variable, value, is_address, self.block) };
+    }
+
+    /// Adds two integers of the same type. If either of the operands is invalid, the error is
+    /// recorded in the module and a poison value is returned instead.
     pub fn add<TLeft: ValueReference, TRight: ValueReference>(
         &self,
         left: &TLeft,
         right: &TRight,
         name: &str,
     ) -> ConstOrDynamicValue {
-        let name = CString::from_str(name).unwrap();
+        let left = left.value(self.module()).as_llvm_ref();
+        let right = right.value(self.module()).as_llvm_ref();
+
+        // SAFETY: Both values come from safe wrappers, so they're valid
+        let validation = unsafe {
+            self.check_owner(left)
+                .and_then(|()| self.check_owner(right))
+                .and_then(|()| {
+                    validation::expect_kind(
+                        left,
+                        LLVMTypeKind::LLVMIntegerTypeKind,
+                        ValidationErrorKind::NotAnInteger,
+                    )
+                })
+                .and_then(|()| validation::same_types(left, right))
+        };
+
+        if let Err(kind) = validation {
+            self.report("add", kind);
+
+            // SAFETY: The type comes from a valid value, so the poison is valid as well
+            return unsafe { ConstOrDynamicValue::new(LLVMGetPoison(validation::type_of(left))) };
+        }
 
         // SAFETY: the builder is valid and positioned, left and right exist for duration of the
         // call, and name is a valid null-terminated C-string
-        let value = unsafe {
-            LLVMBuildAdd(
-                self.builder,
-                left.value(self.module()).as_llvm_ref(),
-                right.value(self.module()).as_llvm_ref(),
-                name.as_ptr(),
-            )
-        };
+        let value = unsafe { LLVMBuildAdd(self.builder, left, right, c"".as_ptr()) };
+        // SAFETY: The value was just created, so it's valid
+        unsafe { set_local_name(value, name) };
         // SAFETY: We know the types of the arguments, so the return type must match them
         unsafe { ConstOrDynamicValue::new(value) }
     }
 
+    /// Calls the function, which has to be defined in or imported into this module. If it's not,
+    /// or the arguments don't match the function's signature, the error is recorded in the module
+    /// and a placeholder value is returned instead.
+    ///
     /// # Panics
-    /// Can panic if the name cannot be converted to a `CString`
+    /// If there are more arguments than fit in an u32
     pub fn direct_call(
         &self,
         function: DeclaredFunctionDescriptor,
         arguments: &[&dyn ValueReference],
         name: &str,
     ) -> DynamicValue {
-        let name = CString::from_str(name).unwrap();
-        let function = self.module().get_function(function);
-        let mut arguments: Vec<_> = arguments
-            .iter()
-            .map(|x| x.value(self.module()))
-            .map(|x| x.as_llvm_ref())
-            .collect();
+        let (function_type, function, mut arguments) =
+            match self.prepare_call("call", function, arguments) {
+                Ok(call) => call,
+                Err(return_type) => return self.placeholder(return_type),
+            };
 
         // SAFETY: we ensured all the references are valid
         let result = unsafe {
             LLVMBuildCall2(
                 self.builder,
-                function.r#type().as_llvm_ref(),
-                function.as_llvm_ref(),
+                function_type,
+                function,
                 arguments.as_mut_ptr(),
                 u32::try_from(arguments.len()).unwrap(),
-                name.as_ptr(),
+                c"".as_ptr(),
             )
         };
+        // SAFETY: LLVMBuildCall2 will return a value that is valid
+        unsafe { set_local_name(result, name) };
 
         // SAFETY: LLVMBuildCall2 will return a value that is valid
         unsafe { DynamicValue::new(result) }
     }
 
+    /// Calls the function like [`Self::direct_call`], continuing in `normal` when it returns, and
+    /// in `unwind` when it unwinds, e.g. because a runtime function panicked. The `unwind` block
+    /// has to start with a [`Self::landing_pad`], and the function needs a personality, see
+    /// [`FunctionBuilder::set_personality`].
+    ///
+    /// The result can only be used in `normal` and the blocks it leads to.
+    ///
     /// # Panics
-    /// Can panic if the name cannot be converted to a `CString`
+    /// Will panic if there are more than `u32::MAX` arguments.
+    pub fn invoke(
+        &self,
+        function: DeclaredFunctionDescriptor,
+        arguments: &[&dyn ValueReference],
+        normal: &FunctionBlock,
+        unwind: &FunctionBlock,
+        name: &str,
+    ) -> (DynamicValue, TerminatorToken) {
+        let (function_type, function, mut arguments) =
+            match self.prepare_call("invoke", function, arguments) {
+                Ok(call) => call,
+                Err(return_type) => return (self.placeholder(return_type), self.unreachable()),
+            };
+
+        if let Err(kind) = self
+            .check_block(normal)
+            .and_then(|()| self.check_block(unwind))
+        {
+            self.report("invoke", kind);
+
+            // SAFETY: The function type is valid
+            let return_type = unsafe { LLVMGetReturnType(function_type) };
+
+            return (self.placeholder(return_type), self.unreachable());
+        }
+
+        // SAFETY: we ensured all the references are valid, and the blocks belong to this function
+        let result = unsafe {
+            LLVMBuildInvoke2(
+                self.builder,
+                function_type,
+                function,
+                arguments.as_mut_ptr(),
+                u32::try_from(arguments.len()).unwrap(),
+                normal.as_llvm_ref(),
+                unwind.as_llvm_ref(),
+                c"".as_ptr(),
+            )
+        };
+        // SAFETY: LLVMBuildInvoke2 will return a value that is valid
+        unsafe { set_local_name(result, name) };
+
+        // SAFETY: LLVMBuildInvoke2 will return a value that is valid
+        (unsafe { DynamicValue::new(result) }, TerminatorToken)
+    }
+
+    /// The exception being unwound, as a `{ ptr, i32 }` pair of the exception object and the
+    /// selector of the matched clause. Has to be the first instruction of the unwind block of an
+    /// [`Self::invoke`].
+    ///
+    /// With `cleanup`, the landing pad is entered for every exception, which should then be
+    /// rethrown with [`Self::resume`]. Otherwise it's only entered for the exceptions matching one
+    /// of the `catch` type infos, as the personality function interprets them, where null matches
+    /// everything.
+    ///
+    /// If a type info is not a pointer, the error is recorded in the module.
+    ///
+    /// # Panics
+    /// Will panic if there are more than `u32::MAX` clauses.
+    pub fn landing_pad(
+        &self,
+        catch: &[&dyn ValueReference],
+        cleanup: bool,
+        name: &str,
+    ) -> DynamicValue {
+        let exception_type = LLVM_CONTEXT.with(|context| {
+            let mut fields = [
+                <*mut u8>::representation().as_llvm_ref(),
+                u32::representation().as_llvm_ref(),
+            ];
+
+            // SAFETY: The context is valid for the thread, and the fields are valid types
+            unsafe {
+                LLVMStructTypeInContext(
+                    context.as_llvm_ref(),
+                    fields.as_mut_ptr(),
+                    u32::try_from(fields.len()).unwrap(),
+                    0,
+                )
+            }
+        });
+        let catch: Vec<_> = catch
+            .iter()
+            .map(|x| x.value(self.module()).as_llvm_ref())
+            .collect();
+
+        for clause in &catch {
+            // SAFETY: The clauses come from safe wrappers, so they're valid
+            let validation = unsafe {
+                self.check_owner(*clause).and_then(|()| {
+                    validation::expect_kind(
+                        *clause,
+                        LLVMTypeKind::LLVMPointerTypeKind,
+                        ValidationErrorKind::NotAPointer,
+                    )
+                })
+            };
+
+            if let Err(kind) = validation {
+                self.report("landingpad", kind);
+
+                return self.placeholder(exception_type);
+            }
+        }
+
+        // SAFETY: The builder is positioned, the type is valid, and the personality is taken from
+        // the function, so it can be null
+        let landing_pad = unsafe {
+            LLVMBuildLandingPad(
+                self.builder,
+                exception_type,
+                std::ptr::null_mut(),
+                u32::try_from(catch.len()).unwrap(),
+                c"".as_ptr(),
+            )
+        };
+
+        // SAFETY: We just created the landing pad, and the clauses are valid pointers
+        unsafe {
+            for clause in catch {
+                LLVMAddClause(landing_pad, clause);
+            }
+
+            LLVMSetCleanup(landing_pad, i32::from(cleanup));
+            set_local_name(landing_pad, name);
+        }
+
+        // SAFETY: We just created the landing pad, it's valid
+        unsafe { DynamicValue::new(landing_pad) }
+    }
+
+    /// Continues unwinding the exception of a [`Self::landing_pad`], once the cleanup is done.
+    ///
+    /// If the exception belongs to another function, the error is recorded in the module, and
+    /// the block is terminated with `unreachable` instead.
+    #[must_use]
+    pub fn resume<TException: ValueReference>(&self, exception: &TException) -> TerminatorToken {
+        let exception = exception.value(self.module()).as_llvm_ref();
+
+        // SAFETY: The exception comes from a safe wrapper
+        if let Err(kind) = unsafe { self.check_owner(exception) } {
+            self.report("resume", kind);
+
+            return self.unreachable();
+        }
+
+        // SAFETY: The builder is positioned, and the exception is valid
+        unsafe { LLVMBuildResume(self.builder, exception) };
+
+        TerminatorToken
+    }
+
+    /// The address and the `u64` length of the string, stored once per module, see
+    /// [`ModuleBuilder::define_string_constant`]. The string is followed by a null byte, which
+    /// the length does not include.
+    pub fn string_literal(&self, value: &str) -> (ConstValue, ConstValue) {
+        let string = self.module().define_string_constant(value);
+
+        (string.pointer(), (string.len() as u64).into())
+    }
+
     pub fn malloc<T: Type>(&self, r#type: T, name: &str) -> DynamicValue {
-        let name = CString::from_str(name).unwrap();
         // SAFETY: All the pointers come from wrappers ensuring their validity
-        let value = unsafe { LLVMBuildMalloc(self.builder, r#type.as_llvm_ref(), name.as_ptr()) };
+        let value = unsafe { LLVMBuildMalloc(self.builder, r#type.as_llvm_ref(), c"".as_ptr()) };
+        // SAFETY: We just crated the value, it must be valid
+        unsafe { set_local_name(value, name) };
 
         // SAFETY: We just crated the value, it must be valid
         unsafe { DynamicValue::new(value) }
     }
 
-    /// # Panics
-    /// Can panic if the name cannot be converted to a `CString`
     pub fn malloc_array<TLength: ValueReference, TValue: Type>(
         &self,
         r#type: TValue,
         length: &TLength,
         name: &str,
     ) -> DynamicValue {
-        let name = CString::from_str(name).unwrap();
+        let length = length.value(self.module()).as_llvm_ref();
+
+        // SAFETY: The length comes from a safe wrapper, so it's valid
+        let validation = unsafe {
+            self.check_owner(length).and_then(|()| {
+                validation::expect_kind(
+                    length,
+                    LLVMTypeKind::LLVMIntegerTypeKind,
+                    ValidationErrorKind::NotAnInteger,
+                )
+            })
+        };
+
+        if let Err(kind) = validation {
+            self.report("malloc", kind);
+
+            return self.placeholder(<*mut u8>::representation().as_llvm_ref());
+        }
+
         // SAFETY: All pointers come from wrappers ensuring their validity
         let value = unsafe {
-            LLVMBuildArrayMalloc(
-                self.builder,
-                r#type.as_llvm_ref(),
-                length.value(self.module()).as_llvm_ref(),
-                name.as_ptr(),
-            )
+            LLVMBuildArrayMalloc(self.builder, r#type.as_llvm_ref(), length, c"".as_ptr())
         };
+        // SAFETY: We just crated the value, the pointer is valid
+        unsafe { set_local_name(value, name) };
 
         // SAFETY: We just crated the value, the pointer is valid
         unsafe { DynamicValue::new(value) }
     }
 
+    /// Stores the value at the pointer. If either of the operands is invalid, the error is
+    /// recorded in the module and nothing is stored.
     pub fn store<TTarget: ValueReference, TValue: ValueReference>(
         &self,
         target_pointer: &TTarget,
         value: &TValue,
-    ) {
-        // SAFETY: All the pointers come from safe wrappers that ensure they're valid
-        unsafe {
-            LLVMBuildStore(
-                self.builder,
-                value.value(self.module()).as_llvm_ref(),
-                target_pointer.value(self.module()).as_llvm_ref(),
-            )
+    ) -> Store {
+        let value = value.value(self.module()).as_llvm_ref();
+        let target_pointer = target_pointer.value(self.module()).as_llvm_ref();
+
+        // SAFETY: Both values come from safe wrappers, so they're valid
+        let validation = unsafe {
+            self.check_owner(value)
+                .and_then(|()| self.check_owner(target_pointer))
+                .and_then(|()| {
+                    validation::expect_kind(
+                        target_pointer,
+                        LLVMTypeKind::LLVMPointerTypeKind,
+                        ValidationErrorKind::NotAPointer,
+                    )
+                })
         };
+
+        if let Err(kind) = validation {
+            self.report("store", kind);
+
+            return Store(None);
+        }
+
+        // SAFETY: All the pointers come from safe wrappers that ensure they're valid
+        Store(Some(unsafe {
+            LLVMBuildStore(self.builder, value, target_pointer)
+        }))
     }
 
-    /// # Panics
-    /// Will panic if the name cannpt be converted to a `CString`
     pub fn load<TPointer: ValueReference, TValue: Into<OpaqueType>>(
         &self,
         pointer: &TPointer,
         r#type: TValue,
         name: &str,
     ) -> DynamicValue {
-        let name = CString::from_str(name).unwrap();
-        // SAFETY: all the values come from safe wrappers, so the pointers must be valid
-        let result = unsafe {
-            LLVMBuildLoad2(
-                self.builder,
-                r#type.into().as_llvm_ref(),
-                pointer.value(self.module()).as_llvm_ref(),
-                name.as_ptr(),
-            )
+        let r#type = r#type.into().as_llvm_ref();
+        let pointer = pointer.value(self.module()).as_llvm_ref();
+
+        // SAFETY: The pointer comes from a safe wrapper, so it's valid
+        let validation = unsafe {
+            self.check_owner(pointer).and_then(|()| {
+                validation::expect_kind(
+                    pointer,
+                    LLVMTypeKind::LLVMPointerTypeKind,
+                    ValidationErrorKind::NotAPointer,
+                )
+            })
         };
 
+        if let Err(kind) = validation {
+            self.report("load", kind);
+
+            return self.placeholder(r#type);
+        }
+
+        // SAFETY: all the values come from safe wrappers, so the pointers must be valid
+        let result = unsafe { LLVMBuildLoad2(self.builder, r#type, pointer, c"".as_ptr()) };
+        // SAFETY: We just crated the value, it must be valid
+        unsafe { set_local_name(result, name) };
+
         // SAFETY: We just crated the value, it must be valid
         unsafe { DynamicValue::new(result) }
     }
 
+    /// If the function does not return void, the error is recorded in the module, and the block
+    /// is terminated with `unreachable` instead.
     #[must_use]
     pub fn return_void(&self) -> TerminatorToken {
+        // SAFETY: The function type comes from a safe wrapper
+        let expected = unsafe { LLVMGetReturnType(self.function_builder.r#type().as_llvm_ref()) };
+
+        // SAFETY: The return type is valid
+        if unsafe { validation::kind_of(expected) } != LLVMTypeKind::LLVMVoidTypeKind {
+            self.report(
+                "return",
+                ValidationErrorKind::ReturnType {
+                    // SAFETY: The return type is valid
+                    expected: unsafe { validation::type_name(expected) },
+                    actual: "void".to_string(),
+                },
+            );
+
+            return self.unreachable();
+        }
+
         // SAFETY: we have a valid positioned builder
         unsafe { LLVMBuildRetVoid(self.builder) };
 
         TerminatorToken
     }
 
+    /// If the type of the value does not match the function's return type, the error is recorded
+    /// in the module, and the block is terminated with `unreachable` instead.
     #[must_use]
     pub fn r#return<TValue: Value>(&self, value: TValue) -> TerminatorToken {
+        let value = value.as_llvm_ref();
+        // SAFETY: The function type comes from a safe wrapper
+        let expected = unsafe { LLVMGetReturnType(self.function_builder.r#type().as_llvm_ref()) };
+
+        // SAFETY: The value comes from a safe wrapper, and the return type is valid
+        let validation = unsafe {
+            self.check_owner(value).and_then(|()| {
+                let actual = validation::type_of(value);
+
+                if actual == expected {
+                    Ok(())
+                } else {
+                    Err(ValidationErrorKind::ReturnType {
+                        expected: validation::type_name(expected),
+                        actual: validation::type_name(actual),
+                    })
+                }
+            })
+        };
+
+        if let Err(kind) = validation {
+            self.report("return", kind);
+
+            return self.unreachable();
+        }
+
         // SAFETY: we've a valid, positioned builder and the value must exist at least for the
         // duration of the call, so we're good
-        unsafe { LLVMBuildRet(self.builder, value.as_llvm_ref()) };
+        unsafe { LLVMBuildRet(self.builder, value) };
+
+        TerminatorToken
+    }
+
+    pub(super) fn unreachable(&self) -> TerminatorToken {
+        // SAFETY: we have a valid positioned builder
+        unsafe { LLVMBuildUnreachable(self.builder) };
 
         TerminatorToken
     }
 
+    /// # Safety
+    /// The value must be valid.
+    pub(super) unsafe fn check_owner(
+        &self,
+        value: LLVMValueRef,
+    ) -> Result<(), ValidationErrorKind> {
+        // SAFETY: The caller guarantees the value is valid, the function and module come from
+        // safe wrappers
+        unsafe {
+            validation::check_owner(
+                value,
+                self.function_builder.as_llvm_ref(),
+                self.module().as_llvm_ref(),
+            )
+        }
+    }
+
+    /// Resolves the function and the arguments of a `call` or an `invoke`. Errors are recorded in
+    /// the module, and the return type of the function is returned for the placeholder.
+    fn prepare_call(
+        &self,
+        instruction: &'static str,
+        function: DeclaredFunctionDescriptor,
+        arguments: &[&dyn ValueReference],
+    ) -> Result<(LLVMTypeRef, LLVMValueRef, Vec<LLVMValueRef>), LLVMTypeRef> {
+        // SAFETY: The function type comes from a safe wrapper
+        let return_type = unsafe { LLVMGetReturnType(function.r#type().as_llvm_ref()) };
+
+        let Ok(function) = self.module().try_get_function(function) else {
+            self.report(
+                instruction,
+                ValidationErrorKind::UnknownFunction(self.module().resolve(function.name())),
+            );
+
+            return Err(return_type);
+        };
+        let function_type = function.r#type().as_llvm_ref();
+        let arguments: Vec<_> = arguments
+            .iter()
+            .map(|x| x.value(self.module()))
+            .map(|x| x.as_llvm_ref())
+            .collect();
+
+        // SAFETY: The function type and the arguments come from safe wrappers
+        if let Err(kind) = unsafe { self.check_arguments(function_type, &arguments) } {
+            self.report(instruction, kind);
+
+            return Err(return_type);
+        }
+
+        Ok((function_type, function.as_llvm_ref(), arguments))
+    }
+
+    fn check_block(&self, block: &FunctionBlock) -> Result<(), ValidationErrorKind> {
+        if block.function_builder().as_llvm_ref() == self.function_builder.as_llvm_ref() {
+            return Ok(());
+        }
+
+        // SAFETY: The block comes from a safe wrapper, LLVM returns a null-terminated string
+        // owned by it
+        let name = unsafe { CStr::from_ptr(LLVMGetBasicBlockName(block.as_llvm_ref())) };
+
+        Err(ValidationErrorKind::BlockFromAnotherFunction(
+            name.to_string_lossy().into_owned(),
+        ))
+    }
+
+    /// # Safety
+    /// The function type and the arguments must be valid.
+    pub(super) unsafe fn check_arguments(
+        &self,
+        function_type: LLVMTypeRef,
+        arguments: &[LLVMValueRef],
+    ) -> Result<(), ValidationErrorKind> {
+        for argument in arguments {
+            // SAFETY: The caller guarantees the arguments are valid
+            unsafe { self.check_owner(*argument) }?;
+        }
+
+        // SAFETY: The caller guarantees the function type is valid
+        let expected_count = unsafe { LLVMCountParamTypes(function_type) } as usize;
+
+        if expected_count != arguments.len() {
+            return Err(ValidationErrorKind::ArgumentCount {
+                expected: expected_count,
+                actual: arguments.len(),
+            });
+        }
+
+        let mut parameter_types = vec![std::ptr::null_mut(); expected_count];
+        // SAFETY: The vector has space for all the parameter types
+        unsafe { LLVMGetParamTypes(function_type, parameter_types.as_mut_ptr()) };
+
+        for (index, (argument, expected)) in arguments.iter().zip(parameter_types).enumerate() {
+            // SAFETY: The caller guarantees the arguments are valid, and the parameter types come
+            // from a valid function type
+            unsafe {
+                let actual = validation::type_of(*argument);
+
+                if actual != expected {
+                    return Err(ValidationErrorKind::ArgumentType {
+                        index,
+                        expected: validation::type_name(expected),
+                        actual: validation::type_name(actual),
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub(super) fn report(&self, instruction: &'static str, kind: ValidationErrorKind) {
+        let mut function_name_length = 0;
+
+        // SAFETY: The function and block come from safe wrappers, LLVM returns strings owned by
+        // them, of the given length for the function name, and null-terminated for the block
+        let (function, block) = unsafe {
+            let function = LLVMGetValueName2(
+                self.function_builder.as_llvm_ref(),
+                &raw mut function_name_length,
+            );
+
+            (
+                String::from_utf8_lossy(std::slice::from_raw_parts(
+                    function.cast::<u8>(),
+                    function_name_length,
+                ))
+                .into_owned(),
+                CStr::from_ptr(LLVMGetBasicBlockName(self.block))
+                    .to_string_lossy()
+                    .into_owned(),
+            )
+        };
+
+        self.module().report_validation_error(ValidationError {
+            function,
+            block,
+            instruction,
+            kind,
+        });
+    }
+
+    /// A value of the given type that stands in for the result of an invalid instruction, so the
+    /// rest of the function can still be built. Void is replaced with `i8`, as there are no values
+    /// of that type.
+    pub(super) fn placeholder(&self, r#type: LLVMTypeRef) -> DynamicValue {
+        // SAFETY: The type is valid
+        let r#type = if unsafe { validation::kind_of(r#type) } == LLVMTypeKind::LLVMVoidTypeKind {
+            u8::representation().as_llvm_ref()
+        } else {
+            r#type
+        };
+
+        // SAFETY: The builder is positioned, and freezing the poison makes it a non-constant
+        // value of the same type
+        let value = unsafe { LLVMBuildFreeze(self.builder, LLVMGetPoison(r#type), c"".as_ptr()) };
+
+        // SAFETY: We just created the value, it's valid
+        unsafe { DynamicValue::new(value) }
+    }
+
     #[must_use]
     pub const fn module(&self) -> &'module ModuleBuilder {
         self.function_builder.module()
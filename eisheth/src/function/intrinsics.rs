@@ -0,0 +1,553 @@
+use llvm_sys::{
+    LLVMTypeKind,
+    core::{
+        LLVMBuildCall2, LLVMBuildExtractValue, LLVMBuildMemCpy, LLVMBuildMemMove, LLVMBuildMemSet,
+        LLVMConstInt, LLVMGetIntrinsicDeclaration, LLVMInt1TypeInContext, LLVMIntrinsicGetType,
+        LLVMLookupIntrinsicID,
+    },
+    prelude::{LLVMTypeRef, LLVMValueRef},
+};
+
+use super::{
+    instruction_builder::{InstructionBuilder, TerminatorToken},
+    validation::{self, ValidationErrorKind},
+};
+use crate::{
+    context::LLVM_CONTEXT,
+    error::set_local_name,
+    types::RepresentedAs,
+    value::{DynamicValue, Value, ValueReference},
+};
+
+/// How the bits of an integer are interpreted by the intrinsics that care, as the integer types
+/// themselves have no sign.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Signedness {
+    Signed,
+    Unsigned,
+}
+
+impl Signedness {
+    const fn prefix(self) -> &'static str {
+        match self {
+            Self::Signed => "s",
+            Self::Unsigned => "u",
+        }
+    }
+}
+
+/// The result of an arithmetic operation checked for overflow, see
+/// [`InstructionBuilder::add_with_overflow`].
+#[derive(Debug, Clone, Copy)]
+pub struct OverflowResult {
+    /// The wrapped result of the operation.
+    pub value: DynamicValue,
+    /// An `i1`, set if the operation overflowed.
+    pub overflowed: DynamicValue,
+}
+
+/// The LLVM intrinsics. Like the other instructions, invalid operands are recorded in the module,
+/// and placeholder values are returned instead.
+impl InstructionBuilder<'_> {
+    /// Copies `length` bytes from `source` to `destination`, which must not overlap.
+    pub fn memcpy<
+        TDestination: ValueReference,
+        TSource: ValueReference,
+        TLength: ValueReference,
+    >(
+        &self,
+        destination: &TDestination,
+        source: &TSource,
+        length: &TLength,
+    ) {
+        self.transfer_memory("memcpy", destination, source, length, LLVMBuildMemCpy);
+    }
+
+    /// Copies `length` bytes from `source` to `destination`, which may overlap.
+    pub fn memmove<
+        TDestination: ValueReference,
+        TSource: ValueReference,
+        TLength: ValueReference,
+    >(
+        &self,
+        destination: &TDestination,
+        source: &TSource,
+        length: &TLength,
+    ) {
+        self.transfer_memory("memmove", destination, source, length, LLVMBuildMemMove);
+    }
+
+    /// Fills `length` bytes at `destination` with the `u8` `value`.
+    pub fn memset<TDestination: ValueReference, TValue: ValueReference, TLength: ValueReference>(
+        &self,
+        destination: &TDestination,
+        value: &TValue,
+        length: &TLength,
+    ) {
+        let destination = destination.value(self.module()).as_llvm_ref();
+        let value = value.value(self.module()).as_llvm_ref();
+        let length = length.value(self.module()).as_llvm_ref();
+
+        // SAFETY: The values come from safe wrappers, so they're valid
+        let validation = unsafe {
+            self.check_pointer(destination)
+                .and_then(|()| self.check_integer(length))
+                .and_then(|()| self.check_integer(value))
+                .and_then(|()| {
+                    validation::same_types(u8::representation().const_value(0).as_llvm_ref(), value)
+                })
+        };
+
+        if let Err(kind) = validation {
+            self.report("memset", kind);
+
+            return;
+        }
+
+        // SAFETY: The builder is positioned, and the operands were validated
+        unsafe { LLVMBuildMemSet(self.builder(), destination, value, length, 1) };
+    }
+
+    /// Adds the integers, along with whether the result overflowed.
+    pub fn add_with_overflow<TLeft: ValueReference, TRight: ValueReference>(
+        &self,
+        left: &TLeft,
+        right: &TRight,
+        signedness: Signedness,
+        name: &str,
+    ) -> OverflowResult {
+        self.with_overflow("add_with_overflow", "add", left, right, signedness, name)
+    }
+
+    /// Subtracts `right` from `left`, along with whether the result overflowed.
+    pub fn sub_with_overflow<TLeft: ValueReference, TRight: ValueReference>(
+        &self,
+        left: &TLeft,
+        right: &TRight,
+        signedness: Signedness,
+        name: &str,
+    ) -> OverflowResult {
+        self.with_overflow("sub_with_overflow", "sub", left, right, signedness, name)
+    }
+
+    /// Multiplies the integers, along with whether the result overflowed.
+    pub fn mul_with_overflow<TLeft: ValueReference, TRight: ValueReference>(
+        &self,
+        left: &TLeft,
+        right: &TRight,
+        signedness: Signedness,
+        name: &str,
+    ) -> OverflowResult {
+        self.with_overflow("mul_with_overflow", "mul", left, right, signedness, name)
+    }
+
+    /// The number of set bits.
+    pub fn count_ones<TValue: ValueReference>(&self, value: &TValue, name: &str) -> DynamicValue {
+        self.unary_integer("ctpop", value, None, name)
+    }
+
+    /// The number of zero bits before the most significant set bit, the bit width for zero.
+    pub fn leading_zeros<TValue: ValueReference>(
+        &self,
+        value: &TValue,
+        name: &str,
+    ) -> DynamicValue {
+        self.unary_integer("ctlz", value, Some(false), name)
+    }
+
+    /// The number of zero bits after the least significant set bit, the bit width for zero.
+    pub fn trailing_zeros<TValue: ValueReference>(
+        &self,
+        value: &TValue,
+        name: &str,
+    ) -> DynamicValue {
+        self.unary_integer("cttz", value, Some(false), name)
+    }
+
+    /// The absolute value of the signed integer, the minimum value stays as it is.
+    pub fn abs<TValue: ValueReference>(&self, value: &TValue, name: &str) -> DynamicValue {
+        self.unary_integer("abs", value, Some(false), name)
+    }
+
+    pub fn min<TLeft: ValueReference, TRight: ValueReference>(
+        &self,
+        left: &TLeft,
+        right: &TRight,
+        signedness: Signedness,
+        name: &str,
+    ) -> DynamicValue {
+        self.binary_integer("min", left, right, signedness, name)
+    }
+
+    pub fn max<TLeft: ValueReference, TRight: ValueReference>(
+        &self,
+        left: &TLeft,
+        right: &TRight,
+        signedness: Signedness,
+        name: &str,
+    ) -> DynamicValue {
+        self.binary_integer("max", left, right, signedness, name)
+    }
+
+    /// Aborts the program, on most targets with an illegal instruction.
+    #[must_use]
+    pub fn trap(&self) -> TerminatorToken {
+        // SAFETY: The trap takes no arguments
+        let _ = unsafe { self.call_intrinsic("llvm.trap", &[], &mut [], "") };
+
+        self.unreachable()
+    }
+
+    /// Stops in the debugger, if there's one attached, and continues otherwise.
+    pub fn debug_trap(&self) {
+        // SAFETY: The debug trap takes no arguments
+        let _ = unsafe { self.call_intrinsic("llvm.debugtrap", &[], &mut [], "") };
+    }
+
+    /// Lets the optimizer assume the `i1` condition holds, the behavior is undefined if it
+    /// doesn't.
+    pub fn assume<TCondition: ValueReference>(&self, condition: &TCondition) {
+        let condition = condition.value(self.module()).as_llvm_ref();
+
+        // SAFETY: The condition comes from a safe wrapper, so it's valid
+        if let Err(kind) = unsafe { self.check_condition(condition) } {
+            self.report("assume", kind);
+
+            return;
+        }
+
+        // SAFETY: The condition was validated
+        let _ = unsafe { self.call_intrinsic("llvm.assume", &[], &mut [condition], "") };
+    }
+
+    /// The `value`, which is expected to be equal to `expected` most of the time, so the optimizer
+    /// makes that case fast.
+    pub fn expect<TValue: ValueReference, TExpected: ValueReference>(
+        &self,
+        value: &TValue,
+        expected: &TExpected,
+        name: &str,
+    ) -> DynamicValue {
+        let value = value.value(self.module()).as_llvm_ref();
+        let expected = expected.value(self.module()).as_llvm_ref();
+
+        // SAFETY: The values come from safe wrappers, so they're valid
+        let validation = unsafe {
+            self.check_integer(value)
+                .and_then(|()| self.check_owner(expected))
+                .and_then(|()| validation::same_types(value, expected))
+        };
+
+        // SAFETY: The value comes from a safe wrapper
+        let r#type = unsafe { validation::type_of(value) };
+
+        if let Err(kind) = validation {
+            self.report("expect", kind);
+
+            return self.placeholder(r#type);
+        }
+
+        // SAFETY: The operands were validated
+        unsafe { self.call_intrinsic("llvm.expect", &[r#type], &mut [value, expected], name) }
+    }
+
+    /// Marks the start of the lifetime of the `size` bytes at `pointer`, which must be an
+    /// `alloca`, so the optimizer can reuse the memory outside of it.
+    pub fn lifetime_start<TPointer: ValueReference>(&self, size: u64, pointer: &TPointer) {
+        self.lifetime("llvm.lifetime.start", "lifetime_start", size, pointer);
+    }
+
+    /// Marks the end of the lifetime of the `size` bytes at `pointer`, see
+    /// [`Self::lifetime_start`].
+    pub fn lifetime_end<TPointer: ValueReference>(&self, size: u64, pointer: &TPointer) {
+        self.lifetime("llvm.lifetime.end", "lifetime_end", size, pointer);
+    }
+
+    fn transfer_memory<
+        TDestination: ValueReference,
+        TSource: ValueReference,
+        TLength: ValueReference,
+    >(
+        &self,
+        instruction: &'static str,
+        destination: &TDestination,
+        source: &TSource,
+        length: &TLength,
+        build: unsafe extern "C" fn(
+            llvm_sys::prelude::LLVMBuilderRef,
+            LLVMValueRef,
+            u32,
+            LLVMValueRef,
+            u32,
+            LLVMValueRef,
+        ) -> LLVMValueRef,
+    ) {
+        let destination = destination.value(self.module()).as_llvm_ref();
+        let source = source.value(self.module()).as_llvm_ref();
+        let length = length.value(self.module()).as_llvm_ref();
+
+        // SAFETY: The values come from safe wrappers, so they're valid
+        let validation = unsafe {
+            self.check_pointer(destination)
+                .and_then(|()| self.check_pointer(source))
+                .and_then(|()| self.check_integer(length))
+        };
+
+        if let Err(kind) = validation {
+            self.report(instruction, kind);
+
+            return;
+        }
+
+        // SAFETY: The builder is positioned, and the operands were validated
+        unsafe { build(self.builder(), destination, 1, source, 1, length) };
+    }
+
+    fn with_overflow<TLeft: ValueReference, TRight: ValueReference>(
+        &self,
+        instruction: &'static str,
+        operation: &str,
+        left: &TLeft,
+        right: &TRight,
+        signedness: Signedness,
+        name: &str,
+    ) -> OverflowResult {
+        let left = left.value(self.module()).as_llvm_ref();
+        let right = right.value(self.module()).as_llvm_ref();
+
+        // SAFETY: The values come from safe wrappers, so they're valid
+        let validation = unsafe {
+            self.check_integer(left)
+                .and_then(|()| self.check_owner(right))
+                .and_then(|()| validation::same_types(left, right))
+        };
+
+        // SAFETY: The value comes from a safe wrapper
+        let r#type = unsafe { validation::type_of(left) };
+
+        if let Err(kind) = validation {
+            self.report(instruction, kind);
+
+            return OverflowResult {
+                value: self.placeholder(r#type),
+                overflowed: self.placeholder(bool_type()),
+            };
+        }
+
+        let intrinsic = format!("llvm.{}{operation}.with.overflow", signedness.prefix());
+
+        // SAFETY: The operands were validated, and the result is the pair of the value and the
+        // overflow flag
+        unsafe {
+            let result = self.call_intrinsic(&intrinsic, &[r#type], &mut [left, right], "");
+
+            let value =
+                LLVMBuildExtractValue(self.builder(), result.as_llvm_ref(), 0, c"".as_ptr());
+            set_local_name(value, name);
+            let overflowed =
+                LLVMBuildExtractValue(self.builder(), result.as_llvm_ref(), 1, c"".as_ptr());
+            if !name.is_empty() {
+                set_local_name(overflowed, &format!("{name}.overflowed"));
+            }
+
+            OverflowResult {
+                value: DynamicValue::new(value),
+                overflowed: DynamicValue::new(overflowed),
+            }
+        }
+    }
+
+    /// Calls the intrinsic overloaded on the type of the value, with the `i1` flag after it if
+    /// there's one.
+    fn unary_integer<TValue: ValueReference>(
+        &self,
+        intrinsic: &'static str,
+        value: &TValue,
+        flag: Option<bool>,
+        name: &str,
+    ) -> DynamicValue {
+        let value = value.value(self.module()).as_llvm_ref();
+
+        // SAFETY: The value comes from a safe wrapper, so it's valid
+        let validation = unsafe { self.check_integer(value) };
+        // SAFETY: The value comes from a safe wrapper
+        let r#type = unsafe { validation::type_of(value) };
+
+        if let Err(kind) = validation {
+            self.report(intrinsic, kind);
+
+            return self.placeholder(r#type);
+        }
+
+        let mut arguments = vec![value];
+        arguments.extend(flag.map(bool_value));
+
+        // SAFETY: The value was validated, and the flag is an i1
+        unsafe {
+            self.call_intrinsic(
+                &format!("llvm.{intrinsic}"),
+                &[r#type],
+                &mut arguments,
+                name,
+            )
+        }
+    }
+
+    fn binary_integer<TLeft: ValueReference, TRight: ValueReference>(
+        &self,
+        operation: &'static str,
+        left: &TLeft,
+        right: &TRight,
+        signedness: Signedness,
+        name: &str,
+    ) -> DynamicValue {
+        let left = left.value(self.module()).as_llvm_ref();
+        let right = right.value(self.module()).as_llvm_ref();
+
+        // SAFETY: The values come from safe wrappers, so they're valid
+        let validation = unsafe {
+            self.check_integer(left)
+                .and_then(|()| self.check_owner(right))
+                .and_then(|()| validation::same_types(left, right))
+        };
+        // SAFETY: The value comes from a safe wrapper
+        let r#type = unsafe { validation::type_of(left) };
+
+        if let Err(kind) = validation {
+            self.report(operation, kind);
+
+            return self.placeholder(r#type);
+        }
+
+        let intrinsic = format!("llvm.{}{operation}", signedness.prefix());
+
+        // SAFETY: The operands were validated
+        unsafe { self.call_intrinsic(&intrinsic, &[r#type], &mut [left, right], name) }
+    }
+
+    fn lifetime<TPointer: ValueReference>(
+        &self,
+        intrinsic: &'static str,
+        instruction: &'static str,
+        size: u64,
+        pointer: &TPointer,
+    ) {
+        let pointer = pointer.value(self.module()).as_llvm_ref();
+
+        // SAFETY: The pointer comes from a safe wrapper, so it's valid
+        if let Err(kind) = unsafe { self.check_pointer(pointer) } {
+            self.report(instruction, kind);
+
+            return;
+        }
+
+        let size = u64::representation().const_value(size).as_llvm_ref();
+
+        // SAFETY: The pointer was validated, and the size is an i64
+        let _ = unsafe {
+            self.call_intrinsic(
+                intrinsic,
+                &[validation::type_of(pointer)],
+                &mut [size, pointer],
+                "",
+            )
+        };
+    }
+
+    /// # Safety
+    /// The intrinsic must exist, and the arguments must match its signature once overloaded with
+    /// the given types.
+    unsafe fn call_intrinsic(
+        &self,
+        intrinsic: &str,
+        overloaded_types: &[LLVMTypeRef],
+        arguments: &mut [LLVMValueRef],
+        name: &str,
+    ) -> DynamicValue {
+        let mut overloaded_types = overloaded_types.to_vec();
+
+        // SAFETY: The name is passed along with its length, the types and the arguments are
+        // valid, and the caller guarantees they match the intrinsic
+        unsafe {
+            let id = LLVMLookupIntrinsicID(intrinsic.as_ptr().cast(), intrinsic.len());
+            debug_assert_ne!(0, id, "unknown intrinsic {intrinsic}");
+
+            let function = LLVMGetIntrinsicDeclaration(
+                self.module().as_llvm_ref(),
+                id,
+                overloaded_types.as_mut_ptr(),
+                overloaded_types.len(),
+            );
+            let r#type = LLVM_CONTEXT.with(|context| {
+                LLVMIntrinsicGetType(
+                    context.as_llvm_ref(),
+                    id,
+                    overloaded_types.as_mut_ptr(),
+                    overloaded_types.len(),
+                )
+            });
+
+            let result = LLVMBuildCall2(
+                self.builder(),
+                r#type,
+                function,
+                arguments.as_mut_ptr(),
+                u32::try_from(arguments.len()).unwrap_or(u32::MAX),
+                c"".as_ptr(),
+            );
+            set_local_name(result, name);
+
+            DynamicValue::new(result)
+        }
+    }
+
+    /// # Safety
+    /// The value must be valid.
+    unsafe fn check_integer(&self, value: LLVMValueRef) -> Result<(), ValidationErrorKind> {
+        // SAFETY: The caller guarantees the value is valid
+        unsafe {
+            self.check_owner(value).and_then(|()| {
+                validation::expect_kind(
+                    value,
+                    LLVMTypeKind::LLVMIntegerTypeKind,
+                    ValidationErrorKind::NotAnInteger,
+                )
+            })
+        }
+    }
+
+    /// # Safety
+    /// The value must be valid.
+    unsafe fn check_pointer(&self, value: LLVMValueRef) -> Result<(), ValidationErrorKind> {
+        // SAFETY: The caller guarantees the value is valid
+        unsafe {
+            self.check_owner(value).and_then(|()| {
+                validation::expect_kind(
+                    value,
+                    LLVMTypeKind::LLVMPointerTypeKind,
+                    ValidationErrorKind::NotAPointer,
+                )
+            })
+        }
+    }
+
+    /// # Safety
+    /// The value must be valid.
+    unsafe fn check_condition(&self, value: LLVMValueRef) -> Result<(), ValidationErrorKind> {
+        // SAFETY: The caller guarantees the value is valid, and the i1 type is always valid
+        unsafe {
+            self.check_integer(value)
+                .and_then(|()| validation::same_types(bool_value(false), value))
+        }
+    }
+}
+
+fn bool_type() -> LLVMTypeRef {
+    // SAFETY: The context is valid for the thread
+    LLVM_CONTEXT.with(|context| unsafe { LLVMInt1TypeInContext(context.as_llvm_ref()) })
+}
+
+fn bool_value(value: bool) -> LLVMValueRef {
+    // SAFETY: The type is valid
+    unsafe { LLVMConstInt(bool_type(), u64::from(value), 0) }
+}
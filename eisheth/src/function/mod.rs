@@ -1,4 +1,8 @@
+pub mod annotations;
 pub mod block;
 pub mod builder;
 pub mod declaration;
+pub mod inline_asm;
 pub mod instruction_builder;
+pub mod intrinsics;
+pub mod validation;
@@ -0,0 +1,163 @@
+use std::ffi::CStr;
+
+use llvm_sys::{
+    LLVMTypeKind,
+    core::{
+        LLVMDisposeMessage, LLVMGetBasicBlockParent, LLVMGetGlobalParent, LLVMGetInstructionParent,
+        LLVMGetParamParent, LLVMGetTypeKind, LLVMIsAArgument, LLVMIsAGlobalValue,
+        LLVMIsAInstruction, LLVMPrintTypeToString, LLVMTypeOf,
+    },
+    prelude::{LLVMModuleRef, LLVMTypeRef, LLVMValueRef},
+};
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ValidationErrorKind {
+    #[error("The operands have different types, {left} and {right}")]
+    OperandTypeMismatch { left: String, right: String },
+    #[error("Expected an integer, got {0}")]
+    NotAnInteger(String),
+    #[error("Expected a pointer, got {0}")]
+    NotAPointer(String),
+    #[error("The value belongs to a different function")]
+    ValueFromAnotherFunction,
+    #[error("The value belongs to a different module")]
+    ValueFromAnotherModule,
+    #[error("The block \"{0}\" belongs to a different function")]
+    BlockFromAnotherFunction(String),
+    #[error("The function \"{0}\" is not declared in this module")]
+    UnknownFunction(String),
+    #[error("Expected {expected} arguments, got {actual}")]
+    ArgumentCount { expected: usize, actual: usize },
+    #[error("Argument {index} should be {expected}, got {actual}")]
+    ArgumentType {
+        index: usize,
+        expected: String,
+        actual: String,
+    },
+    #[error("Expected a load or a store")]
+    NotAMemoryAccess,
+    #[error("!{0} only applies to loads")]
+    NotALoad(&'static str),
+    #[error("The function returns {expected}, got {actual}")]
+    ReturnType { expected: String, actual: String },
+}
+
+/// An instruction that would produce invalid IR, caught before the LLVM verifier.
+///
+/// The instruction is not added to the block, a placeholder value is returned instead, and the
+/// module fails to build.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("Invalid {instruction} in block \"{block}\" of function \"{function}\": {kind}")]
+pub struct ValidationError {
+    pub function: String,
+    pub block: String,
+    pub instruction: &'static str,
+    pub kind: ValidationErrorKind,
+}
+
+/// # Safety
+/// The value must be valid.
+pub(crate) unsafe fn type_of(value: LLVMValueRef) -> LLVMTypeRef {
+    // SAFETY: The caller guarantees the value is valid
+    unsafe { LLVMTypeOf(value) }
+}
+
+/// # Safety
+/// The type must be valid.
+pub(crate) unsafe fn type_name(r#type: LLVMTypeRef) -> String {
+    // SAFETY: The caller guarantees the type is valid, LLVM returns a C-string we own
+    unsafe {
+        let raw = LLVMPrintTypeToString(r#type);
+        let name = CStr::from_ptr(raw).to_string_lossy().into_owned();
+        LLVMDisposeMessage(raw);
+
+        name
+    }
+}
+
+/// # Safety
+/// The type must be valid.
+pub(crate) unsafe fn kind_of(r#type: LLVMTypeRef) -> LLVMTypeKind {
+    // SAFETY: The caller guarantees the type is valid
+    unsafe { LLVMGetTypeKind(r#type) }
+}
+
+/// # Safety
+/// The value must be valid.
+pub(crate) unsafe fn expect_kind(
+    value: LLVMValueRef,
+    kind: LLVMTypeKind,
+    error: fn(String) -> ValidationErrorKind,
+) -> Result<(), ValidationErrorKind> {
+    // SAFETY: The caller guarantees the value is valid
+    let r#type = unsafe { type_of(value) };
+
+    // SAFETY: The type comes from a valid value
+    if unsafe { kind_of(r#type) } == kind {
+        Ok(())
+    } else {
+        // SAFETY: The type comes from a valid value
+        Err(error(unsafe { type_name(r#type) }))
+    }
+}
+
+/// Checks that arguments and instructions come from the `function`, and functions and globals
+/// come from the `module`.
+///
+/// # Safety
+/// All the references must be valid.
+pub(crate) unsafe fn check_owner(
+    value: LLVMValueRef,
+    function: LLVMValueRef,
+    module: LLVMModuleRef,
+) -> Result<(), ValidationErrorKind> {
+    // SAFETY: The caller guarantees the value is valid, and each of the getters is only called
+    // for the kind of value it's meant for
+    let (value_function, value_module) = unsafe {
+        if !LLVMIsAArgument(value).is_null() {
+            (Some(LLVMGetParamParent(value)), None)
+        } else if !LLVMIsAInstruction(value).is_null() {
+            (
+                Some(LLVMGetBasicBlockParent(LLVMGetInstructionParent(value))),
+                None,
+            )
+        } else if !LLVMIsAGlobalValue(value).is_null() {
+            (None, Some(LLVMGetGlobalParent(value)))
+        } else {
+            (None, None)
+        }
+    };
+
+    if value_function.is_some_and(|x| x != function) {
+        return Err(ValidationErrorKind::ValueFromAnotherFunction);
+    }
+
+    if value_module.is_some_and(|x| x != module) {
+        return Err(ValidationErrorKind::ValueFromAnotherModule);
+    }
+
+    Ok(())
+}
+
+/// # Safety
+/// Both values must be valid.
+pub(crate) unsafe fn same_types(
+    left: LLVMValueRef,
+    right: LLVMValueRef,
+) -> Result<(), ValidationErrorKind> {
+    // SAFETY: The caller guarantees the values are valid
+    let (left, right) = unsafe { (type_of(left), type_of(right)) };
+
+    if left == right {
+        return Ok(());
+    }
+
+    // SAFETY: The types come from valid values
+    Err(unsafe {
+        ValidationErrorKind::OperandTypeMismatch {
+            left: type_name(left),
+            right: type_name(right),
+        }
+    })
+}
@@ -1,27 +1,74 @@
-use std::marker::PhantomData;
+use std::{
+    marker::PhantomData,
+    sync::atomic::{AtomicI64, Ordering},
+    time::Duration,
+};
+
+use super::{
+    guard::{self, JitCallError},
+    watchdog::Watchdog,
+};
 
 pub struct JitFunction<TFunction> {
     pointer: usize,
+    /// The address of the fuel counter, if the package was built with fuel.
+    fuel: Option<usize>,
     _phantom: PhantomData<TFunction>,
 }
 
 impl<TFunction> JitFunction<TFunction> {
-    pub(super) const fn new(pointer: usize) -> Self {
+    pub(super) const fn new(pointer: usize, fuel: Option<usize>) -> Self {
         Self {
             pointer,
+            fuel,
             _phantom: PhantomData,
         }
     }
+
+    /// # Safety
+    /// The JIT the function comes from must still exist, and the call must be safe to interrupt,
+    /// see `call_guarded`.
+    unsafe fn guarded<TReturn>(
+        &self,
+        timeout: Option<Duration>,
+        call: impl FnOnce() -> TReturn,
+    ) -> Result<TReturn, JitCallError> {
+        // SAFETY: The caller guarantees the JIT, and so the fuel counter, outlives the call
+        let watchdog = timeout.map(|timeout| unsafe { Watchdog::start(timeout, self.fuel) });
+        // SAFETY: The caller guarantees the call can be interrupted
+        let result = unsafe { guard::call(call) };
+        let timed_out = watchdog.is_some_and(Watchdog::stop);
+
+        let is_out_of_fuel = self.fuel.is_some_and(|fuel| {
+            // SAFETY: The caller guarantees the JIT, and so the fuel counter, still exists
+            unsafe { &*(fuel as *const AtomicI64) }.load(Ordering::Relaxed) < 0
+        });
+
+        // A call that returned, or failed otherwise, after the deadline still reports its result
+        match result {
+            Err(JitCallError::Fault(_)) if is_out_of_fuel && timed_out => {
+                Err(JitCallError::Timeout(timeout.unwrap_or_default()))
+            }
+            Err(JitCallError::Fault(_)) if is_out_of_fuel => Err(JitCallError::OutOfFuel),
+            result => result,
+        }
+    }
+
+    /// Where the compiled code of the function starts, e.g. to match it with profiler output.
+    #[must_use]
+    pub const fn address(&self) -> usize {
+        self.pointer
+    }
 }
 
 macro_rules! jit_function_impl {
-    ($($argument:tt),*) => {
+    ($abi:literal; $($argument:tt),*) => {
         #[allow(unused)]
         impl<
             TReturn,
             $($argument),*
         > JitFunction<
-            unsafe extern "C" fn ($($argument),*) -> TReturn
+            unsafe extern $abi fn ($($argument),*) -> TReturn
         > {
             /// # Safety
             /// The function signature on the Rust side must match the jitted function, and the
@@ -31,7 +78,7 @@ macro_rules! jit_function_impl {
                 &self,
                 $(paste::paste!([<$argument _arg>]): $argument),*
             ) -> TReturn {
-                let callable:unsafe extern "C" fn ($($argument),*) -> TReturn =
+                let callable:unsafe extern $abi fn ($($argument),*) -> TReturn =
                     // SAFETY: The caller has ensured that the signature is correct and the pointer
                     // can be safely transmuted
                     unsafe { std::mem::transmute(self.pointer) };
@@ -60,11 +107,133 @@ impl<TReturn> JitFunction<unsafe extern "C" fn() -> TReturn> {
     }
 }
 
-jit_function_impl!(TArg1);
-jit_function_impl!(TArg1, TArg2);
-jit_function_impl!(TArg1, TArg2, TArg3);
-jit_function_impl!(TArg1, TArg2, TArg3, TArg4);
-jit_function_impl!(TArg1, TArg2, TArg3, TArg4, TArg5);
-jit_function_impl!(TArg1, TArg2, TArg3, TArg4, TArg5, TArg6);
-jit_function_impl!(TArg1, TArg2, TArg3, TArg4, TArg5, TArg6, TArg7);
-jit_function_impl!(TArg1, TArg2, TArg3, TArg4, TArg5, TArg6, TArg7, TArg8);
+impl<TReturn> JitFunction<unsafe extern "C-unwind" fn() -> TReturn> {
+    /// Panics of the runtime functions unwind out of the call, and can be caught with
+    /// [`std::panic::catch_unwind`].
+    ///
+    /// # Safety
+    /// The function must be memory safe, and the signature on the Rust side must match the jitted
+    /// function's signature.
+    #[must_use]
+    pub unsafe fn call(&self) -> TReturn {
+        let callable: unsafe extern "C-unwind" fn() -> TReturn =
+            // SAFETY: The caller has ensured that the signature matches
+            unsafe { std::mem::transmute(self.pointer) };
+
+        // SAFETY: The caller ensured that the code pointed to is correct, and the arguments are as
+        // well
+        unsafe { callable() }
+    }
+}
+
+macro_rules! jit_function_guarded_impl {
+    ($($argument:tt),*) => {
+        #[allow(unused)]
+        impl<
+            TReturn,
+            $($argument),*
+        > JitFunction<
+            unsafe extern "C-unwind" fn ($($argument),*) -> TReturn
+        > {
+            /// Like `call`, but the panics of the runtime functions, and the faults of the JIT-ed
+            /// code (invalid memory accesses, division by zero and traps) are returned as errors,
+            /// instead of taking the process down. The faults are caught on an alternate signal
+            /// stack, so a stack overflow is caught as well.
+            ///
+            /// Only the faults raised by the JIT-ed code itself are recovered from, the ones
+            /// raised by the runtime functions, or by anything else, are forwarded to the handler
+            /// installed before, or kill the process. A fault jumps straight back here, skipping
+            /// the rest of the JIT-ed code without dropping anything, so whatever it was doing is
+            /// left half-done.
+            ///
+            /// # Errors
+            /// Will return an error if the function panicked, crashed, or ran out of fuel.
+            ///
+            /// # Safety
+            /// The function signature on the Rust side must match the jitted function, the JIT
+            /// must still exist, and the arguments must not need dropping. The JIT-ed code must
+            /// not be called back from a runtime function while it might fault, as the jump would
+            /// skip the frames of the runtime function as well.
+            #[allow(non_snake_case, clippy::too_many_arguments)]
+            pub unsafe fn call_guarded(
+                &self,
+                $(paste::paste!([<$argument _arg>]): $argument),*
+            ) -> Result<TReturn, JitCallError> {
+                let callable: unsafe extern "C-unwind" fn ($($argument),*) -> TReturn =
+                    // SAFETY: The caller has ensured that the signature is correct and the pointer
+                    // can be safely transmuted
+                    unsafe { std::mem::transmute(self.pointer) };
+
+                // SAFETY: The caller has ensured that the signature matches, and that the
+                // arguments and the pointed at code can be interrupted by a fault
+                unsafe { self.guarded(None, || callable($(paste::paste!([<$argument _arg>])),*)) }
+            }
+
+            /// Like `call_guarded`, but a watchdog stops the function once the `timeout`
+            /// expires, by exhausting the fuel, so the package must be built with
+            /// [`crate::package::builder::PackageBuilder::fuel`] for the function to be stopped.
+            /// Without it, the function runs until it returns. A function which returns after
+            /// the timeout, before reaching a fuel check, still returns its result.
+            ///
+            /// The fuel counter is shared by all the calls of the JIT, so a timeout stops all of
+            /// its calls running at the time, which then report [`JitCallError::OutOfFuel`], or
+            /// a timeout of their own. The fuel stays exhausted after a timeout, until it's set
+            /// again with [`crate::jit::Jit::set_fuel`].
+            ///
+            /// # Errors
+            /// Will return an error if the function timed out, panicked, crashed, or ran out of
+            /// fuel.
+            ///
+            /// # Safety
+            /// Same as `call_guarded`.
+            #[allow(non_snake_case, clippy::too_many_arguments)]
+            pub unsafe fn call_guarded_with_timeout(
+                &self,
+                timeout: Duration,
+                $(paste::paste!([<$argument _arg>]): $argument),*
+            ) -> Result<TReturn, JitCallError> {
+                let callable: unsafe extern "C-unwind" fn ($($argument),*) -> TReturn =
+                    // SAFETY: The caller has ensured that the signature is correct and the pointer
+                    // can be safely transmuted
+                    unsafe { std::mem::transmute(self.pointer) };
+
+                // SAFETY: The caller has ensured that the signature matches, and that the
+                // arguments and the pointed at code can be interrupted by a fault
+                unsafe {
+                    self.guarded(
+                        Some(timeout),
+                        || callable($(paste::paste!([<$argument _arg>])),*),
+                    )
+                }
+            }
+        }
+    };
+}
+
+jit_function_impl!("C"; TArg1);
+jit_function_impl!("C"; TArg1, TArg2);
+jit_function_impl!("C"; TArg1, TArg2, TArg3);
+jit_function_impl!("C"; TArg1, TArg2, TArg3, TArg4);
+jit_function_impl!("C"; TArg1, TArg2, TArg3, TArg4, TArg5);
+jit_function_impl!("C"; TArg1, TArg2, TArg3, TArg4, TArg5, TArg6);
+jit_function_impl!("C"; TArg1, TArg2, TArg3, TArg4, TArg5, TArg6, TArg7);
+jit_function_impl!("C"; TArg1, TArg2, TArg3, TArg4, TArg5, TArg6, TArg7, TArg8);
+
+jit_function_impl!("C-unwind"; TArg1);
+jit_function_impl!("C-unwind"; TArg1, TArg2);
+jit_function_impl!("C-unwind"; TArg1, TArg2, TArg3);
+jit_function_impl!("C-unwind"; TArg1, TArg2, TArg3, TArg4);
+jit_function_impl!("C-unwind"; TArg1, TArg2, TArg3, TArg4, TArg5);
+jit_function_impl!("C-unwind"; TArg1, TArg2, TArg3, TArg4, TArg5, TArg6);
+jit_function_impl!("C-unwind"; TArg1, TArg2, TArg3, TArg4, TArg5, TArg6, TArg7);
+jit_function_impl!("C-unwind"; TArg1, TArg2, TArg3, TArg4, TArg5, TArg6, TArg7, TArg8);
+
+jit_function_guarded_impl!();
+jit_function_guarded_impl!(TArg1);
+jit_function_guarded_impl!(TArg1, TArg2);
+jit_function_guarded_impl!(TArg1, TArg2, TArg3);
+jit_function_guarded_impl!(TArg1, TArg2, TArg3, TArg4);
+jit_function_guarded_impl!(TArg1, TArg2, TArg3, TArg4, TArg5);
+jit_function_guarded_impl!(TArg1, TArg2, TArg3, TArg4, TArg5, TArg6);
+jit_function_guarded_impl!(TArg1, TArg2, TArg3, TArg4, TArg5, TArg6, TArg7);
+jit_function_guarded_impl!(TArg1, TArg2, TArg3, TArg4, TArg5, TArg6, TArg7, TArg8);
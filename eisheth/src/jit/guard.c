@@ -0,0 +1,63 @@
+// The parts of the guarded calls which can't be written in Rust: the setjmp half, as setjmp
+// returns twice, and reading the machine context of a fault, along with flushing the instruction
+// cache of the JIT-ed code.
+
+// For the register names of the machine context
+#define _GNU_SOURCE
+
+#include <setjmp.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <ucontext.h>
+
+typedef void (*eisheth_guarded_body)(void *data);
+
+static _Thread_local sigjmp_buf *eisheth_current_jump = NULL;
+
+// Calls the body, returning 0 once it returns, or the number of the signal it was interrupted by.
+int eisheth_guarded_call(eisheth_guarded_body body, void *data) {
+    sigjmp_buf jump;
+    sigjmp_buf *previous = eisheth_current_jump;
+    int signal = sigsetjmp(jump, 1);
+
+    if (signal == 0) {
+        eisheth_current_jump = &jump;
+        body(data);
+    }
+
+    eisheth_current_jump = previous;
+
+    return signal;
+}
+
+// Jumps back to the innermost guarded call of the thread, returns only if there is none.
+void eisheth_recover(int signal) {
+    if (eisheth_current_jump != NULL) {
+        siglongjmp(*eisheth_current_jump, signal);
+    }
+}
+
+// The address of the instruction that raised the fault, from the context passed to the handler,
+// or 0 on platforms we don't know the layout of, so their faults are never recovered from.
+uintptr_t eisheth_fault_address(void *context) {
+    ucontext_t *user_context = context;
+
+#if defined(__linux__) && defined(__x86_64__)
+    return (uintptr_t)user_context->uc_mcontext.gregs[REG_RIP];
+#elif defined(__linux__) && defined(__aarch64__)
+    return (uintptr_t)user_context->uc_mcontext.pc;
+#elif defined(__APPLE__) && defined(__x86_64__)
+    return (uintptr_t)user_context->uc_mcontext->__ss.__rip;
+#elif defined(__APPLE__) && defined(__aarch64__)
+    return (uintptr_t)user_context->uc_mcontext->__ss.__pc;
+#else
+    (void)user_context;
+    return 0;
+#endif
+}
+
+// Makes the instructions written into the memory visible to the instruction cache, which only
+// matters on architectures such as ARM.
+void eisheth_clear_cache(void *start, void *end) {
+    __builtin___clear_cache((char *)start, (char *)end);
+}
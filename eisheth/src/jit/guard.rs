@@ -0,0 +1,303 @@
+use std::{
+    any::Any,
+    cell::RefCell,
+    ffi::{c_int, c_void},
+    mem::MaybeUninit,
+    panic::AssertUnwindSafe,
+    sync::{
+        OnceLock,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use thiserror::Error;
+
+/// The signals raised by faulting code: invalid memory accesses, division by zero, and traps
+/// such as the ones `unreachable` compiles to.
+const FAULT_SIGNALS: [c_int; 4] = [libc::SIGSEGV, libc::SIGBUS, libc::SIGFPE, libc::SIGILL];
+
+/// Large enough for the handler, which only jumps back to the guarded call, or forwards the
+/// signal to the handler installed before ours.
+const ALTERNATE_STACK_SIZE: usize = 64 * 1024;
+
+/// How many code sections can be guarded at once, across all the engines of the process.
+const CODE_RANGE_SLOTS: usize = 4096;
+
+/// Marks a slot taken by a range that's still being registered.
+const RESERVED: usize = usize::MAX;
+
+unsafe extern "C" {
+    fn eisheth_guarded_call(body: unsafe extern "C" fn(*mut c_void), data: *mut c_void) -> c_int;
+    fn eisheth_recover(signal: c_int);
+    fn eisheth_fault_address(context: *mut c_void) -> usize;
+}
+
+/// Why a guarded call did not return, see
+/// [`crate::jit::function::JitFunction::call_guarded`].
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum JitCallError {
+    #[error("The function panicked: {0}")]
+    Panic(String),
+    #[error("The function crashed with {}", signal_name(*.0))]
+    Fault(i32),
+    #[error("The function ran out of fuel")]
+    OutOfFuel,
+    #[error("The function did not return within {0:?}")]
+    Timeout(Duration),
+}
+
+fn signal_name(signal: c_int) -> String {
+    match signal {
+        libc::SIGSEGV => "SIGSEGV".to_string(),
+        libc::SIGBUS => "SIGBUS".to_string(),
+        libc::SIGFPE => "SIGFPE".to_string(),
+        libc::SIGILL => "SIGILL".to_string(),
+        _ => format!("signal {signal}"),
+    }
+}
+
+/// The code sections of the engines, the faults of which are recovered from. Read by the fault
+/// handler, so it only uses atomics, and an empty slot starts at 0.
+static CODE_RANGES: [CodeRange; CODE_RANGE_SLOTS] =
+    [const { CodeRange::empty() }; CODE_RANGE_SLOTS];
+
+struct CodeRange {
+    start: AtomicUsize,
+    end: AtomicUsize,
+}
+
+impl CodeRange {
+    const fn empty() -> Self {
+        Self {
+            start: AtomicUsize::new(0),
+            end: AtomicUsize::new(0),
+        }
+    }
+}
+
+/// Registers the executable code from `start` up to `end`, returns the slot to unregister it
+/// with, or `None` if all the slots are taken.
+pub(super) fn register_code(start: usize, end: usize) -> Option<usize> {
+    let slot = CODE_RANGES.iter().position(|range| {
+        range
+            .start
+            .compare_exchange(0, RESERVED, Ordering::Relaxed, Ordering::Relaxed)
+            .is_ok()
+    })?;
+
+    CODE_RANGES[slot].end.store(end, Ordering::Relaxed);
+    CODE_RANGES[slot].start.store(start, Ordering::Release);
+
+    Some(slot)
+}
+
+/// Unregisters the code before it's unmapped.
+pub(super) fn unregister_code(slot: usize) {
+    CODE_RANGES[slot].start.store(0, Ordering::Release);
+}
+
+/// Whether the address lies inside the code of an engine.
+fn is_jit_code(address: usize) -> bool {
+    CODE_RANGES.iter().any(|range| {
+        let start = range.start.load(Ordering::Acquire);
+
+        start != 0
+            && start != RESERVED
+            && (start..range.end.load(Ordering::Relaxed)).contains(&address)
+    })
+}
+
+/// The handlers that were installed before ours, which get the faults outside of guarded calls.
+static PREVIOUS_ACTIONS: OnceLock<Vec<(c_int, libc::sigaction)>> = OnceLock::new();
+
+thread_local! {
+    static ALTERNATE_STACK: RefCell<Option<AlternateStack>> = const { RefCell::new(None) };
+}
+
+/// A signal stack for the threads that don't have one yet, so the faults caused by a stack
+/// overflow can be handled too.
+struct AlternateStack {
+    /// Kept alive for as long as the stack is installed.
+    _memory: Vec<u8>,
+}
+
+impl AlternateStack {
+    fn install() -> Self {
+        let mut memory = vec![0; ALTERNATE_STACK_SIZE.max(libc::SIGSTKSZ)];
+        let stack = libc::stack_t {
+            ss_sp: memory.as_mut_ptr().cast(),
+            ss_flags: 0,
+            ss_size: memory.len(),
+        };
+
+        // SAFETY: The memory is owned by the returned value, which uninstalls the stack before
+        // freeing it
+        unsafe { libc::sigaltstack(&raw const stack, std::ptr::null_mut()) };
+
+        Self { _memory: memory }
+    }
+}
+
+impl Drop for AlternateStack {
+    fn drop(&mut self) {
+        let stack = libc::stack_t {
+            ss_sp: std::ptr::null_mut(),
+            ss_flags: libc::SS_DISABLE,
+            ss_size: 0,
+        };
+
+        // SAFETY: The stack is only dropped when the thread exits, outside of any signal handler,
+        // so nothing runs on it anymore
+        unsafe { libc::sigaltstack(&raw const stack, std::ptr::null_mut()) };
+    }
+}
+
+fn ensure_alternate_stack() {
+    let mut current = MaybeUninit::<libc::stack_t>::uninit();
+
+    // SAFETY: Only queries the current stack, into memory large enough for it
+    let current = unsafe {
+        libc::sigaltstack(std::ptr::null(), current.as_mut_ptr());
+        current.assume_init()
+    };
+
+    // The standard library sets up a stack for the threads it creates
+    if current.ss_flags & libc::SS_DISABLE == 0 {
+        return;
+    }
+
+    ALTERNATE_STACK.with(|stack| {
+        stack
+            .borrow_mut()
+            .get_or_insert_with(AlternateStack::install);
+    });
+}
+
+fn install_handlers() {
+    PREVIOUS_ACTIONS.get_or_init(|| {
+        FAULT_SIGNALS
+            .iter()
+            .map(|&signal| {
+                // SAFETY: The action is fully initialized before it's installed, the handler
+                // only calls async-signal-safe functions, and the previous action is written into
+                // memory large enough for it
+                unsafe {
+                    let mut action = MaybeUninit::<libc::sigaction>::zeroed().assume_init();
+                    action.sa_sigaction = handle_fault
+                        as extern "C" fn(c_int, *mut libc::siginfo_t, *mut c_void)
+                        as usize;
+                    action.sa_flags = libc::SA_SIGINFO | libc::SA_ONSTACK;
+                    libc::sigemptyset(&raw mut action.sa_mask);
+
+                    let mut previous = MaybeUninit::<libc::sigaction>::zeroed();
+                    libc::sigaction(signal, &raw const action, previous.as_mut_ptr());
+
+                    (signal, previous.assume_init())
+                }
+            })
+            .collect()
+    });
+}
+
+extern "C" fn handle_fault(signal: c_int, info: *mut libc::siginfo_t, context: *mut c_void) {
+    // SAFETY: The context is the one the kernel passed to the handler
+    if is_jit_code(unsafe { eisheth_fault_address(context) }) {
+        // SAFETY: When inside a guarded call, this jumps back to it, only skipping the frames of
+        // the JIT-ed code, and the ones between it and the guarded call, which don't own anything
+        // that needs dropping, see `call`
+        unsafe { eisheth_recover(signal) };
+    }
+
+    // Outside of the JIT-ed code, or of a guarded call, the fault is handled as if we weren't
+    // there
+    let previous = PREVIOUS_ACTIONS
+        .get()
+        .and_then(|actions| actions.iter().find(|(previous, _)| *previous == signal))
+        .map(|(_, action)| *action);
+
+    match previous {
+        Some(action)
+            if action.sa_sigaction != libc::SIG_DFL && action.sa_sigaction != libc::SIG_IGN =>
+        {
+            // SAFETY: The handler was installed for this signal, with the signature matching its
+            // flags
+            unsafe {
+                if action.sa_flags & libc::SA_SIGINFO == 0 {
+                    let handler: extern "C" fn(c_int) = std::mem::transmute(action.sa_sigaction);
+                    handler(signal);
+                } else {
+                    let handler: extern "C" fn(c_int, *mut libc::siginfo_t, *mut c_void) =
+                        std::mem::transmute(action.sa_sigaction);
+                    handler(signal, info, context);
+                }
+            }
+        }
+        _ => {
+            // Returning runs the faulting instruction again, which now kills the process as usual
+            // SAFETY: Resetting the action is async-signal-safe
+            unsafe { libc::signal(signal, libc::SIG_DFL) };
+        }
+    }
+}
+
+struct GuardedCall<TFunction, TReturn> {
+    function: Option<TFunction>,
+    result: Option<std::thread::Result<TReturn>>,
+}
+
+/// # Safety
+/// The data must point at a `GuardedCall` of the same types.
+unsafe extern "C" fn run_guarded<TFunction: FnOnce() -> TReturn, TReturn>(data: *mut c_void) {
+    // SAFETY: The caller guarantees the data points at a GuardedCall of these types
+    let call = unsafe { &mut *data.cast::<GuardedCall<TFunction, TReturn>>() };
+
+    if let Some(function) = call.function.take() {
+        call.result = Some(std::panic::catch_unwind(AssertUnwindSafe(function)));
+    }
+}
+
+/// Runs the function, turning the panics unwinding out of it, and the faults raised by the JIT-ed
+/// code it calls, into errors. The faults raised anywhere else, e.g. in the runtime functions,
+/// are forwarded to the handler installed before ours, or kill the process.
+///
+/// # Safety
+/// A fault jumps back here with `siglongjmp`, skipping the frames of `run_guarded`, of the
+/// function, and of the JIT-ed code without dropping anything, so none of them may own anything
+/// that needs dropping while the JIT-ed code might fault. In particular, the JIT-ed code must not
+/// be running inside a runtime function, called back from it.
+pub unsafe fn call<TFunction: FnOnce() -> TReturn, TReturn>(
+    function: TFunction,
+) -> Result<TReturn, JitCallError> {
+    install_handlers();
+    ensure_alternate_stack();
+
+    let mut call = GuardedCall {
+        function: Some(function),
+        result: None,
+    };
+
+    // SAFETY: The data points at a GuardedCall of the types run_guarded is instantiated with, and
+    // the caller guarantees jumping over the function is fine
+    let signal = unsafe {
+        eisheth_guarded_call(
+            run_guarded::<TFunction, TReturn>,
+            (&raw mut call).cast::<c_void>(),
+        )
+    };
+
+    match call.result {
+        _ if signal != 0 => Err(JitCallError::Fault(signal)),
+        Some(Ok(result)) => Ok(result),
+        Some(Err(payload)) => Err(JitCallError::Panic(panic_message(&*payload))),
+        None => unreachable!("the function either returns, panics or faults"),
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| (*message).to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "Box<dyn Any>".to_string())
+}
@@ -0,0 +1,217 @@
+use std::{
+    ffi::{CStr, c_char, c_uint, c_void},
+    ptr::NonNull,
+};
+
+use llvm_sys::{
+    execution_engine::{LLVMCreateSimpleMCJITMemoryManager, LLVMMCJITMemoryManagerRef},
+    prelude::LLVMBool,
+};
+
+use super::guard;
+
+unsafe extern "C" {
+    fn eisheth_clear_cache(start: *mut c_void, end: *mut c_void);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SectionKind {
+    Code,
+    Data,
+    ReadOnlyData,
+}
+
+struct Section {
+    start: NonNull<c_void>,
+    size: usize,
+    kind: SectionKind,
+    is_finalized: bool,
+}
+
+/// The sections the engine loads the code and data of the package into, each mapped on its own,
+/// so the fault handler can tell whether a fault was raised by JIT-ed code, see
+/// [`guard::is_jit_code`].
+#[derive(Default)]
+struct Sections {
+    sections: Vec<Section>,
+    /// The slots of the code sections registered with the fault handler.
+    code_ranges: Vec<usize>,
+}
+
+impl Sections {
+    fn allocate(&mut self, size: usize, alignment: c_uint, kind: SectionKind) -> *mut u8 {
+        let page_size = page_size();
+
+        // Mappings are aligned to pages, which is all the engine asks for in practice
+        if usize::try_from(alignment).map_or(true, |alignment| alignment > page_size) {
+            return std::ptr::null_mut();
+        }
+
+        let size = size.max(1).next_multiple_of(page_size);
+        // Placed after the previous section if possible, as the code refers to the data relative
+        // to itself, which only reaches so far
+        let hint = self.sections.last().map_or(std::ptr::null_mut(), |last| {
+            last.start.as_ptr().wrapping_byte_add(last.size)
+        });
+
+        // SAFETY: The mapping is anonymous and private, so it doesn't affect any other memory
+        let start = unsafe {
+            libc::mmap(
+                hint,
+                size,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            )
+        };
+
+        if start == libc::MAP_FAILED {
+            return std::ptr::null_mut();
+        }
+
+        let Some(start) = NonNull::new(start) else {
+            return std::ptr::null_mut();
+        };
+
+        self.sections.push(Section {
+            start,
+            size,
+            kind,
+            is_finalized: false,
+        });
+
+        start.as_ptr().cast()
+    }
+
+    /// Makes the sections loaded since the last time executable or read-only, and registers the
+    /// code with the fault handler.
+    fn finalize(&mut self) -> Result<(), &'static CStr> {
+        for section in self
+            .sections
+            .iter_mut()
+            .filter(|section| !section.is_finalized)
+        {
+            let protection = match section.kind {
+                SectionKind::Code => libc::PROT_READ | libc::PROT_EXEC,
+                SectionKind::Data => libc::PROT_READ | libc::PROT_WRITE,
+                SectionKind::ReadOnlyData => libc::PROT_READ,
+            };
+
+            // SAFETY: The section was mapped by us, and the engine is done writing into it
+            if unsafe { libc::mprotect(section.start.as_ptr(), section.size, protection) } != 0 {
+                return Err(c"the JIT memory could not be protected");
+            }
+
+            section.is_finalized = true;
+
+            if section.kind == SectionKind::Code {
+                let end = section.start.as_ptr().wrapping_byte_add(section.size);
+
+                // SAFETY: The range was just written, and is mapped for as long as the section
+                unsafe { eisheth_clear_cache(section.start.as_ptr(), end) };
+
+                // Code that doesn't fit in the registry is not guarded, its faults are forwarded
+                if let Some(slot) =
+                    guard::register_code(section.start.as_ptr() as usize, end as usize)
+                {
+                    self.code_ranges.push(slot);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for Sections {
+    fn drop(&mut self) {
+        for slot in self.code_ranges.drain(..) {
+            guard::unregister_code(slot);
+        }
+
+        for section in &self.sections {
+            // SAFETY: The engine is being disposed of, so nothing runs the code, or uses the
+            // data anymore
+            unsafe { libc::munmap(section.start.as_ptr(), section.size) };
+        }
+    }
+}
+
+fn page_size() -> usize {
+    // SAFETY: Only queries the configuration
+    usize::try_from(unsafe { libc::sysconf(libc::_SC_PAGESIZE) }).unwrap_or(4096)
+}
+
+/// Creates the memory manager for an engine, which takes ownership of it.
+pub(super) fn create() -> LLVMMCJITMemoryManagerRef {
+    let sections = Box::into_raw(Box::<Sections>::default());
+
+    // SAFETY: The callbacks match the signatures expected by LLVM, and the sections are only
+    // freed by `destroy`, once the engine is done with them
+    unsafe {
+        LLVMCreateSimpleMCJITMemoryManager(
+            sections.cast(),
+            allocate_code_section,
+            allocate_data_section,
+            finalize_memory,
+            Some(destroy),
+        )
+    }
+}
+
+/// # Safety
+/// The `sections` must be the ones created by `create`, and not destroyed yet.
+const unsafe fn sections<'a>(sections: *mut c_void) -> &'a mut Sections {
+    // SAFETY: The caller guarantees the pointer is valid, the engine only calls the memory
+    // manager from one thread at a time
+    unsafe { &mut *sections.cast::<Sections>() }
+}
+
+extern "C" fn allocate_code_section(
+    opaque: *mut c_void,
+    size: usize,
+    alignment: c_uint,
+    _section_id: c_uint,
+    _section_name: *const c_char,
+) -> *mut u8 {
+    // SAFETY: LLVM passes the pointer given to `LLVMCreateSimpleMCJITMemoryManager`
+    unsafe { sections(opaque) }.allocate(size, alignment, SectionKind::Code)
+}
+
+extern "C" fn allocate_data_section(
+    opaque: *mut c_void,
+    size: usize,
+    alignment: c_uint,
+    _section_id: c_uint,
+    _section_name: *const c_char,
+    is_read_only: LLVMBool,
+) -> *mut u8 {
+    let kind = if is_read_only == 0 {
+        SectionKind::Data
+    } else {
+        SectionKind::ReadOnlyData
+    };
+
+    // SAFETY: LLVM passes the pointer given to `LLVMCreateSimpleMCJITMemoryManager`
+    unsafe { sections(opaque) }.allocate(size, alignment, kind)
+}
+
+extern "C" fn finalize_memory(opaque: *mut c_void, error: *mut *mut c_char) -> LLVMBool {
+    // SAFETY: LLVM passes the pointer given to `LLVMCreateSimpleMCJITMemoryManager`
+    match unsafe { sections(opaque) }.finalize() {
+        Ok(()) => 0,
+        Err(message) => {
+            // SAFETY: LLVM frees the message with `free`, so it's copied with `strdup`
+            unsafe { *error = libc::strdup(message.as_ptr()) };
+
+            1
+        }
+    }
+}
+
+extern "C" fn destroy(opaque: *mut c_void) {
+    // SAFETY: LLVM passes the pointer given to `LLVMCreateSimpleMCJITMemoryManager`, and calls
+    // this once, when it's done with the memory
+    drop(unsafe { Box::from_raw(opaque.cast::<Sections>()) });
+}
@@ -1,27 +1,45 @@
 pub mod function;
+mod guard;
+mod memory;
+pub mod options;
+mod perf_map;
+mod watchdog;
 
 use std::{
     error::Error,
     ffi::{CStr, CString, c_void},
     fmt::Display,
     mem::MaybeUninit,
-    rc::Rc,
     str::FromStr,
-    sync::LazyLock,
+    sync::{
+        Arc, LazyLock,
+        atomic::{AtomicI64, Ordering},
+    },
 };
 
 use function::JitFunction;
+pub use guard::JitCallError;
 use llvm_sys::{
     core::{LLVMDisposeMessage, LLVMGetNamedFunction},
     execution_engine::{
-        LLVMAddGlobalMapping, LLVMCreateExecutionEngineForModule, LLVMDisposeExecutionEngine,
-        LLVMExecutionEngineRef, LLVMGetFunctionAddress, LLVMLinkInMCJIT, LLVMRunStaticConstructors,
-        LLVMRunStaticDestructors,
+        LLVMAddGlobalMapping, LLVMCreateMCJITCompilerForModule, LLVMDisposeExecutionEngine,
+        LLVMExecutionEngineRef, LLVMGetFunctionAddress, LLVMGetGlobalValueAddress,
+        LLVMInitializeMCJITCompilerOptions, LLVMLinkInMCJIT, LLVMMCJITCompilerOptions,
+        LLVMRunStaticConstructors, LLVMRunStaticDestructors,
     },
     target::{LLVM_InitializeNativeAsmPrinter, LLVM_InitializeNativeTarget},
 };
+use options::JitOptions;
+
+use super::{
+    context::Context,
+    global_symbol::GlobalSymbols,
+    module::DeclaredFunctionDescriptor,
+    package::{Package, fuel::FUEL_GLOBAL_NAME},
+};
 
-use super::{global_symbol::GlobalSymbols, module::DeclaredFunctionDescriptor, package::Package};
+/// The level `LLVMCreateExecutionEngineForModule` would use.
+const DEFAULT_OPTIMIZATION_LEVEL: u32 = 2;
 
 #[derive(Clone, Copy)]
 struct JITToken;
@@ -51,43 +69,77 @@ impl Error for JitInitializationError {}
 pub struct Jit {
     _token: JITToken,
     execution_engine: LLVMExecutionEngineRef,
-    symbols: Rc<GlobalSymbols>,
+    symbols: Arc<GlobalSymbols>,
+    // The engine owns the module, which is disposed in `drop`, before the context is
+    _context: Context,
 }
 
 impl Jit {
-    /// # Panics
-    /// Will panic if the execution engine cannot be created, and no message is provided.
+    /// Creates the execution engine with the default options, see [`Self::with_options`].
+    ///
     /// # Errors
-    /// Will return an error if th execution engine cannot be created.
+    /// Will return an error if the execution engine cannot be created, or a runtime function
+    /// mapping refers to a function missing from the module.
     pub fn new(package: Package) -> Result<Self, JitInitializationError> {
+        Self::with_options(package, &JitOptions::default())
+    }
+
+    /// # Errors
+    /// Will return an error if the execution engine cannot be created, a runtime function mapping
+    /// refers to a function missing from the module, or the perf map cannot be written.
+    pub fn with_options(
+        package: Package,
+        options: &JitOptions,
+    ) -> Result<Self, JitInitializationError> {
         let token = *JIT_SETUP;
         let symbols = package.symbols();
-        let module = package.into_module();
+        let (module, context) = package.into_parts();
 
         let (global_mappings, module_reference) = module.take();
 
         let execution_engine = {
             let mut engine = MaybeUninit::uninit();
             let mut error_raw = std::ptr::null_mut();
+            let mut compiler_options = MaybeUninit::<LLVMMCJITCompilerOptions>::uninit();
+
+            // SAFETY: The options are initialized by the called function, and have the size it
+            // expects
+            let mut compiler_options = unsafe {
+                LLVMInitializeMCJITCompilerOptions(
+                    compiler_options.as_mut_ptr(),
+                    size_of::<LLVMMCJITCompilerOptions>(),
+                );
+                compiler_options.assume_init()
+            };
+            compiler_options.OptLevel = DEFAULT_OPTIMIZATION_LEVEL;
+            compiler_options.NoFramePointerElim = i32::from(options.has_frame_pointers());
+            // Owned by the engine, which destroys it even if it fails to be created
+            compiler_options.MCJMM = memory::create();
 
             // SAFETY: the `module` must be correctly initialized if it exists, engine and error
-            // are initialized by the called function
+            // are initialized by the called function, and the options were initialized above
             if unsafe {
-                LLVMCreateExecutionEngineForModule(
+                LLVMCreateMCJITCompilerForModule(
                     engine.as_mut_ptr(),
                     module_reference,
+                    &raw mut compiler_options,
+                    size_of::<LLVMMCJITCompilerOptions>(),
                     &raw mut error_raw,
                 )
             } != 0
             {
-                assert!(!error_raw.is_null());
+                if error_raw.is_null() {
+                    return Err(JitInitializationError(
+                        "the execution engine could not be created".to_string(),
+                    ));
+                }
+
                 // SAFETY: We've checked the `error` is not null, so it must be a valid CStr
                 // pointer
                 let error = JitInitializationError(
                     (unsafe { CStr::from_ptr(error_raw) })
-                        .to_str()
-                        .unwrap()
-                        .to_string(),
+                        .to_string_lossy()
+                        .into_owned(),
                 );
                 // SAFETY: We're done with the string, made our copy, safe to destroy
                 unsafe { LLVMDisposeMessage(error_raw) };
@@ -100,12 +152,21 @@ impl Jit {
         };
 
         for (name, address) in global_mappings {
-            let name = CString::from_str(&name).unwrap();
             // TODO support globals other than functions?
             // SAFETY: The module_reference is valid, as it came from a safe wrapper, we just
             // crated the name so it's also a valid pointer
-            let value = unsafe { LLVMGetNamedFunction(module_reference, name.as_ptr()) };
-            assert!(!value.is_null(), "Global called {name:?} not found");
+            let value = CString::from_str(&name).map_or(std::ptr::null_mut(), |x| unsafe {
+                LLVMGetNamedFunction(module_reference, x.as_ptr())
+            });
+
+            if value.is_null() {
+                // SAFETY: The engine was just created, and none of the code ran yet
+                unsafe { LLVMDisposeExecutionEngine(execution_engine) };
+
+                return Err(JitInitializationError(format!(
+                    "global called {name:?} not found"
+                )));
+            }
 
             // SAFETY: The caller must ensure that the address is correct, we just got the value,
             // so it's valid (and type-matching is up to the caller). The execution_engine is
@@ -116,15 +177,25 @@ impl Jit {
         // SAFETY: We just initialized the engine, it's valid and ready to run static constructors
         unsafe { LLVMRunStaticConstructors(execution_engine) };
 
-        Ok(Self {
+        let jit = Self {
             _token: token,
             execution_engine,
             symbols,
-        })
+            _context: context,
+        };
+
+        if options.has_perf_map() {
+            // SAFETY: The engine owns the module, and both are valid until the Jit is dropped
+            unsafe { perf_map::write(jit.execution_engine, module_reference) }.map_err(
+                |error| JitInitializationError(format!("failed to write the perf map: {error}")),
+            )?;
+        }
+
+        Ok(jit)
     }
 
     /// # Panics
-    /// If the name of the function cannot be converted to a `CString`
+    /// If the function is not in the package, see [`Self::try_get_function`].
     /// # Safety
     /// The caller must ensure that the signature on the Rust side matches the signature of the
     /// defined function, and that the function itself is memory-safe.
@@ -133,15 +204,86 @@ impl Jit {
         &self,
         id: DeclaredFunctionDescriptor,
     ) -> JitFunction<TFunction> {
-        let name = CString::from_str(&self.symbols.resolve(id.name())).unwrap();
-
-        // SAFETY: We have a valid `execution_engine`, valid null-terminated name. The function
-        // must exist. We transmute the pointer into a generic fn() one, which must be further
-        // transmuted by the callee to match the function signature
-        unsafe {
-            let function_address = LLVMGetFunctionAddress(self.execution_engine, name.as_ptr());
-            JitFunction::new(usize::try_from(function_address).unwrap())
+        // SAFETY: The caller upholds the same requirements
+        unsafe { self.try_get_function(id) }.unwrap()
+    }
+
+    /// # Errors
+    /// Will return an error if the function is not in the package.
+    /// # Safety
+    /// The caller must ensure that the signature on the Rust side matches the signature of the
+    /// defined function, and that the function itself is memory-safe.
+    pub unsafe fn try_get_function<TFunction>(
+        &self,
+        id: DeclaredFunctionDescriptor,
+    ) -> Result<JitFunction<TFunction>, crate::Error> {
+        let name = self.symbols.resolve(id.name());
+
+        // SAFETY: The caller upholds the same requirements
+        unsafe { self.get_function_by_name(&name) }.ok_or(crate::Error::UnknownFunction(name))
+    }
+
+    /// Looks the function up by its name, for packages where the descriptors are not available,
+    /// e.g. ones read from bitcode. Returns `None` if there's no such function.
+    ///
+    /// # Safety
+    /// The caller must ensure that the signature on the Rust side matches the signature of the
+    /// defined function, and that the function itself is memory-safe.
+    #[must_use]
+    pub unsafe fn get_function_by_name<TFunction>(
+        &self,
+        name: &str,
+    ) -> Option<JitFunction<TFunction>> {
+        let name = CString::from_str(name).ok()?;
+
+        // SAFETY: We have a valid `execution_engine` and a valid null-terminated name
+        let function_address =
+            unsafe { LLVMGetFunctionAddress(self.execution_engine, name.as_ptr()) };
+
+        if function_address == 0 {
+            return None;
         }
+
+        Some(JitFunction::new(
+            usize::try_from(function_address).ok()?,
+            self.fuel_address(),
+        ))
+    }
+
+    /// Sets how much fuel the code can consume before it traps, which a guarded call reports as
+    /// [`JitCallError::OutOfFuel`]. The fuel is unlimited until it's set, and is shared by all the
+    /// calls, including the ones running on other threads, which is also how a timeout stops a
+    /// call, see [`JitFunction::call_guarded_with_timeout`], so a timeout exhausts the fuel of
+    /// every call running at the time. Does nothing unless the package was built with
+    /// [`crate::package::builder::PackageBuilder::fuel`].
+    pub fn set_fuel(&self, fuel: u64) {
+        if let Some(counter) = self.fuel_counter() {
+            counter.store(i64::try_from(fuel).unwrap_or(i64::MAX), Ordering::Relaxed);
+        }
+    }
+
+    /// The fuel left, or `None` if the package was built without fuel.
+    #[must_use]
+    pub fn remaining_fuel(&self) -> Option<u64> {
+        self.fuel_counter()
+            .map(|counter| u64::try_from(counter.load(Ordering::Relaxed)).unwrap_or(0))
+    }
+
+    fn fuel_address(&self) -> Option<usize> {
+        // SAFETY: We have a valid `execution_engine` and a valid null-terminated name
+        let address =
+            unsafe { LLVMGetGlobalValueAddress(self.execution_engine, FUEL_GLOBAL_NAME.as_ptr()) };
+
+        usize::try_from(address)
+            .ok()
+            .filter(|address| *address != 0)
+    }
+
+    fn fuel_counter(&self) -> Option<&AtomicI64> {
+        // SAFETY: The address is of the i64 fuel counter, which lives for as long as the engine
+        // does, and is only accessed atomically by the instrumented code
+        self.fuel_address()
+            .map(|address| unsafe { &*(address as *const AtomicI64) })
     }
 }
 
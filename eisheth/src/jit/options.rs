@@ -0,0 +1,51 @@
+/// Settings of the execution engine created by [`super::Jit::with_options`].
+///
+/// The compiled code is always registered with the GDB JIT interface, so `gdb` and `lldb` can
+/// symbolize the JIT-ed frames. To also get source locations, the package has to be built with
+/// debug info, see [`crate::package::builder::PackageBuilder::debug_info`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JitOptions {
+    frame_pointers: bool,
+    perf_map: bool,
+}
+
+impl Default for JitOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JitOptions {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            frame_pointers: false,
+            perf_map: false,
+        }
+    }
+
+    /// Keeps the frame pointers in the generated code, so profilers and debuggers can walk the
+    /// stack through the JIT-ed frames.
+    #[must_use]
+    pub const fn frame_pointers(mut self, enabled: bool) -> Self {
+        self.frame_pointers = enabled;
+        self
+    }
+
+    /// Appends the addresses of the compiled functions to `/tmp/perf-<pid>.map`, which `perf
+    /// report` uses to symbolize JIT-ed code. Usually wanted along with
+    /// [`Self::frame_pointers`].
+    #[must_use]
+    pub const fn perf_map(mut self, enabled: bool) -> Self {
+        self.perf_map = enabled;
+        self
+    }
+
+    pub(super) const fn has_frame_pointers(&self) -> bool {
+        self.frame_pointers
+    }
+
+    pub(super) const fn has_perf_map(&self) -> bool {
+        self.perf_map
+    }
+}
@@ -0,0 +1,88 @@
+use std::{
+    fs::OpenOptions,
+    io::Write as _,
+    path::{Path, PathBuf},
+};
+
+use llvm_sys::{
+    core::{LLVMGetFirstFunction, LLVMGetNextFunction, LLVMGetValueName2, LLVMIsDeclaration},
+    execution_engine::{LLVMExecutionEngineRef, LLVMGetFunctionAddress},
+    prelude::LLVMModuleRef,
+};
+
+const PAGE_SIZE: u64 = 4096;
+
+/// The map `perf` looks for when symbolizing the code of this process, always in `/tmp`,
+/// regardless of `TMPDIR`.
+fn path() -> PathBuf {
+    Path::new("/tmp").join(format!("perf-{}.map", std::process::id()))
+}
+
+/// Finds the addresses of all the functions of the module visible outside of it, internal ones
+/// can't be looked up.
+///
+/// # Safety
+/// The module must be valid, and owned by the execution engine.
+unsafe fn function_addresses(
+    execution_engine: LLVMExecutionEngineRef,
+    module: LLVMModuleRef,
+) -> Vec<(u64, String)> {
+    let mut addresses = vec![];
+
+    // SAFETY: The caller guarantees the module is valid
+    let mut function = unsafe { LLVMGetFirstFunction(module) };
+
+    while !function.is_null() {
+        // SAFETY: The function comes from a valid module, the name is valid for as long as the
+        // function is, and ends with a null, which is what the engine expects
+        unsafe {
+            if LLVMIsDeclaration(function) == 0 {
+                let mut length = 0;
+                let name = LLVMGetValueName2(function, &raw mut length);
+                let address = LLVMGetFunctionAddress(execution_engine, name);
+
+                if address != 0 {
+                    let name = std::slice::from_raw_parts(name.cast::<u8>(), length);
+                    addresses.push((address, String::from_utf8_lossy(name).into_owned()));
+                }
+            }
+
+            function = LLVMGetNextFunction(function);
+        }
+    }
+
+    addresses
+}
+
+/// Appends the functions of the module to the perf map.
+///
+/// MCJIT doesn't report the sizes of the functions, but lays them out one after another, so each
+/// function is assumed to end where the next one starts, and the last one at the end of its page.
+///
+/// # Safety
+/// The module must be valid, and owned by the execution engine.
+pub(super) unsafe fn write(
+    execution_engine: LLVMExecutionEngineRef,
+    module: LLVMModuleRef,
+) -> std::io::Result<()> {
+    // SAFETY: The caller upholds the same requirements
+    let mut addresses = unsafe { function_addresses(execution_engine, module) };
+    addresses.sort_unstable();
+
+    // Written at once, so the entries of different engines don't interleave
+    let mut entries = vec![];
+
+    for (index, (address, name)) in addresses.iter().enumerate() {
+        let end = addresses
+            .get(index + 1)
+            .map_or_else(|| (address / PAGE_SIZE + 1) * PAGE_SIZE, |(next, _)| *next);
+
+        writeln!(entries, "{address:x} {:x} {name}", end - address)?;
+    }
+
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path())?
+        .write_all(&entries)
+}
@@ -0,0 +1,118 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    sync::{
+        Condvar, LazyLock, Mutex, MutexGuard, PoisonError,
+        atomic::{AtomicI64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+/// The single thread watching over the calls with a timeout of the whole process.
+static WATCHDOGS: LazyLock<&'static Watchdogs> = LazyLock::new(|| {
+    let watchdogs: &'static Watchdogs = Box::leak(Box::default());
+
+    std::thread::Builder::new()
+        .name("eisheth-watchdog".to_string())
+        .spawn(|| watchdogs.run())
+        .expect("the watchdog thread should start");
+
+    watchdogs
+});
+
+#[derive(Default)]
+struct Watchdogs {
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+#[derive(Default)]
+struct State {
+    next_id: u64,
+    /// The fuel counters of the calls still running, by when they expire.
+    deadlines: BTreeMap<(Instant, u64), Option<usize>>,
+    /// The calls that expired, but haven't been stopped yet.
+    expired: HashSet<u64>,
+}
+
+impl Watchdogs {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn run(&self) {
+        let mut state = self.lock();
+
+        loop {
+            let now = Instant::now();
+
+            while let Some(entry) = state.deadlines.first_entry() {
+                if entry.key().0 > now {
+                    break;
+                }
+
+                let ((_, id), fuel) = entry.remove_entry();
+                state.expired.insert(id);
+
+                if let Some(fuel) = fuel {
+                    // SAFETY: The counter outlives the watchdog, which is only stopped, and
+                    // removed from the deadlines, while holding the lock
+                    unsafe { &*(fuel as *const AtomicI64) }.store(0, Ordering::Relaxed);
+                }
+            }
+
+            state = match state.deadlines.first_key_value() {
+                Some(((deadline, _), _)) => {
+                    let timeout = deadline.saturating_duration_since(now);
+
+                    self.changed
+                        .wait_timeout(state, timeout)
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .0
+                }
+                None => self
+                    .changed
+                    .wait(state)
+                    .unwrap_or_else(PoisonError::into_inner),
+            };
+        }
+    }
+}
+
+/// Stops a call once the timeout expires, by exhausting the fuel, so the code traps at its next
+/// fuel check. The fuel is shared by all the calls of a JIT, so the others trap as well.
+pub struct Watchdog {
+    /// `None` if the deadline is too far away to ever expire.
+    key: Option<(Instant, u64)>,
+}
+
+impl Watchdog {
+    /// # Safety
+    /// The fuel must point at the fuel counter of a JIT that outlives the watchdog.
+    pub unsafe fn start(timeout: Duration, fuel: Option<usize>) -> Self {
+        let Some(deadline) = Instant::now().checked_add(timeout) else {
+            return Self { key: None };
+        };
+
+        let watchdogs = *WATCHDOGS;
+        let mut state = watchdogs.lock();
+        let key = (deadline, state.next_id);
+
+        state.next_id += 1;
+        state.deadlines.insert(key, fuel);
+        drop(state);
+        watchdogs.changed.notify_one();
+
+        Self { key: Some(key) }
+    }
+
+    /// Stops the watchdog, returns whether the timeout expired first.
+    pub fn stop(self) -> bool {
+        let Some(key) = self.key else {
+            return false;
+        };
+
+        let mut state = WATCHDOGS.lock();
+
+        state.deadlines.remove(&key).is_none() && state.expired.remove(&key.1)
+    }
+}
@@ -1,13 +1,20 @@
-mod context;
+pub mod context;
+pub mod debug_info;
+mod error;
 pub mod function;
 pub mod global_symbol;
 pub mod jit;
+pub mod metadata;
 pub mod module;
 pub mod package;
+pub mod target_machine;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 pub mod types;
 pub mod value;
 
 pub use eisheth_proc_macros::{define_module, ffi_enum, ffi_struct};
+pub use error::Error;
 pub use llvm_sys;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
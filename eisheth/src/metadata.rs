@@ -0,0 +1,158 @@
+use std::marker::PhantomData;
+
+use llvm_sys::{
+    core::{
+        LLVMConstInt, LLVMInt64TypeInContext, LLVMMDNodeInContext2, LLVMMDStringInContext2,
+        LLVMValueAsMetadata,
+    },
+    prelude::LLVMMetadataRef,
+};
+
+use crate::{
+    context::{Context, LLVM_CONTEXT},
+    value::{ConstValue, Value},
+};
+
+/// A metadata node, e.g. the operand of named module metadata, see
+/// [`crate::module::builder::ModuleBuilder::add_named_metadata`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Metadata {
+    reference: LLVMMetadataRef,
+    _context: PhantomData<&'static Context>,
+}
+
+impl Metadata {
+    const fn new(reference: LLVMMetadataRef) -> Self {
+        Self {
+            reference,
+            _context: PhantomData,
+        }
+    }
+
+    #[must_use]
+    pub fn string(value: &str) -> Self {
+        // SAFETY: The context is valid, and the string is passed along with its length
+        Self::new(LLVM_CONTEXT.with(|context| unsafe {
+            LLVMMDStringInContext2(context.as_llvm_ref(), value.as_ptr().cast(), value.len())
+        }))
+    }
+
+    #[must_use]
+    pub fn constant(value: ConstValue) -> Self {
+        // SAFETY: The value comes from a safe wrapper
+        Self::new(unsafe { LLVMValueAsMetadata(value.as_llvm_ref()) })
+    }
+
+    /// A tuple of the elements, e.g. `!{!"name", i64 1}`.
+    #[must_use]
+    pub fn node(elements: &[Self]) -> Self {
+        let mut elements: Vec<_> = elements.iter().map(|x| x.reference).collect();
+
+        // SAFETY: The context is valid, and the elements vector is alive for the duration of the
+        // call
+        Self::new(LLVM_CONTEXT.with(|context| unsafe {
+            LLVMMDNodeInContext2(context.as_llvm_ref(), elements.as_mut_ptr(), elements.len())
+        }))
+    }
+
+    fn offset(offset: u64) -> Self {
+        // SAFETY: The context is valid, and so is the type created in it
+        let value = LLVM_CONTEXT.with(|context| unsafe {
+            LLVMConstInt(LLVMInt64TypeInContext(context.as_llvm_ref()), offset, 0)
+        });
+
+        // SAFETY: We just created the value, it is valid
+        Self::new(unsafe { LLVMValueAsMetadata(value) })
+    }
+
+    pub(crate) const fn as_llvm_ref(self) -> LLVMMetadataRef {
+        self.reference
+    }
+}
+
+/// A node of the type hierarchy used by type-based alias analysis. Accesses through types of
+/// which neither is an ancestor of the other are assumed not to alias.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TbaaType(Metadata);
+
+impl TbaaType {
+    /// The root of a hierarchy. Types of different hierarchies may alias.
+    #[must_use]
+    pub fn root(name: &str) -> Self {
+        Self(Metadata::node(&[Metadata::string(name)]))
+    }
+
+    /// A scalar type, like an integer or a pointer, below the parent.
+    #[must_use]
+    pub fn scalar(&self, name: &str) -> Self {
+        Self(Metadata::node(&[
+            Metadata::string(name),
+            self.0,
+            Metadata::offset(0),
+        ]))
+    }
+
+    /// The tag attached to the loads and stores of the type.
+    #[must_use]
+    pub fn access_tag(&self) -> TbaaAccessTag {
+        TbaaAccessTag(Metadata::node(&[self.0, self.0, Metadata::offset(0)]))
+    }
+}
+
+/// Describes the memory accessed by a load or a store, see [`TbaaType::access_tag`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TbaaAccessTag(Metadata);
+
+impl TbaaAccessTag {
+    pub(crate) const fn metadata(self) -> Metadata {
+        self.0
+    }
+}
+
+/// A set of alias scopes, where an access annotated with [`MemoryAnnotation::NoAlias`] of some
+/// scopes is assumed not to alias the accesses in those scopes. Identified by its name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AliasScopeDomain(Metadata);
+
+impl AliasScopeDomain {
+    #[must_use]
+    pub fn new(name: &str) -> Self {
+        Self(Metadata::node(&[Metadata::string(name)]))
+    }
+
+    /// A scope of the domain, identified by its name.
+    #[must_use]
+    pub fn scope(&self, name: &str) -> AliasScope {
+        AliasScope(Metadata::node(&[Metadata::string(name), self.0]))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AliasScope(Metadata);
+
+impl AliasScope {
+    pub(crate) fn list(scopes: &[Self]) -> Metadata {
+        Metadata::node(&scopes.iter().map(|x| x.0).collect::<Vec<_>>())
+    }
+}
+
+/// A fact about a load or a store, which the optimizer can rely on.
+///
+/// Attached by [`crate::function::instruction_builder::InstructionBuilder::annotate`]. The facts
+/// are not checked, so a wrong one makes the code undefined behavior.
+#[derive(Debug, Clone)]
+pub enum MemoryAnnotation {
+    /// `!tbaa`, the type of the accessed memory.
+    Tbaa(TbaaAccessTag),
+    /// `!alias.scope`, the scopes the access belongs to.
+    AliasScopes(Vec<AliasScope>),
+    /// `!noalias`, the scopes the access does not alias with.
+    NoAlias(Vec<AliasScope>),
+    /// `!range`, for loads of integers, which are at least `low` and less than `high`, wrapping
+    /// around if `low` is greater. Both bounds have the type of the loaded value.
+    Range { low: ConstValue, high: ConstValue },
+    /// `!nonnull`, for loads of pointers, which are never null.
+    NonNull,
+    /// `!invariant.load`, for loads of memory which does not change while the code runs.
+    InvariantLoad,
+}
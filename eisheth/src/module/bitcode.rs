@@ -0,0 +1,309 @@
+use std::{
+    collections::{HashMap, HashSet},
+    ffi::{CStr, c_char},
+    fmt::Write as _,
+    fs::File,
+    hash::{DefaultHasher, Hash as _, Hasher as _},
+    io::Read as _,
+    sync::LazyLock,
+    time::SystemTime,
+};
+
+use llvm_sys::{
+    bit_reader::LLVMParseBitcodeInContext2,
+    bit_writer::LLVMWriteBitcodeToMemoryBuffer,
+    core::{
+        LLVMAddNamedMetadataOperand, LLVMCloneModule, LLVMConstInt, LLVMConstIntGetSExtValue,
+        LLVMConstIntGetZExtValue, LLVMCreateMemoryBufferWithMemoryRangeCopy,
+        LLVMDisposeMemoryBuffer, LLVMDisposeModule, LLVMGetBufferSize, LLVMGetBufferStart,
+        LLVMGetMDNodeNumOperands, LLVMGetMDNodeOperands, LLVMGetMDString, LLVMGetModuleContext,
+        LLVMGetModuleIdentifier, LLVMGetNamedMetadataNumOperands, LLVMGetNamedMetadataOperands,
+        LLVMInt64TypeInContext, LLVMIsAConstantInt, LLVMIsAMDString, LLVMMDNodeInContext2,
+        LLVMMDStringInContext2, LLVMMetadataAsValue, LLVMValueAsMetadata,
+    },
+    prelude::{LLVMModuleRef, LLVMValueRef},
+};
+use thiserror::Error;
+
+use super::AnyModule;
+use crate::context::{
+    Context,
+    diagnostic::{DIAGNOSTIC_HANDLER, DiagnosticHandler},
+};
+
+const GLOBAL_MAPPINGS_METADATA: &CStr = c"eisheth.global_mappings";
+
+/// The global mappings are absolute addresses, which change between runs of the program. Instead
+/// of those, the offsets relative to this static are stored in the bitcode, which stay the same
+/// for as long as the binary does not change.
+static ADDRESS_ANCHOR: u8 = 0;
+
+/// Identifies the build of the binary, so that the global mappings are only read by the binary
+/// that wrote them. It's the hash of the executable, read when bitcode is first written or read.
+/// If the executable cannot be read, the fingerprint is unique to the process instead, so that
+/// the bitcode can only be read back by the process that wrote it.
+static BUILD_FINGERPRINT: LazyLock<u64> =
+    LazyLock::new(|| executable_hash().unwrap_or_else(|_| process_fingerprint()));
+
+#[derive(Debug, Error)]
+pub enum BitcodeError {
+    #[error("Failed to parse the bitcode:\n{0}")]
+    Parse(String),
+    #[error("The global mappings stored in the bitcode are malformed")]
+    MalformedGlobalMappings,
+    #[error("The bitcode was written by another build of the binary")]
+    DifferentBuild,
+}
+
+fn anchor_address() -> usize {
+    (&raw const ADDRESS_ANCHOR).addr()
+}
+
+fn executable_hash() -> std::io::Result<u64> {
+    let mut executable = File::open(std::env::current_exe()?)?;
+    let mut hasher = DefaultHasher::new();
+    let mut buffer = vec![0; 1 << 16];
+
+    loop {
+        match executable.read(&mut buffer)? {
+            0 => break Ok(hasher.finish()),
+            read => hasher.write(&buffer[..read]),
+        }
+    }
+}
+
+fn process_fingerprint() -> u64 {
+    let mut hasher = DefaultHasher::new();
+
+    (std::process::id(), anchor_address(), SystemTime::now()).hash(&mut hasher);
+
+    hasher.finish()
+}
+
+/// Writes the bitcode of a copy of the module, with the `global_mappings` stored as named
+/// metadata, along with the fingerprint of the build, so the module itself is left untouched.
+///
+/// Named metadata can't be removed through the C API, so the mappings of a module that was read
+/// back, or linked from modules that were, stay in it. Only the mappings missing from it are
+/// added, so writing it again doesn't duplicate them.
+pub(crate) fn write(module: &dyn AnyModule, global_mappings: &HashMap<String, usize>) -> Vec<u8> {
+    // SAFETY: The module reference is valid for as long as the module exists
+    let module = unsafe { LLVMCloneModule(module.as_llvm_ref()) };
+    // SAFETY: We just cloned the module, so it's valid
+    let context = unsafe { LLVMGetModuleContext(module) };
+    let anchor = anchor_address();
+
+    // SAFETY: We just cloned the module, so it's valid
+    let stored: HashSet<String> = unsafe { named_metadata(module, GLOBAL_MAPPINGS_METADATA) }
+        .into_iter()
+        // SAFETY: The nodes come from the valid module
+        .filter_map(|node| unsafe { read_global_mapping(node) }.ok())
+        .map(|(name, _, _)| name)
+        .collect();
+
+    let mut global_mappings: Vec<_> = global_mappings
+        .iter()
+        .filter(|(name, _)| !stored.contains(*name))
+        .collect();
+    global_mappings.sort();
+
+    for (name, address) in global_mappings {
+        #[allow(clippy::cast_possible_wrap)]
+        let offset = address.wrapping_sub(anchor) as i64;
+
+        // SAFETY: The context and module are valid, the name pointer is valid for the given
+        // length, and the constants get created in the module's context
+        unsafe {
+            let mut operands = [
+                LLVMMDStringInContext2(context, name.as_ptr().cast(), name.len()),
+                LLVMValueAsMetadata(LLVMConstInt(
+                    LLVMInt64TypeInContext(context),
+                    offset.cast_unsigned(),
+                    1,
+                )),
+                LLVMValueAsMetadata(LLVMConstInt(
+                    LLVMInt64TypeInContext(context),
+                    *BUILD_FINGERPRINT,
+                    0,
+                )),
+            ];
+            let node = LLVMMDNodeInContext2(context, operands.as_mut_ptr(), operands.len());
+
+            LLVMAddNamedMetadataOperand(
+                module,
+                GLOBAL_MAPPINGS_METADATA.as_ptr(),
+                LLVMMetadataAsValue(context, node),
+            );
+        }
+    }
+
+    // SAFETY: The module is valid, the returned buffer is owned by us
+    let buffer = unsafe { LLVMWriteBitcodeToMemoryBuffer(module) };
+
+    // SAFETY: The buffer was just created, the start and size describe its contents
+    let result = unsafe {
+        std::slice::from_raw_parts(
+            LLVMGetBufferStart(buffer).cast::<u8>(),
+            LLVMGetBufferSize(buffer),
+        )
+    }
+    .to_vec();
+
+    // SAFETY: The contents were copied and the clone is not referenced by anything else, so both
+    // can be disposed
+    unsafe {
+        LLVMDisposeMemoryBuffer(buffer);
+        LLVMDisposeModule(module);
+    };
+
+    result
+}
+
+pub(crate) struct ReadModule {
+    pub(crate) reference: LLVMModuleRef,
+    pub(crate) name: String,
+    pub(crate) global_mappings: HashMap<String, usize>,
+}
+
+/// Parses the bitcode into a module in the given context. The caller takes ownership of the
+/// returned module reference, which must be disposed before the context. Bitcode with global
+/// mappings is rejected, unless it was written by the same build of the binary.
+///
+/// # Safety
+/// The bitcode must have been written by [`write`], as otherwise the global mappings will point
+/// at arbitrary addresses.
+pub(crate) unsafe fn read(context: &Context, bitcode: &[u8]) -> Result<ReadModule, BitcodeError> {
+    // SAFETY: The data pointer is valid for the given length, the buffer name is a valid
+    // null-terminated string, and LLVM makes its own copy of the data
+    let buffer = unsafe {
+        LLVMCreateMemoryBufferWithMemoryRangeCopy(
+            bitcode.as_ptr().cast(),
+            bitcode.len(),
+            c"bitcode".as_ptr(),
+        )
+    };
+
+    let mut module = std::ptr::null_mut();
+
+    // SAFETY: The context and buffer are valid, the module is an out-pointer
+    let is_failed =
+        unsafe { LLVMParseBitcodeInContext2(context.as_llvm_ref(), buffer, &raw mut module) != 0 };
+    let diagnostics = DIAGNOSTIC_HANDLER.with(DiagnosticHandler::take_diagnostics);
+
+    // SAFETY: The parser does not take ownership of the buffer, and the module does not keep
+    // references to it
+    unsafe { LLVMDisposeMemoryBuffer(buffer) };
+
+    if is_failed {
+        let mut message = String::new();
+        for diagnostic in diagnostics {
+            let _ = writeln!(message, "{diagnostic}");
+        }
+
+        return Err(BitcodeError::Parse(message));
+    }
+
+    // SAFETY: The module was just parsed, so it's valid
+    let global_mappings = match unsafe { read_global_mappings(module) } {
+        Ok(global_mappings) => global_mappings,
+        Err(error) => {
+            // SAFETY: Nobody else has a reference to the module yet
+            unsafe { LLVMDisposeModule(module) };
+
+            return Err(error);
+        }
+    };
+
+    let mut name_length = 0;
+    // SAFETY: The module is valid, LLVM returns a pointer to a string of the given length, owned
+    // by the module
+    let name = unsafe {
+        let name = LLVMGetModuleIdentifier(module, &raw mut name_length);
+
+        String::from_utf8_lossy(std::slice::from_raw_parts(name.cast::<u8>(), name_length))
+            .into_owned()
+    };
+
+    Ok(ReadModule {
+        reference: module,
+        name,
+        global_mappings,
+    })
+}
+
+/// # Safety
+/// The module must be valid.
+unsafe fn read_global_mappings(
+    module: LLVMModuleRef,
+) -> Result<HashMap<String, usize>, BitcodeError> {
+    let anchor = anchor_address();
+    let mut global_mappings = HashMap::new();
+
+    // SAFETY: The caller guarantees the module is valid
+    for node in unsafe { named_metadata(module, GLOBAL_MAPPINGS_METADATA) } {
+        // SAFETY: The node comes from the valid module
+        let (name, offset, fingerprint) = unsafe { read_global_mapping(node) }?;
+
+        // The offsets are only meaningful for the binary that wrote them
+        if fingerprint != *BUILD_FINGERPRINT {
+            return Err(BitcodeError::DifferentBuild);
+        }
+
+        #[allow(clippy::cast_possible_truncation)]
+        global_mappings.insert(name, anchor.wrapping_add(offset.cast_unsigned() as usize));
+    }
+
+    Ok(global_mappings)
+}
+
+/// Reads the name, offset and build fingerprint of a global mapping.
+///
+/// # Safety
+/// The node must be an operand of the module's named metadata.
+unsafe fn read_global_mapping(node: LLVMValueRef) -> Result<(String, i64, u64), BitcodeError> {
+    // SAFETY: All the operands of named metadata are metadata nodes
+    if unsafe { LLVMGetMDNodeNumOperands(node) } != 3 {
+        return Err(BitcodeError::MalformedGlobalMappings);
+    }
+
+    let mut operands: [LLVMValueRef; 3] = [std::ptr::null_mut(); 3];
+    // SAFETY: We've checked the node has exactly three operands
+    unsafe { LLVMGetMDNodeOperands(node, operands.as_mut_ptr()) };
+    let [name, offset, fingerprint] = operands;
+
+    // SAFETY: The operands are valid values, we check their kinds before using them
+    unsafe {
+        if LLVMIsAMDString(name).is_null()
+            || LLVMIsAConstantInt(offset).is_null()
+            || LLVMIsAConstantInt(fingerprint).is_null()
+        {
+            return Err(BitcodeError::MalformedGlobalMappings);
+        }
+
+        let mut name_length = 0;
+        let name_raw: *const c_char = LLVMGetMDString(name, &raw mut name_length);
+        let name = String::from_utf8(
+            std::slice::from_raw_parts(name_raw.cast::<u8>(), name_length as usize).to_vec(),
+        )
+        .map_err(|_| BitcodeError::MalformedGlobalMappings)?;
+
+        Ok((
+            name,
+            LLVMConstIntGetSExtValue(offset),
+            LLVMConstIntGetZExtValue(fingerprint),
+        ))
+    }
+}
+
+/// # Safety
+/// The module must be valid.
+unsafe fn named_metadata(module: LLVMModuleRef, name: &CStr) -> Vec<LLVMValueRef> {
+    // SAFETY: The caller guarantees the module is valid, and the name is a valid C-string
+    let count = unsafe { LLVMGetNamedMetadataNumOperands(module, name.as_ptr()) } as usize;
+    let mut nodes: Vec<LLVMValueRef> = vec![std::ptr::null_mut(); count];
+
+    // SAFETY: The vector has enough space for all the operands
+    unsafe { LLVMGetNamedMetadataOperands(module, name.as_ptr(), nodes.as_mut_ptr()) };
+
+    nodes
+}
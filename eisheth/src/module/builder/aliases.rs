@@ -0,0 +1,74 @@
+use std::{ffi::CString, str::FromStr as _};
+
+use llvm_sys::{
+    LLVMLinkage,
+    core::{LLVMAddAlias2, LLVMAddGlobalIFunc, LLVMSetLinkage},
+    prelude::{LLVMTypeRef, LLVMValueRef},
+};
+
+use crate::{
+    Visibility,
+    function::declaration::FunctionSignature,
+    module::{DeclaredFunctionDescriptor, builder::ModuleBuilder},
+    types::Type as _,
+};
+
+/// Defines another name for the aliasee, which has to be a definition of the given type.
+pub fn define_alias(
+    module: &ModuleBuilder,
+    aliasee: LLVMValueRef,
+    value_type: LLVMTypeRef,
+    name: &str,
+    visibility: Visibility,
+) -> LLVMValueRef {
+    let name = CString::from_str(name).unwrap();
+
+    // SAFETY: the module reference, the aliasee, its type and the name are all valid pointers
+    // for the duration of the call, and the alias is valid once created
+    unsafe {
+        let alias = LLVMAddAlias2(module.reference, value_type, 0, aliasee, name.as_ptr());
+        LLVMSetLinkage(alias, linkage(visibility));
+
+        alias
+    }
+}
+
+/// Defines a function which is resolved once, when it's first linked, to the pointer returned by
+/// the resolver.
+pub fn define_ifunc(
+    module: &ModuleBuilder,
+    declaration: &FunctionSignature,
+    resolver: LLVMValueRef,
+) -> (DeclaredFunctionDescriptor, LLVMValueRef) {
+    let id = DeclaredFunctionDescriptor {
+        module_id: module.id,
+        name: module.symbols.intern(declaration.name()),
+        r#type: declaration.r#type(),
+        visibility: declaration.visibility(),
+    };
+
+    // SAFETY: the module reference, the type and the resolver are valid pointers, the name is
+    // passed along with its length, and the ifunc is valid once created
+    let ifunc = unsafe {
+        let ifunc = LLVMAddGlobalIFunc(
+            module.reference,
+            declaration.name().as_ptr().cast(),
+            declaration.name().len(),
+            id.r#type.as_llvm_ref(),
+            0,
+            resolver,
+        );
+        LLVMSetLinkage(ifunc, linkage(declaration.visibility()));
+
+        ifunc
+    };
+
+    (id, ifunc)
+}
+
+const fn linkage(visibility: Visibility) -> LLVMLinkage {
+    match visibility {
+        Visibility::Internal => LLVMLinkage::LLVMInternalLinkage,
+        Visibility::Export => LLVMLinkage::LLVMExternalLinkage,
+    }
+}
@@ -0,0 +1,59 @@
+use llvm_sys::{comdat::LLVMComdatSelectionKind, prelude::LLVMComdatRef};
+
+use crate::module::{DeclaredFunctionDescriptor, DeclaredGlobalDescriptor, ModuleId};
+
+/// How the linker picks between the comdats of the same name, see
+/// [`super::ModuleBuilder::define_comdat`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ComdatSelection {
+    /// Keeps any of them, as they're assumed to be equivalent.
+    #[default]
+    Any,
+    /// Keeps one of them, and fails to link if they differ.
+    ExactMatch,
+    /// Keeps the largest one.
+    Largest,
+    /// Fails to link if there's more than one.
+    NoDeduplicate,
+    /// Keeps one of them, and fails to link if their sizes differ.
+    SameSize,
+}
+
+impl ComdatSelection {
+    pub(super) const fn as_llvm(self) -> LLVMComdatSelectionKind {
+        match self {
+            Self::Any => LLVMComdatSelectionKind::LLVMAnyComdatSelectionKind,
+            Self::ExactMatch => LLVMComdatSelectionKind::LLVMExactMatchComdatSelectionKind,
+            Self::Largest => LLVMComdatSelectionKind::LLVMLargestComdatSelectionKind,
+            Self::NoDeduplicate => LLVMComdatSelectionKind::LLVMNoDuplicatesComdatSelectionKind,
+            Self::SameSize => LLVMComdatSelectionKind::LLVMSameSizeComdatSelectionKind,
+        }
+    }
+}
+
+/// A group of functions and globals the linker keeps or discards together, so the same
+/// definitions can be emitted by several modules, and only one copy of them is linked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Comdat {
+    pub(super) module_id: ModuleId,
+    pub(super) reference: LLVMComdatRef,
+}
+
+/// A definition added to a [`Comdat`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComdatMember {
+    Function(DeclaredFunctionDescriptor),
+    Global(DeclaredGlobalDescriptor),
+}
+
+impl From<DeclaredFunctionDescriptor> for ComdatMember {
+    fn from(value: DeclaredFunctionDescriptor) -> Self {
+        Self::Function(value)
+    }
+}
+
+impl From<DeclaredGlobalDescriptor> for ComdatMember {
+    fn from(value: DeclaredGlobalDescriptor) -> Self {
+        Self::Global(value)
+    }
+}
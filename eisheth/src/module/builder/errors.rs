@@ -2,21 +2,197 @@ use std::{error::Error, fmt::Display};
 
 use thiserror::Error;
 
+use crate::{
+    context::diagnostic::{Diagnostic, find_function},
+    function::validation::ValidationError,
+};
+
+/// One of the errors reported by the LLVM verifier.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifierError {
+    pub message: String,
+    /// The function the offending instructions belong to, if it could be found.
+    pub function: Option<String>,
+    /// The instructions and values LLVM printed along with the message.
+    pub values: Vec<String>,
+}
+
+impl VerifierError {
+    /// Splits the output of the verifier into the separate errors. Each error starts with an
+    /// unindented message ending with `!`, followed by the values it refers to, where the
+    /// instructions are indented.
+    pub(super) fn parse(output: &str, ir: &str) -> Vec<Self> {
+        let mut errors: Vec<Self> = vec![];
+
+        for line in output.lines().filter(|x| !x.trim().is_empty()) {
+            let is_message = !line.starts_with(char::is_whitespace) && line.ends_with('!');
+
+            match errors.last_mut() {
+                Some(error) if !is_message => error.values.push(line.trim().to_string()),
+                _ => errors.push(Self {
+                    message: line.to_string(),
+                    function: find_function(line),
+                    values: vec![],
+                }),
+            }
+        }
+
+        let functions = split_functions(ir);
+
+        for error in &mut errors {
+            if error.function.is_none() {
+                error.function = error
+                    .values
+                    .iter()
+                    .find_map(|value| find_containing_function(value, &functions));
+            }
+        }
+
+        errors
+    }
+}
+
+impl Display for VerifierError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)?;
+
+        if let Some(function) = &self.function {
+            write!(f, " (in function \"{function}\")")?;
+        }
+
+        for value in &self.values {
+            write!(f, "\n  {value}")?;
+        }
+
+        Ok(())
+    }
+}
+
+fn find_containing_function(value: &str, functions: &[(&str, &str)]) -> Option<String> {
+    functions
+        .iter()
+        .find(|(_, ir)| ir.lines().any(|line| line.trim() == value))
+        .map(|(name, _)| (*name).to_string())
+}
+
+/// Splits the IR of a module into the names and the IR of the defined functions.
+fn split_functions(ir: &str) -> Vec<(&str, &str)> {
+    let mut functions = vec![];
+    let mut rest = ir;
+
+    while let Some(start) = rest
+        .find("\ndefine ")
+        .map(|x| x + 1)
+        .or_else(|| rest.starts_with("define ").then_some(0))
+    {
+        let function = &rest[start..];
+        let end = function.find("\n}").map_or(function.len(), |x| x + 2);
+        let (function, remaining) = function.split_at(end);
+
+        if let Some(name) = function_name(function) {
+            functions.push((name, function));
+        }
+
+        rest = remaining;
+    }
+
+    functions
+}
+
+/// Takes the name from the `define` line, e.g. `define i64 @name(i64 %0) {`, where names with
+/// special characters are quoted, e.g. `@"some name"`.
+fn function_name(function: &str) -> Option<&str> {
+    let (_, name) = function.lines().next()?.split_once('@')?;
+
+    let (name, _) = match name.strip_prefix('"') {
+        Some(quoted) => quoted.split_once('"')?,
+        None => name.split_once('(')?,
+    };
+
+    Some(name)
+}
+
 #[derive(Debug)]
 pub struct ModuleBuildError {
     pub(super) module_name: String,
-    pub(super) message: String,
-    pub(super) diagnostics: Vec<crate::context::diagnostic::Diagnostic>,
+    pub(super) diagnostics: Vec<Diagnostic>,
+    pub(super) validation_errors: Vec<ValidationError>,
+    pub(super) verifier_errors: Vec<VerifierError>,
     pub(super) raw_ir: String,
 }
 
+impl ModuleBuildError {
+    /// The names and the IR of the functions mentioned by the validation and verifier errors.
+    fn failing_functions(&self) -> Vec<(&str, &str)> {
+        let failing: Vec<_> = self
+            .validation_errors
+            .iter()
+            .map(|x| x.function.as_str())
+            .chain(
+                self.verifier_errors
+                    .iter()
+                    .filter_map(|x| x.function.as_deref()),
+            )
+            .collect();
+
+        split_functions(&self.raw_ir)
+            .into_iter()
+            .filter(|(name, _)| failing.contains(name))
+            .collect()
+    }
+
+    #[must_use]
+    pub fn module_name(&self) -> &str {
+        &self.module_name
+    }
+
+    /// The diagnostics LLVM reported while the module was being verified.
+    #[must_use]
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// The instructions that were rejected while the module was being built, before the
+    /// verifier could run.
+    #[must_use]
+    pub fn validation_errors(&self) -> &[ValidationError] {
+        &self.validation_errors
+    }
+
+    /// The errors reported by the LLVM verifier, split by the function they refer to.
+    #[must_use]
+    pub fn verifier_errors(&self) -> &[VerifierError] {
+        &self.verifier_errors
+    }
+
+    /// The IR of the whole module, as it was when it failed to build.
+    #[must_use]
+    pub fn raw_ir(&self) -> &str {
+        &self.raw_ir
+    }
+
+    /// A report that only contains the IR of the functions that failed, instead of the whole
+    /// module. Falls back to the whole module if none of the errors could be attributed to a
+    /// function.
+    #[must_use]
+    pub const fn compact(&self) -> CompactReport<'_> {
+        CompactReport(self)
+    }
+}
+
 impl Display for ModuleBuildError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "Failed to build the module \"{}\":\n{}\nDiagnosics:\n",
-            self.module_name, self.message,
-        )?;
+        writeln!(f, "Failed to build the module \"{}\":", self.module_name)?;
+
+        for error in &self.validation_errors {
+            writeln!(f, "{error}")?;
+        }
+
+        for error in &self.verifier_errors {
+            writeln!(f, "{error}")?;
+        }
+
+        writeln!(f, "Diagnosics:")?;
 
         for diagnostic in &self.diagnostics {
             writeln!(f, "{diagnostic}")?;
@@ -30,10 +206,47 @@ impl Display for ModuleBuildError {
 
 impl Error for ModuleBuildError {}
 
+/// See [`ModuleBuildError::compact`].
+pub struct CompactReport<'a>(&'a ModuleBuildError);
+
+impl Display for CompactReport<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let error = self.0;
+
+        writeln!(f, "Failed to build the module \"{}\":", error.module_name)?;
+
+        for validation_error in &error.validation_errors {
+            writeln!(f, "{validation_error}")?;
+        }
+
+        for verifier_error in &error.verifier_errors {
+            writeln!(f, "{verifier_error}")?;
+        }
+
+        for diagnostic in &error.diagnostics {
+            writeln!(f, "{diagnostic}")?;
+        }
+
+        let failing_functions = error.failing_functions();
+
+        if failing_functions.is_empty() {
+            return writeln!(f, "LLVM IR:\n{}", error.raw_ir);
+        }
+
+        for (name, ir) in failing_functions {
+            writeln!(f, "LLVM IR of \"{name}\":\n{ir}")?;
+        }
+
+        Ok(())
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum ImportError {
     #[error("{0} is not exported")]
     NotExported(String),
     #[error("{0} cannot be imported into the same module where it was defined")]
     DefinedInThisModule(String),
+    #[error("The imported item was declared in a different package")]
+    DifferentPackage,
 }
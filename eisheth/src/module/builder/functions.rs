@@ -60,6 +60,10 @@ pub fn import_function(
     module: &ModuleBuilder,
     id: DeclaredFunctionDescriptor,
 ) -> Result<(DeclaredFunctionDescriptor, LLVMValueRef), ImportError> {
+    if id.module_id.package_id() != module.id.package_id() {
+        return Err(ImportError::DifferentPackage);
+    }
+
     if id.module_id == module.id {
         return Err(ImportError::DefinedInThisModule(
             module.symbols.resolve(id.name),
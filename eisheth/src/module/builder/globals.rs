@@ -1,7 +1,11 @@
 use std::{ffi::CString, str::FromStr as _};
 
 use llvm_sys::{
-    core::{LLVMAddGlobal, LLVMGetUndef, LLVMSetInitializer, LLVMSetLinkage},
+    LLVMLinkage, LLVMUnnamedAddr,
+    core::{
+        LLVMAddGlobal, LLVMGetUndef, LLVMSetGlobalConstant, LLVMSetInitializer, LLVMSetLinkage,
+        LLVMSetUnnamedAddress,
+    },
     prelude::LLVMValueRef,
 };
 
@@ -56,10 +60,36 @@ pub fn define_global<T: Type>(
     (descriptor, global)
 }
 
+/// Defines a private constant global holding the string, whose address can be merged with any
+/// other constant of the same contents.
+pub fn define_string_constant(module: &ModuleBuilder, value: &str) -> ConstValue {
+    let initializer = ConstValue::null_terminated_string(value);
+
+    // SAFETY: the module reference, type and name are all valid pointers for the duration of
+    // the call, the global is valid once created, and its initializer is of its type
+    unsafe {
+        let global = LLVMAddGlobal(
+            module.reference,
+            initializer.r#type().as_llvm_ref(),
+            c".str".as_ptr(),
+        );
+        LLVMSetInitializer(global, initializer.as_llvm_ref());
+        LLVMSetGlobalConstant(global, 1);
+        LLVMSetLinkage(global, LLVMLinkage::LLVMPrivateLinkage);
+        LLVMSetUnnamedAddress(global, LLVMUnnamedAddr::LLVMGlobalUnnamedAddr);
+
+        ConstValue::new(global)
+    }
+}
+
 pub fn import_global(
     module: &ModuleBuilder,
     id: DeclaredGlobalDescriptor,
 ) -> Result<(DeclaredGlobalDescriptor, LLVMValueRef), ImportError> {
+    if id.module_id.package_id() != module.id.package_id() {
+        return Err(ImportError::DifferentPackage);
+    }
+
     if module.id == id.module_id {
         return Err(ImportError::DefinedInThisModule(
             module.symbols.resolve(id.name),
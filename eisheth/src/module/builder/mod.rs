@@ -1,11 +1,11 @@
 use crate::{
     Visibility,
-    context::diagnostic::{DIAGNOSTIC_HANDLER, DiagnosticHandler},
+    context::diagnostic::{DIAGNOSTIC_HANDLER, DiagnosticHandler, DiagnosticScope},
     global_symbol::GlobalSymbol,
     module::{
-        AnyModule, AnyModuleExtensions, DeclaredGlobalDescriptor, GlobalReference,
+        AnyModule, AnyModuleExtensions, DeclaredGlobalDescriptor, GlobalReference, StringConstant,
         builder::{
-            errors::{ImportError, ModuleBuildError},
+            errors::{ImportError, ModuleBuildError, VerifierError},
             global_finalizers::{
                 FinalizersEntryType, GLOBAL_FINALIZERS_ENTRY_TYPE, GlobalFinalizerDescriptor,
             },
@@ -14,25 +14,25 @@ use crate::{
     },
 };
 
+mod aliases;
+pub mod comdat;
 pub mod errors;
 mod functions;
 mod global_finalizers;
 mod global_initializers;
 mod globals;
 
-use std::{
-    collections::HashMap,
-    ffi::{CStr, CString},
-    hash::Hash,
-    rc::Rc,
-    str::FromStr as _,
-};
+use std::{cell::RefCell, collections::HashMap, ffi::CStr, hash::Hash, sync::Arc};
 
+use comdat::{Comdat, ComdatMember, ComdatSelection};
 use llvm_sys::{
-    LLVMLinkage,
+    LLVMLinkage, LLVMTypeKind,
     analysis::{LLVMVerifierFailureAction, LLVMVerifyModule},
+    comdat::{LLVMGetOrInsertComdat, LLVMSetComdat, LLVMSetComdatSelectionKind},
     core::{
-        LLVMDisposeMessage, LLVMDisposeModule, LLVMModuleCreateWithNameInContext, LLVMSetLinkage,
+        LLVMAddNamedMetadataOperand, LLVMAppendModuleInlineAsm, LLVMDisposeMessage,
+        LLVMDisposeModule, LLVMGetReturnType, LLVMGetTypeKind, LLVMMetadataAsValue,
+        LLVMModuleCreateWithNameInContext, LLVMSetLinkage,
     },
     prelude::{LLVMModuleRef, LLVMValueRef},
 };
@@ -40,13 +40,17 @@ use llvm_sys::{
 use super::{DeclaredFunctionDescriptor, ModuleId, built::Module};
 use crate::{
     context::LLVM_CONTEXT,
+    debug_info::{DebugInfoBuilder, DebugType, DebugTypeEncoding},
+    error::{Error, to_c_string},
     function::{
         builder::{FunctionBuilder, FunctionReference},
         declaration::FunctionSignature,
+        validation::ValidationError,
     },
     global_symbol::GlobalSymbols,
+    metadata::Metadata,
     module::builder::global_initializers::{GLOBAL_INITIALIZERS_ENTRY_TYPE, InitializersEntryType},
-    package::context::PackageContext,
+    package::{context::PackageContext, id::PACKAGE_ID_GENERATOR},
     types::{self, Type},
     value::ConstValue,
 };
@@ -57,12 +61,16 @@ pub struct GlobalId(ModuleId, GlobalSymbol);
 pub struct ModuleBuilder {
     id: ModuleId,
     reference: LLVMModuleRef,
-    symbols: Rc<GlobalSymbols>,
+    symbols: Arc<GlobalSymbols>,
     global_initializers: Vec<GlobalInitializerDescriptor>,
     global_finalizers: Vec<GlobalFinalizerDescriptor>,
     global_mappings: HashMap<String, usize>,
     global_values: HashMap<DeclaredGlobalDescriptor, LLVMValueRef>,
     function_values: HashMap<DeclaredFunctionDescriptor, LLVMValueRef>,
+    imports: Vec<(ModuleId, GlobalSymbol)>,
+    validation_errors: RefCell<Vec<ValidationError>>,
+    string_constants: RefCell<HashMap<String, StringConstant>>,
+    debug_info: Option<DebugInfoBuilder>,
 }
 
 impl AnyModule for ModuleBuilder {
@@ -72,20 +80,19 @@ impl AnyModule for ModuleBuilder {
 }
 
 impl ModuleBuilder {
-    pub(crate) fn new(package_context: &PackageContext, name: &str) -> Self {
+    pub(crate) fn new(package_context: &PackageContext, name: &str) -> Result<Self, Error> {
+        let c_name = to_c_string(name)?;
         let module = LLVM_CONTEXT.with(|context| {
-            let name = CString::from_str(name).unwrap();
-
             // SAFETY: The `name` is a valid null-terminated string, and we have a reference to
             // context, so the one returned from `as_llvm_ref` must be valid
             unsafe {
-                LLVMModuleCreateWithNameInContext(name.as_ptr().cast(), context.as_llvm_ref())
+                LLVMModuleCreateWithNameInContext(c_name.as_ptr().cast(), context.as_llvm_ref())
             }
         });
 
         let symbols = package_context.symbols();
 
-        Self {
+        Ok(Self {
             reference: module,
             id: ModuleId(package_context.id(), symbols.intern(name)),
             symbols,
@@ -94,42 +101,213 @@ impl ModuleBuilder {
             global_mappings: HashMap::new(),
             global_values: HashMap::new(),
             function_values: HashMap::new(),
-        }
+            imports: vec![],
+            validation_errors: RefCell::new(vec![]),
+            string_constants: RefCell::new(HashMap::new()),
+            debug_info: None,
+        })
+    }
+
+    /// Creates a module which does not belong to any package. Once built, it can be added to any
+    /// number of packages with [`crate::package::builder::PackageBuilder::attach_module`].
+    ///
+    /// A standalone module cannot import anything, as it has no other modules to import from.
+    ///
+    /// # Panics
+    /// If the name cannot be converted into a C-string, see [`Self::try_standalone`].
+    #[must_use]
+    pub fn standalone(name: &str) -> Self {
+        Self::try_standalone(name).unwrap()
+    }
+
+    /// # Errors
+    /// Will return an error if the name cannot be converted into a C-string.
+    pub fn try_standalone(name: &str) -> Result<Self, Error> {
+        Self::new(
+            &PackageContext::new(PACKAGE_ID_GENERATOR.next(), Arc::new(GlobalSymbols::new())),
+            name,
+        )
     }
 
     pub(crate) const fn as_llvm_ref(&self) -> LLVMModuleRef {
         self.reference
     }
 
+    pub(crate) fn name(&self) -> String {
+        self.symbols.resolve(self.id.1)
+    }
+
+    /// The IR of the module as it is so far, before it's verified and the global initializers and
+    /// finalizers are added.
+    #[must_use]
+    pub fn dump_ir(&self) -> String {
+        AnyModuleExtensions::dump_ir(self)
+    }
+
+    /// Adds a compile unit for the source file to the module. Functions defined from then on get
+    /// debug info, which can be extended with source locations and variables, see
+    /// [`crate::function::instruction_builder::InstructionBuilder::set_location`].
+    ///
+    /// Does nothing if the debug info was already enabled.
+    pub fn enable_debug_info(&mut self, file: &str, directory: &str) {
+        if self.debug_info.is_none() {
+            // SAFETY: The module is valid, and the builder is dropped before the module is
+            self.debug_info =
+                Some(unsafe { DebugInfoBuilder::new(self.reference, file, directory) });
+        }
+    }
+
+    /// Describes a primitive type, like an integer or a float, for the debugger. The type is
+    /// empty if the debug info is not enabled.
+    #[must_use]
+    pub fn debug_basic_type(
+        &self,
+        name: &str,
+        size_in_bits: u64,
+        encoding: DebugTypeEncoding,
+    ) -> DebugType {
+        self.debug_info
+            .as_ref()
+            .map_or(DebugType::empty(), |debug_info| {
+                debug_info.basic_type(name, size_in_bits, encoding)
+            })
+    }
+
+    /// Describes a pointer to the `pointee` for the debugger. The type is empty if the debug info
+    /// is not enabled.
+    #[must_use]
+    pub fn debug_pointer_type(&self, pointee: DebugType, size_in_bits: u64) -> DebugType {
+        self.debug_info
+            .as_ref()
+            .map_or(DebugType::empty(), |debug_info| {
+                debug_info.pointer_type(pointee, size_in_bits)
+            })
+    }
+
+    pub(crate) const fn debug_info(&self) -> Option<&DebugInfoBuilder> {
+        self.debug_info.as_ref()
+    }
+
+    pub(crate) fn resolve(&self, symbol: GlobalSymbol) -> String {
+        self.symbols.resolve(symbol)
+    }
+
+    /// The modules and names of all the functions and globals imported into this module.
+    pub(crate) fn imports(&self) -> impl Iterator<Item = (String, String)> {
+        self.imports.iter().map(|(module_id, name)| {
+            (
+                self.symbols.resolve(module_id.name()),
+                self.symbols.resolve(*name),
+            )
+        })
+    }
+
     /// # Panics
-    /// Will panic if the name cannot be converted into a c-string.
+    /// Will panic if the name cannot be converted into a c-string, see
+    /// [`Self::try_define_runtime_function`].
     /// # Safety
-    /// The `runtime_function_address` must point at a function with `extern "C"` linkage, that
-    /// matches the signature declared in `declaration`
+    /// The `runtime_function_address` must point at a function with `extern "C"` linkage, or
+    /// `extern "C-unwind"` if it may panic, that matches the signature declared in `declaration`
     pub unsafe fn define_runtime_function(
         &mut self,
         declaration: &FunctionSignature,
         runtime_function_address: usize,
     ) -> DeclaredFunctionDescriptor {
+        // SAFETY: The caller upholds the same guarantees
+        unsafe { self.try_define_runtime_function(declaration, runtime_function_address) }.unwrap()
+    }
+
+    /// Runtime functions declared as `extern "C-unwind"` may panic, the panic unwinds through the
+    /// JIT-ed code, running the cleanups of the calls made with
+    /// [`crate::function::instruction_builder::InstructionBuilder::invoke`], and out of the
+    /// function called from Rust, which must then be called as `extern "C-unwind"` as well. A
+    /// panic in an `extern "C"` function aborts the process instead.
+    ///
+    /// # Errors
+    /// Will return an error if the name cannot be converted into a C-string.
+    /// # Safety
+    /// The `runtime_function_address` must point at a function with `extern "C"` linkage, or
+    /// `extern "C-unwind"` if it may panic, that matches the signature declared in `declaration`
+    pub unsafe fn try_define_runtime_function(
+        &mut self,
+        declaration: &FunctionSignature,
+        runtime_function_address: usize,
+    ) -> Result<DeclaredFunctionDescriptor, Error> {
+        to_c_string(declaration.name())?;
+
         let (id, function) = functions::declare_function(self, declaration);
 
         self.function_values.insert(id, function);
         self.global_mappings
             .insert(declaration.name().to_string(), runtime_function_address);
 
-        id
+        Ok(id)
+    }
+
+    /// # Panics
+    /// Will panic if the name cannot be converted into a c-string, see
+    /// [`Self::try_declare_external_function`].
+    pub fn declare_external_function(
+        &mut self,
+        declaration: &FunctionSignature,
+    ) -> DeclaredFunctionDescriptor {
+        self.try_declare_external_function(declaration).unwrap()
+    }
+
+    /// Declares a function which is neither defined in the package nor mapped to the runtime, and
+    /// gets resolved by symbol when linking, or from the symbols of the process in the JIT. Mostly
+    /// useful for personality functions, see [`FunctionBuilder::set_personality`].
+    ///
+    /// # Errors
+    /// Will return an error if the name cannot be converted into a C-string.
+    pub fn try_declare_external_function(
+        &mut self,
+        declaration: &FunctionSignature,
+    ) -> Result<DeclaredFunctionDescriptor, Error> {
+        to_c_string(declaration.name())?;
+
+        let (id, function) = functions::declare_function(self, declaration);
+
+        self.function_values.insert(id, function);
+
+        Ok(id)
     }
 
+    /// Appends the assembly to the module, to be emitted as is alongside the compiled code. The
+    /// symbols it defines can be called through [`Self::declare_external_function`]. The assembly
+    /// is not checked until the module is compiled, so it has to be valid for the target.
+    pub fn append_module_asm(&mut self, assembly: &str) {
+        // SAFETY: The module is valid, and the assembly is passed along with its length
+        unsafe {
+            LLVMAppendModuleInlineAsm(self.reference, assembly.as_ptr().cast(), assembly.len());
+        };
+    }
+
+    /// # Panics
+    /// Will panic if the name cannot be converted into a c-string, see
+    /// [`Self::try_define_function`].
     pub fn define_function(
         &mut self,
         declaration: &FunctionSignature,
         implement: impl FnOnce(&FunctionBuilder),
     ) -> DeclaredFunctionDescriptor {
+        self.try_define_function(declaration, implement).unwrap()
+    }
+
+    /// # Errors
+    /// Will return an error if the name cannot be converted into a C-string.
+    pub fn try_define_function(
+        &mut self,
+        declaration: &FunctionSignature,
+        implement: impl FnOnce(&FunctionBuilder),
+    ) -> Result<DeclaredFunctionDescriptor, Error> {
+        to_c_string(declaration.name())?;
+
         let (id, function) = functions::define_function(self, declaration, implement);
 
         self.function_values.insert(id, function);
 
-        id
+        Ok(id)
     }
 
     pub fn define_global_initializer(
@@ -169,9 +347,11 @@ impl ModuleBuilder {
         &mut self,
         id: DeclaredFunctionDescriptor,
     ) -> Result<DeclaredFunctionDescriptor, ImportError> {
+        let exporting_module = id.module_id;
         let (id, function) = functions::import_function(self, id)?;
 
         self.function_values.insert(id, function);
+        self.imports.push((exporting_module, id.name));
 
         Ok(id)
     }
@@ -183,61 +363,64 @@ impl ModuleBuilder {
         &mut self,
         id: DeclaredGlobalDescriptor,
     ) -> Result<DeclaredGlobalDescriptor, ImportError> {
+        let exporting_module = id.module_id;
         let (id, global) = globals::import_global(self, id)?;
 
         self.global_values.insert(id, global);
+        self.imports.push((exporting_module, id.name));
 
         Ok(id)
     }
 
-    pub(crate) fn build(mut self) -> Result<(String, Module), ModuleBuildError> {
-        self.build_global_initializers();
-        self.build_global_finalizers();
+    /// Verifies the module, and returns it along with the verifier's messages. Modules added to
+    /// a package are built by [`crate::package::builder::PackageBuilder::build`], this is meant
+    /// for the modules created with [`Self::standalone`].
+    ///
+    /// # Errors
+    /// Will return an error if the module fails verification.
+    /// # Panics
+    /// If the verifier returns a message that is not valid UTF-8
+    pub fn build(self) -> Result<(String, Module), ModuleBuildError> {
+        self.check_validation()?;
 
-        let mut out_message = std::ptr::null_mut();
-        // SAFETY: We have a valid, non-null `reference`, and since the action is
-        // `LLVMAbortProcessAction`, and `out_message` is passed as a pointer to a pointer, so
-        // we'll get a new pointer put into there
-        let verify_result = unsafe {
-            LLVMVerifyModule(
-                self.reference,
-                LLVMVerifierFailureAction::LLVMReturnStatusAction,
-                &raw mut out_message,
-            )
-        };
+        let name = self.name();
+        let _diagnostic_scope = DiagnosticScope::new(&name, None);
+        let module = self.into_module();
+        let message = verify(&module, &name)?;
 
-        if verify_result != 0 {
-            // SAFETY: We received the message from the verify call above, it must be a valid
-            // pointer
-            let message = unsafe { CStr::from_ptr(out_message) }
-                .to_str()
-                .unwrap()
-                .to_string();
+        Ok((message, module))
+    }
 
-            // SAFETY: We made a copy of the message, this pointer won't be used anymore
-            unsafe {
-                LLVMDisposeMessage(out_message);
-            };
+    pub(crate) fn report_validation_error(&self, error: ValidationError) {
+        self.validation_errors.borrow_mut().push(error);
+    }
 
-            let diagnostics = DIAGNOSTIC_HANDLER.with(DiagnosticHandler::take_diagnostics);
+    /// Fails if any of the instructions were invalid, before the module gets to the LLVM
+    /// verifier.
+    pub(crate) fn check_validation(&self) -> Result<(), ModuleBuildError> {
+        let validation_errors = self.validation_errors.borrow();
 
-            return Err(ModuleBuildError {
-                module_name: self.symbols.resolve(self.id.1),
-                message,
-                diagnostics,
-                raw_ir: self.dump_ir(),
-            });
+        if validation_errors.is_empty() {
+            return Ok(());
         }
 
-        let mut message = String::new();
+        Err(ModuleBuildError {
+            module_name: self.name(),
+            diagnostics: vec![],
+            validation_errors: validation_errors.clone(),
+            verifier_errors: vec![],
+            raw_ir: self.dump_ir(),
+        })
+    }
 
-        if !out_message.is_null() {
-            // SAFETY: We've checked that the message was set to something, so it must be a valid
-            // string
-            message = unsafe { CStr::from_ptr(out_message).to_str().unwrap().to_string() };
+    /// Finishes the global initializers and finalizers, and hands the module over without
+    /// verifying it.
+    pub(crate) fn into_module(mut self) -> Module {
+        self.build_global_initializers();
+        self.build_global_finalizers();
 
-            // SAFETY: This pointer won't be used anymore, safe to dispose
-            unsafe { LLVMDisposeMessage(out_message) };
+        if let Some(debug_info) = self.debug_info.take() {
+            debug_info.finalize();
         }
 
         let reference = self.reference;
@@ -250,7 +433,7 @@ impl ModuleBuilder {
         std::mem::swap(&mut global_mappings, &mut self.global_mappings);
 
         // SAFETY: We have ensured that the reference is not owned by this current object
-        Ok((message.trim().to_string(), unsafe {
+        unsafe {
             Module::new(
                 self.id,
                 reference,
@@ -258,23 +441,37 @@ impl ModuleBuilder {
                 self.symbols.clone(),
                 global_mappings,
             )
-        }))
+        }
     }
 
     /// # Panics
-    /// Will panic if the given function is not found in this module
+    /// Will panic if the given function is not found in this module, see
+    /// [`Self::try_get_function`].
     #[must_use]
     pub fn get_function(&self, function: DeclaredFunctionDescriptor) -> FunctionReference<'_> {
-        let value = self.function_values.get(&function).unwrap();
+        self.try_get_function(function).unwrap()
+    }
+
+    /// # Errors
+    /// Will return an error if the function was neither defined in nor imported into this module.
+    pub fn try_get_function(
+        &self,
+        function: DeclaredFunctionDescriptor,
+    ) -> Result<FunctionReference<'_>, Error> {
+        let value = self
+            .function_values
+            .get(&function)
+            .ok_or_else(|| Error::UnknownFunction(self.symbols.resolve(function.name)))?;
 
         // SAFETY: The functions here were transfered from the ModuleBuilder, so we know they
         // belong to this module, so as long as the function reference has a life time at least
         // equivalent to the lifetime of the Module, the value will remain valid
-        unsafe { FunctionReference::new(self, *value, function.r#type) }
+        Ok(unsafe { FunctionReference::new(self, *value, function.r#type) })
     }
 
     /// # Panics
-    /// This function can panic if the `name` cannot be converted into a `CString`
+    /// This function can panic if the `name` cannot be converted into a `CString`, see
+    /// [`Self::try_define_global`].
     pub fn define_global<T: Type>(
         &mut self,
         visibility: Visibility,
@@ -282,10 +479,278 @@ impl ModuleBuilder {
         r#type: T,
         value: Option<&ConstValue>,
     ) -> DeclaredGlobalDescriptor {
+        self.try_define_global(visibility, name, r#type, value)
+            .unwrap()
+    }
+
+    /// # Errors
+    /// Will return an error if the name cannot be converted into a C-string.
+    pub fn try_define_global<T: Type>(
+        &mut self,
+        visibility: Visibility,
+        name: &str,
+        r#type: T,
+        value: Option<&ConstValue>,
+    ) -> Result<DeclaredGlobalDescriptor, Error> {
+        to_c_string(name)?;
+
         let (descriptor, global) = globals::define_global(self, visibility, name, r#type, value);
         self.global_values.insert(descriptor, global);
 
-        descriptor
+        Ok(descriptor)
+    }
+
+    /// # Panics
+    /// Will panic if the name cannot be converted into a C-string, see
+    /// [`Self::try_add_named_metadata`].
+    pub fn add_named_metadata(&mut self, name: &str, node: Metadata) {
+        self.try_add_named_metadata(name, node).unwrap();
+    }
+
+    /// Appends the node to the named metadata of the module, e.g. to pass facts about the module
+    /// to custom passes, creating the named metadata if needed.
+    ///
+    /// # Errors
+    /// Will return an error if the name cannot be converted into a C-string.
+    pub fn try_add_named_metadata(&mut self, name: &str, node: Metadata) -> Result<(), Error> {
+        let name = to_c_string(name)?;
+
+        LLVM_CONTEXT.with(|context| {
+            // SAFETY: The module and the context are valid, the node belongs to the context, and
+            // the name is a valid pointer for the duration of the call
+            unsafe {
+                LLVMAddNamedMetadataOperand(
+                    self.reference,
+                    name.as_ptr(),
+                    LLVMMetadataAsValue(context.as_llvm_ref(), node.as_llvm_ref()),
+                );
+            };
+        });
+
+        Ok(())
+    }
+
+    /// # Panics
+    /// Will panic if the name cannot be converted into a C-string, or the function is not in
+    /// this module, see [`Self::try_define_alias`].
+    pub fn define_alias(
+        &mut self,
+        function: DeclaredFunctionDescriptor,
+        name: &str,
+        visibility: Visibility,
+    ) -> DeclaredFunctionDescriptor {
+        self.try_define_alias(function, name, visibility).unwrap()
+    }
+
+    /// Defines another name for the function, which can have a different visibility, e.g. to
+    /// export one implementation under several names. The function has to be defined in this
+    /// module.
+    ///
+    /// # Errors
+    /// Will return an error if the name cannot be converted into a C-string, or the function is
+    /// not in this module.
+    pub fn try_define_alias(
+        &mut self,
+        function: DeclaredFunctionDescriptor,
+        name: &str,
+        visibility: Visibility,
+    ) -> Result<DeclaredFunctionDescriptor, Error> {
+        to_c_string(name)?;
+        let aliasee = self.try_get_function(function)?.as_llvm_ref();
+
+        let alias = aliases::define_alias(
+            self,
+            aliasee,
+            function.r#type.as_llvm_ref(),
+            name,
+            visibility,
+        );
+        let id = DeclaredFunctionDescriptor {
+            module_id: self.id,
+            name: self.symbols.intern(name),
+            r#type: function.r#type,
+            visibility,
+        };
+        self.function_values.insert(id, alias);
+
+        Ok(id)
+    }
+
+    /// # Panics
+    /// Will panic if the name cannot be converted into a C-string, or the global is not in this
+    /// module, see [`Self::try_define_global_alias`].
+    pub fn define_global_alias(
+        &mut self,
+        global: DeclaredGlobalDescriptor,
+        name: &str,
+        visibility: Visibility,
+    ) -> DeclaredGlobalDescriptor {
+        self.try_define_global_alias(global, name, visibility)
+            .unwrap()
+    }
+
+    /// Defines another name for the global, like [`Self::define_alias`] does for functions.
+    ///
+    /// # Errors
+    /// Will return an error if the name cannot be converted into a C-string, or the global is
+    /// not in this module.
+    pub fn try_define_global_alias(
+        &mut self,
+        global: DeclaredGlobalDescriptor,
+        name: &str,
+        visibility: Visibility,
+    ) -> Result<DeclaredGlobalDescriptor, Error> {
+        to_c_string(name)?;
+        let aliasee = self.try_get_global(global)?.reference;
+
+        let alias =
+            aliases::define_alias(self, aliasee, global.r#type.as_llvm_ref(), name, visibility);
+        let id = DeclaredGlobalDescriptor {
+            module_id: self.id,
+            name: self.symbols.intern(name),
+            r#type: global.r#type,
+            visibility,
+        };
+        self.global_values.insert(id, alias);
+
+        Ok(id)
+    }
+
+    /// # Panics
+    /// Will panic if the name cannot be converted into a C-string, or the resolver is invalid,
+    /// see [`Self::try_define_ifunc`].
+    pub fn define_ifunc(
+        &mut self,
+        declaration: &FunctionSignature,
+        resolver: DeclaredFunctionDescriptor,
+    ) -> DeclaredFunctionDescriptor {
+        self.try_define_ifunc(declaration, resolver).unwrap()
+    }
+
+    /// Defines a function whose implementation is picked when the code is loaded, by calling the
+    /// resolver, which returns a pointer to a function of the declared type, e.g. the one best
+    /// suited for the CPU. The resolver has to be defined in this module.
+    ///
+    /// Ifuncs are only supported on ELF targets.
+    ///
+    /// # Errors
+    /// Will return an error if the name cannot be converted into a C-string, the resolver is not
+    /// in this module, or does not return a pointer.
+    pub fn try_define_ifunc(
+        &mut self,
+        declaration: &FunctionSignature,
+        resolver: DeclaredFunctionDescriptor,
+    ) -> Result<DeclaredFunctionDescriptor, Error> {
+        to_c_string(declaration.name())?;
+        let resolver_value = self.try_get_function(resolver)?.as_llvm_ref();
+
+        // SAFETY: The function type comes from a safe wrapper
+        let returns_pointer = unsafe {
+            LLVMGetTypeKind(LLVMGetReturnType(resolver.r#type.as_llvm_ref()))
+                == LLVMTypeKind::LLVMPointerTypeKind
+        };
+        if !returns_pointer {
+            return Err(Error::ResolverType(self.symbols.resolve(resolver.name)));
+        }
+
+        let (id, ifunc) = aliases::define_ifunc(self, declaration, resolver_value);
+        self.function_values.insert(id, ifunc);
+
+        Ok(id)
+    }
+
+    /// # Panics
+    /// Will panic if the name cannot be converted into a C-string, see
+    /// [`Self::try_define_comdat`].
+    pub fn define_comdat(&mut self, name: &str, selection: ComdatSelection) -> Comdat {
+        self.try_define_comdat(name, selection).unwrap()
+    }
+
+    /// Defines a comdat, or returns the one of the same name, updating its selection. Modules
+    /// defining the same functions and globals can put them into comdats of the same name, and
+    /// only one of the copies gets linked, see [`Self::add_to_comdat`].
+    ///
+    /// Comdats are not supported on Mach-O targets.
+    ///
+    /// # Errors
+    /// Will return an error if the name cannot be converted into a C-string.
+    pub fn try_define_comdat(
+        &mut self,
+        name: &str,
+        selection: ComdatSelection,
+    ) -> Result<Comdat, Error> {
+        let name = to_c_string(name)?;
+
+        // SAFETY: The module is valid, and the name is a valid pointer for the duration of the
+        // call, the comdat is owned by the module
+        let reference = unsafe {
+            let reference = LLVMGetOrInsertComdat(self.reference, name.as_ptr());
+            LLVMSetComdatSelectionKind(reference, selection.as_llvm());
+
+            reference
+        };
+
+        Ok(Comdat {
+            module_id: self.id,
+            reference,
+        })
+    }
+
+    /// # Panics
+    /// Will panic if the comdat or the member are not in this module, see
+    /// [`Self::try_add_to_comdat`].
+    pub fn add_to_comdat(&mut self, comdat: Comdat, member: impl Into<ComdatMember>) {
+        self.try_add_to_comdat(comdat, member).unwrap();
+    }
+
+    /// Adds the definition to the comdat. Exported members get the `weak_odr` linkage, so the
+    /// duplicates don't clash when the modules are linked, while they're still kept if they're
+    /// unused.
+    ///
+    /// # Errors
+    /// Will return an error if the comdat or the member are not in this module.
+    pub fn try_add_to_comdat(
+        &mut self,
+        comdat: Comdat,
+        member: impl Into<ComdatMember>,
+    ) -> Result<(), Error> {
+        if comdat.module_id != self.id {
+            return Err(Error::DifferentModule);
+        }
+
+        let (value, visibility) = match member.into() {
+            ComdatMember::Function(function) => (
+                self.try_get_function(function)?.as_llvm_ref(),
+                function.visibility,
+            ),
+            ComdatMember::Global(global) => {
+                (self.try_get_global(global)?.reference, global.visibility)
+            }
+        };
+
+        // SAFETY: The value and the comdat both belong to this module
+        unsafe {
+            LLVMSetComdat(value, comdat.reference);
+
+            if visibility == Visibility::Export {
+                LLVMSetLinkage(value, LLVMLinkage::LLVMWeakODRLinkage);
+            }
+        };
+
+        Ok(())
+    }
+
+    /// Stores the string, followed by a null byte, in a private constant global, e.g. for a
+    /// message passed to `printf`. Defining the same string again returns the same global.
+    pub fn define_string_constant(&self, value: &str) -> StringConstant {
+        *self
+            .string_constants
+            .borrow_mut()
+            .entry(value.to_string())
+            .or_insert_with(|| StringConstant {
+                pointer: globals::define_string_constant(self, value),
+                len: value.len(),
+            })
     }
 
     fn build_global_initializers(&mut self) {
@@ -314,7 +779,7 @@ impl ModuleBuilder {
                 Visibility::Export,
                 "llvm.global_ctors",
                 initializers_array_type,
-                Some(&initializers_array_type.const_values(&initializer_values)),
+                Some(&initializers_array_type.const_value(&initializer_values)),
             )
         });
 
@@ -352,7 +817,7 @@ impl ModuleBuilder {
                 Visibility::Export,
                 "llvm.global_dtors",
                 finalizers_array_type,
-                Some(&finalizers_array_type.const_values(&finalizer_values)),
+                Some(&finalizers_array_type.const_value(&finalizer_values)),
             )
         });
 
@@ -366,18 +831,30 @@ impl ModuleBuilder {
     }
 
     /// # Panics
-    /// If the provided ID is invalid. This most likely means a bug, since globals cannot be in any
-    /// way removed.
+    /// If the global was neither defined in nor imported into this module, see
+    /// [`Self::try_get_global`].
     #[must_use]
     pub fn get_global(&self, id: DeclaredGlobalDescriptor) -> GlobalReference<'_> {
-        let result = *self.global_values.get(&id).unwrap();
+        self.try_get_global(id).unwrap()
+    }
+
+    /// # Errors
+    /// Will return an error if the global was neither defined in nor imported into this module.
+    pub fn try_get_global(
+        &self,
+        id: DeclaredGlobalDescriptor,
+    ) -> Result<GlobalReference<'_>, Error> {
+        let result = *self
+            .global_values
+            .get(&id)
+            .ok_or_else(|| Error::UnknownGlobal(self.symbols.resolve(id.name)))?;
 
         // SAFETY: the global is connected to the current module, so it is valid
-        GlobalReference {
+        Ok(GlobalReference {
             _module: std::marker::PhantomData,
             reference: result,
             r#type: id.r#type,
-        }
+        })
     }
 }
 
@@ -387,7 +864,68 @@ impl Drop for ModuleBuilder {
             return;
         }
 
+        // The debug info builder refers to the module, so it goes first
+        self.debug_info = None;
+
         // SAFETY: if `reference` is not null, we own the module and are free to dispose it
         unsafe { LLVMDisposeModule(self.reference) };
     }
 }
+
+/// Runs the LLVM verifier on the module, and returns its messages. Diagnostics are taken from the
+/// current thread, so this has to run on the thread that owns the module's context.
+///
+/// # Errors
+/// Will return an error if the module fails verification.
+/// # Panics
+/// If the verifier returns a message that is not valid UTF-8
+pub(crate) fn verify(module: &impl AnyModule, name: &str) -> Result<String, ModuleBuildError> {
+    let mut out_message = std::ptr::null_mut();
+    // SAFETY: We have a valid, non-null module reference, and since the action is
+    // `LLVMReturnStatusAction`, and `out_message` is passed as a pointer to a pointer, so we'll
+    // get a new pointer put into there
+    let verify_result = unsafe {
+        LLVMVerifyModule(
+            module.as_llvm_ref(),
+            LLVMVerifierFailureAction::LLVMReturnStatusAction,
+            &raw mut out_message,
+        )
+    };
+
+    if verify_result != 0 {
+        // SAFETY: We received the message from the verify call above, it must be a valid pointer
+        let message = unsafe { CStr::from_ptr(out_message) }
+            .to_str()
+            .unwrap()
+            .to_string();
+
+        // SAFETY: We made a copy of the message, this pointer won't be used anymore
+        unsafe {
+            LLVMDisposeMessage(out_message);
+        };
+
+        let diagnostics = DIAGNOSTIC_HANDLER.with(DiagnosticHandler::take_diagnostics);
+        let raw_ir = module.dump_ir();
+
+        return Err(ModuleBuildError {
+            module_name: name.to_string(),
+            diagnostics,
+            validation_errors: vec![],
+            verifier_errors: VerifierError::parse(&message, &raw_ir),
+            raw_ir,
+        });
+    }
+
+    let mut message = String::new();
+
+    if !out_message.is_null() {
+        // SAFETY: We've checked that the message was set to something, so it must be a valid
+        // string
+        message = unsafe { CStr::from_ptr(out_message).to_str().unwrap().to_string() };
+
+        // SAFETY: This pointer won't be used anymore, safe to dispose
+        unsafe { LLVMDisposeMessage(out_message) };
+    }
+
+    Ok(message.trim().to_string())
+}
@@ -1,17 +1,33 @@
-use std::{collections::HashMap, error::Error, fmt::Display, rc::Rc};
+use std::{collections::HashMap, ffi::CString, fmt::Display, str::FromStr as _, sync::Arc};
 
 use llvm_sys::{
-    core::LLVMDisposeModule,
+    LLVMLinkage,
+    core::{
+        LLVMCloneModule, LLVMDisposeModule, LLVMGetFirstFunction, LLVMGetLinkage,
+        LLVMGetNamedFunction, LLVMGetNextFunction, LLVMGetValueName2, LLVMGlobalGetValueType,
+        LLVMIsDeclaration,
+    },
     linker::LLVMLinkModules2,
     prelude::{LLVMModuleRef, LLVMValueRef},
 };
 
-use super::{DeclaredFunctionDescriptor, ModuleId};
+use super::{
+    DeclaredFunctionDescriptor, ModuleId,
+    bitcode::{self, BitcodeError},
+    mapping::ModuleMapping,
+};
 use crate::{
-    context::diagnostic::{DIAGNOSTIC_HANDLER, Diagnostic, DiagnosticHandler},
+    Visibility,
+    context::{
+        Context,
+        diagnostic::{DIAGNOSTIC_HANDLER, Diagnostic, DiagnosticHandler, DiagnosticSeverity},
+    },
+    error::Error,
     function::builder::FunctionReference,
     global_symbol::GlobalSymbols,
-    module::AnyModule,
+    module::{AnyModule, AnyModuleExtensions},
+    package::context::PackageContext,
+    types::Function,
 };
 
 #[derive(Debug)]
@@ -21,7 +37,53 @@ pub struct LinkError {
     source_module_name: String,
 }
 
-impl Error for LinkError {}
+impl LinkError {
+    /// The diagnostics LLVM reported while linking the modules.
+    #[must_use]
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// The module that failed to be linked in.
+    #[must_use]
+    pub fn source_module(&self) -> &str {
+        &self.source_module_name
+    }
+
+    /// The module the others are linked into.
+    #[must_use]
+    pub fn target_module(&self) -> &str {
+        &self.target_module_name
+    }
+
+    /// The functions and globals the errors are about, e.g. the ones defined in both modules.
+    /// LLVM quotes them in the messages, like `Linking globals named 'foo': symbol multiply
+    /// defined!`.
+    #[must_use]
+    pub fn symbols(&self) -> Vec<&str> {
+        let mut symbols = vec![];
+
+        for diagnostic in &self.diagnostics {
+            if diagnostic.severity() != DiagnosticSeverity::Error {
+                continue;
+            }
+
+            let symbol = diagnostic
+                .message()
+                .split_once('\'')
+                .and_then(|(_, rest)| rest.split_once('\''))
+                .map(|(symbol, _)| symbol);
+
+            if let Some(symbol) = symbol.filter(|x| !symbols.contains(x)) {
+                symbols.push(symbol);
+            }
+        }
+
+        symbols
+    }
+}
+
+impl std::error::Error for LinkError {}
 
 impl Display for LinkError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -43,7 +105,7 @@ pub struct Module {
     id: ModuleId,
     reference: LLVMModuleRef,
     functions: HashMap<DeclaredFunctionDescriptor, LLVMValueRef>,
-    symbols: Rc<GlobalSymbols>,
+    symbols: Arc<GlobalSymbols>,
     global_mappings: HashMap<String, usize>,
 }
 
@@ -58,7 +120,7 @@ impl Module {
         id: ModuleId,
         reference: *mut llvm_sys::LLVMModule,
         functions: HashMap<DeclaredFunctionDescriptor, LLVMValueRef>,
-        symbols: Rc<GlobalSymbols>,
+        symbols: Arc<GlobalSymbols>,
         global_mappings: HashMap<String, usize>,
     ) -> Self {
         Self {
@@ -70,6 +132,68 @@ impl Module {
         }
     }
 
+    /// # Safety
+    /// The bitcode must have been written by [`Self::write_bitcode`] in the same build of the
+    /// same binary, as the global mappings are stored relative to the binary's addresses. The
+    /// module must be dropped before the `context`.
+    pub(crate) unsafe fn read_bitcode(
+        context: &Context,
+        package_context: &PackageContext,
+        bitcode: &[u8],
+    ) -> Result<Self, BitcodeError> {
+        // SAFETY: The caller guarantees the bitcode comes from this binary, and that the module
+        // won't outlive the context
+        let read = unsafe { bitcode::read(context, bitcode) }?;
+        let symbols = package_context.symbols();
+        let id = ModuleId(package_context.id(), symbols.intern(&read.name));
+        // SAFETY: We've just parsed the module, so it's valid
+        let functions = unsafe { defined_functions(id, read.reference, &symbols) };
+
+        // SAFETY: We've just parsed the module, and nothing else owns it
+        Ok(unsafe { Self::new(id, read.reference, functions, symbols, read.global_mappings) })
+    }
+
+    /// Creates a copy of the module that belongs to the package, along with the mapping of the
+    /// descriptors.
+    pub(crate) fn attach(&self, package_context: &PackageContext) -> (Self, ModuleMapping) {
+        let symbols = package_context.symbols();
+        let id = ModuleId(package_context.id(), symbols.intern(&self.name()));
+        let mapping = ModuleMapping::new(self.id, self.symbols.clone(), id, symbols.clone());
+
+        // SAFETY: The module reference is valid for as long as the module exists
+        let reference = unsafe { LLVMCloneModule(self.reference) };
+
+        let functions = self
+            .functions
+            .keys()
+            .map(|function| {
+                let name = CString::from_str(&self.symbols.resolve(function.name)).unwrap();
+                // SAFETY: The clone is valid, and the name is a valid C-string. The clone has all
+                // the functions of the original module, so the function will be found
+                let value = unsafe { LLVMGetNamedFunction(reference, name.as_ptr()) };
+
+                (mapping.function(*function), value)
+            })
+            .collect();
+
+        // SAFETY: We've just cloned the module, nothing else owns the clone
+        let module = unsafe {
+            Self::new(
+                id,
+                reference,
+                functions,
+                symbols,
+                self.global_mappings.clone(),
+            )
+        };
+
+        (module, mapping)
+    }
+
+    pub(crate) fn write_bitcode(&self) -> Vec<u8> {
+        bitcode::write(self, &self.global_mappings)
+    }
+
     pub(crate) fn link(&mut self, mut module: Self) -> Result<(), LinkError> {
         let reference = module.reference;
 
@@ -93,7 +217,7 @@ impl Module {
         Ok(())
     }
 
-    pub(crate) fn symbols(&self) -> Rc<GlobalSymbols> {
+    pub(crate) fn symbols(&self) -> Arc<GlobalSymbols> {
         self.symbols.clone()
     }
 
@@ -104,20 +228,42 @@ impl Module {
     }
 
     /// # Panics
-    /// If the `FunctionDeclaration` is from another module.
+    /// If the `FunctionDeclaration` is from another module, see [`Self::try_get_function`].
     #[must_use]
     pub fn get_function(&self, id: DeclaredFunctionDescriptor) -> FunctionReference<'_> {
-        assert!(id.module_id == self.id);
+        self.try_get_function(id).unwrap()
+    }
 
-        let function = self.functions.get(&id).unwrap();
+    /// # Errors
+    /// Will return an error if the function is from another module, or was not defined in this
+    /// one.
+    pub fn try_get_function(
+        &self,
+        id: DeclaredFunctionDescriptor,
+    ) -> Result<FunctionReference<'_>, Error> {
+        if id.module_id != self.id {
+            return Err(Error::DifferentModule);
+        }
+
+        // The types of modules read from bitcode are in another context than the descriptor's
+        let (function, value) = self
+            .functions
+            .iter()
+            .find(|(function, _)| function.name == id.name)
+            .ok_or_else(|| Error::UnknownFunction(self.symbols.resolve(id.name)))?;
 
         // SAFETY: We got a reference to the function in the HashMap, so it must be valid
-        unsafe { FunctionReference::new(self, *function, id.r#type) }
+        Ok(unsafe { FunctionReference::new(self, *value, function.r#type) })
     }
 
     pub(crate) fn name(&self) -> String {
         self.symbols.resolve(self.id.1)
     }
+
+    #[must_use]
+    pub fn dump_ir(&self) -> String {
+        AnyModuleExtensions::dump_ir(self)
+    }
 }
 
 impl Drop for Module {
@@ -131,3 +277,53 @@ impl Drop for Module {
         unsafe { LLVMDisposeModule(self.reference) };
     }
 }
+
+/// The descriptors of the functions defined in the module, e.g. one read from bitcode.
+///
+/// # Safety
+/// The module must be valid.
+unsafe fn defined_functions(
+    module_id: ModuleId,
+    module: LLVMModuleRef,
+    symbols: &GlobalSymbols,
+) -> HashMap<DeclaredFunctionDescriptor, LLVMValueRef> {
+    // SAFETY: The caller guarantees the module is valid, and the functions returned by LLVM
+    // belong to it
+    let functions = std::iter::successors(
+        Some(unsafe { LLVMGetFirstFunction(module) }).filter(|function| !function.is_null()),
+        // SAFETY: The function is a valid value from the module
+        |function| Some(unsafe { LLVMGetNextFunction(*function) }).filter(|next| !next.is_null()),
+    );
+
+    functions
+        .filter_map(|function| {
+            // SAFETY: The function is a valid value from the module, LLVM returns its name along
+            // with the length, and its type is a function type
+            unsafe {
+                if LLVMIsDeclaration(function) != 0 {
+                    return None;
+                }
+
+                let mut length = 0;
+                let name = LLVMGetValueName2(function, &raw mut length);
+                let name =
+                    String::from_utf8_lossy(std::slice::from_raw_parts(name.cast::<u8>(), length));
+                let visibility = match LLVMGetLinkage(function) {
+                    LLVMLinkage::LLVMInternalLinkage | LLVMLinkage::LLVMPrivateLinkage => {
+                        Visibility::Internal
+                    }
+                    _ => Visibility::Export,
+                };
+
+                let descriptor = DeclaredFunctionDescriptor {
+                    module_id,
+                    name: symbols.intern(&name),
+                    r#type: Function::from_llvm_ref(LLVMGlobalGetValueType(function)),
+                    visibility,
+                };
+
+                Some((descriptor, function))
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,89 @@
+use std::sync::Arc;
+
+use super::{DeclaredFunctionDescriptor, DeclaredGlobalDescriptor, ModuleId};
+use crate::{
+    error::Error,
+    global_symbol::{GlobalSymbol, GlobalSymbols},
+};
+
+/// Translates the descriptors of a standalone module into the descriptors of its copy attached to
+/// a package.
+pub struct ModuleMapping {
+    from: ModuleId,
+    from_symbols: Arc<GlobalSymbols>,
+    to: ModuleId,
+    to_symbols: Arc<GlobalSymbols>,
+}
+
+impl ModuleMapping {
+    pub(crate) const fn new(
+        from: ModuleId,
+        from_symbols: Arc<GlobalSymbols>,
+        to: ModuleId,
+        to_symbols: Arc<GlobalSymbols>,
+    ) -> Self {
+        Self {
+            from,
+            from_symbols,
+            to,
+            to_symbols,
+        }
+    }
+
+    fn symbol(&self, symbol: GlobalSymbol) -> GlobalSymbol {
+        self.to_symbols.intern(&self.from_symbols.resolve(symbol))
+    }
+
+    /// # Panics
+    /// If the function does not come from the module this mapping was created for, see
+    /// [`Self::try_function`].
+    pub fn function(&self, function: DeclaredFunctionDescriptor) -> DeclaredFunctionDescriptor {
+        self.try_function(function).unwrap()
+    }
+
+    /// # Errors
+    /// Will return an error if the function does not come from the module this mapping was
+    /// created for.
+    pub fn try_function(
+        &self,
+        function: DeclaredFunctionDescriptor,
+    ) -> Result<DeclaredFunctionDescriptor, Error> {
+        if function.module_id != self.from {
+            return Err(Error::DifferentModule);
+        }
+
+        Ok(DeclaredFunctionDescriptor {
+            module_id: self.to,
+            name: self.symbol(function.name),
+            r#type: function.r#type,
+            visibility: function.visibility,
+        })
+    }
+
+    /// # Panics
+    /// If the global does not come from the module this mapping was created for, see
+    /// [`Self::try_global`].
+    #[must_use]
+    pub fn global(&self, global: DeclaredGlobalDescriptor) -> DeclaredGlobalDescriptor {
+        self.try_global(global).unwrap()
+    }
+
+    /// # Errors
+    /// Will return an error if the global does not come from the module this mapping was created
+    /// for.
+    pub fn try_global(
+        &self,
+        global: DeclaredGlobalDescriptor,
+    ) -> Result<DeclaredGlobalDescriptor, Error> {
+        if global.module_id != self.from {
+            return Err(Error::DifferentModule);
+        }
+
+        Ok(DeclaredGlobalDescriptor {
+            module_id: self.to,
+            name: self.symbol(global.name),
+            r#type: global.r#type,
+            visibility: global.visibility,
+        })
+    }
+}
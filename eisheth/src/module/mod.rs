@@ -1,5 +1,7 @@
+pub mod bitcode;
 pub mod builder;
 pub mod built;
+pub mod mapping;
 
 use std::{ffi::CStr, marker::PhantomData};
 
@@ -43,6 +45,16 @@ impl<T: AnyModule> AnyModuleExtensions for T {
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct ModuleId(PackageId, GlobalSymbol);
 
+impl ModuleId {
+    pub(crate) const fn package_id(self) -> PackageId {
+        self.0
+    }
+
+    pub(crate) const fn name(self) -> GlobalSymbol {
+        self.1
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[must_use]
 pub struct DeclaredFunctionDescriptor {
@@ -52,10 +64,22 @@ pub struct DeclaredFunctionDescriptor {
     visibility: Visibility,
 }
 
+/// The address of the function, e.g. to store it as a callback, or return it from an ifunc
+/// resolver.
+impl ValueReference for DeclaredFunctionDescriptor {
+    fn value(&self, module: &builder::ModuleBuilder) -> ConstOrDynamicValue {
+        module.get_function(*self).as_value().into()
+    }
+}
+
 impl DeclaredFunctionDescriptor {
     pub(crate) const fn name(&self) -> GlobalSymbol {
         self.name
     }
+
+    pub(crate) const fn r#type(&self) -> Function {
+        self.r#type
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -73,6 +97,10 @@ impl ValueReference for DeclaredGlobalDescriptor {
 }
 
 impl DeclaredGlobalDescriptor {
+    pub(crate) const fn name(&self) -> GlobalSymbol {
+        self.name
+    }
+
     #[must_use]
     pub const fn r#type(&self) -> OpaqueType {
         self.r#type
@@ -114,3 +142,29 @@ impl ValueReference for GlobalReference<'_> {
         (*self).into()
     }
 }
+
+/// A null-terminated string stored in a private global of the module, see
+/// [`builder::ModuleBuilder::define_string_constant`].
+#[derive(Debug, Clone, Copy)]
+pub struct StringConstant {
+    pointer: ConstValue,
+    len: usize,
+}
+
+impl StringConstant {
+    /// The address of the first byte of the string.
+    pub const fn pointer(&self) -> ConstValue {
+        self.pointer
+    }
+
+    /// The length of the string in bytes, without the null terminator.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
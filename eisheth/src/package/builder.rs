@@ -1,19 +1,36 @@
 use std::{
-    collections::{HashMap, hash_map::Entry},
+    collections::{BTreeMap, HashSet},
     error::Error,
+    ffi::CString,
     fmt::Display,
-    rc::Rc,
+    num::NonZeroUsize,
+    sync::Arc,
 };
 
+use llvm_sys::core::{LLVMGetNamedFunction, LLVMGetNamedGlobal};
 use thiserror::Error;
 
-use super::{Package, context::PackageContext, id::PACKAGE_ID_GENERATOR};
+use super::{
+    Package,
+    context::PackageContext,
+    dependencies::{DependencyError, DependencyGraph, Import},
+    fuel,
+    id::PACKAGE_ID_GENERATOR,
+    optimization::{self, Optimization, OptimizationError, Phase},
+    parallel::{self, ModuleJob, ModuleJobError, ModuleReport},
+};
 use crate::{
+    context::{
+        Context, LLVM_CONTEXT,
+        diagnostic::{DiagnosticScope, DiagnosticSink},
+    },
     global_symbol::GlobalSymbols,
     module::{
-        AnyModuleExtensions,
-        builder::{ModuleBuilder, errors::ModuleBuildError},
-        built::LinkError,
+        AnyModule, DeclaredFunctionDescriptor, DeclaredGlobalDescriptor,
+        bitcode::BitcodeError,
+        builder::{self, ModuleBuilder, errors::ModuleBuildError},
+        built::{LinkError, Module},
+        mapping::ModuleMapping,
     },
 };
 
@@ -21,12 +38,20 @@ use crate::{
 pub enum AddModuleError {
     #[error("Module \"{0}\" already exists in this package")]
     AlreadyExists(String),
+    #[error("{0:?} cannot be converted into a C-string")]
+    InvalidName(String),
+    #[error(transparent)]
+    InvalidBitcode(#[from] BitcodeError),
 }
 
 #[derive(Debug)]
 pub enum PackageBuildError {
-    Link(LinkError),
+    Link(Vec<LinkError>),
     Build(Vec<ModuleBuildError>),
+    Optimization(OptimizationError),
+    Dependencies(Vec<DependencyError>),
+    Bitcode(BitcodeError),
+    Empty,
 }
 
 impl Error for PackageBuildError {}
@@ -34,8 +59,14 @@ impl Error for PackageBuildError {}
 impl Display for PackageBuildError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Link(link_error) => {
-                write!(f, "Link error:\n{link_error}")
+            Self::Link(link_errors) => {
+                writeln!(f, "Link errors:")?;
+
+                for error in link_errors {
+                    write!(f, "{error}")?;
+                }
+
+                Ok(())
             }
             Self::Build(module_build_errors) => {
                 writeln!(f, "Module build errors:")?;
@@ -46,18 +77,37 @@ impl Display for PackageBuildError {
 
                 Ok(())
             }
+            Self::Optimization(optimization_error) => {
+                write!(f, "Optimization error:\n{optimization_error}")
+            }
+            Self::Dependencies(dependency_errors) => {
+                writeln!(f, "Dependency errors:")?;
+
+                for error in dependency_errors {
+                    writeln!(f, "{error}")?;
+                }
+
+                Ok(())
+            }
+            Self::Bitcode(bitcode_error) => {
+                write!(
+                    f,
+                    "Failed to move a module between threads:\n{bitcode_error}"
+                )
+            }
+            Self::Empty => write!(f, "The package contains no modules"),
         }
     }
 }
 
 pub struct PackageBuildResult {
-    messages: HashMap<String, String>,
+    messages: BTreeMap<String, String>,
     package: Package,
 }
 
 impl PackageBuildResult {
     #[must_use]
-    pub const fn messages(&self) -> &HashMap<String, String> {
+    pub const fn messages(&self) -> &BTreeMap<String, String> {
         &self.messages
     }
 
@@ -66,9 +116,74 @@ impl PackageBuildResult {
     }
 }
 
+enum PackageModule {
+    Defined(Box<ModuleBuilder>),
+    /// A copy of a module built outside of the package, see [`PackageBuilder::attach_module`]
+    Attached(Module),
+    /// Already built module, e.g. one read from bitcode, which is linked in as-is
+    Prebuilt(Module),
+}
+
+impl PackageModule {
+    fn name(&self) -> String {
+        match self {
+            Self::Defined(module_builder) => module_builder.name(),
+            Self::Attached(module) | Self::Prebuilt(module) => module.name(),
+        }
+    }
+
+    /// Verifies the modules defined in the package, in the context they were built in, and
+    /// writes the module for the workers.
+    fn into_job(self) -> Result<ModuleJob, ModuleBuildError> {
+        let (module, verify, optimize) = match self {
+            Self::Defined(module_builder) => (module_builder.into_module(), true, true),
+            Self::Attached(module) => (module, false, true),
+            Self::Prebuilt(module) => (module, false, false),
+        };
+        let name = module.name();
+
+        let messages = if verify {
+            builder::verify(&module, &name)?
+        } else {
+            String::new()
+        };
+
+        Ok(ModuleJob {
+            name,
+            bitcode: module.write_bitcode(),
+            messages,
+            optimize,
+        })
+    }
+
+    fn contains_symbol(&self, name: &str) -> bool {
+        let reference = match self {
+            Self::Defined(module_builder) => module_builder.as_llvm_ref(),
+            Self::Attached(module) | Self::Prebuilt(module) => module.as_llvm_ref(),
+        };
+        let Ok(name) = CString::new(name) else {
+            return false;
+        };
+
+        // SAFETY: The module is valid for as long as the wrapper exists, the name is a valid
+        // null-terminated string
+        unsafe {
+            !LLVMGetNamedFunction(reference, name.as_ptr()).is_null()
+                || !LLVMGetNamedGlobal(reference, name.as_ptr()).is_null()
+        }
+    }
+}
+
 pub struct PackageBuilder {
     context: PackageContext,
-    modules: HashMap<String, ModuleBuilder>,
+    modules: Vec<PackageModule>,
+    optimization: Optimization,
+    link_time_optimization: Optimization,
+    preserved_symbols: HashSet<String>,
+    diagnostic_sink: Option<Arc<dyn DiagnosticSink>>,
+    debug_info: bool,
+    fuel: bool,
+    bitcode_per_module: bool,
 }
 
 impl Default for PackageBuilder {
@@ -82,41 +197,307 @@ impl PackageBuilder {
         Self {
             context: PackageContext::new(
                 PACKAGE_ID_GENERATOR.next(),
-                Rc::new(GlobalSymbols::new()),
+                Arc::new(GlobalSymbols::new()),
             ),
-            modules: HashMap::new(),
+            modules: vec![],
+            optimization: Optimization::default(),
+            link_time_optimization: Optimization::default(),
+            preserved_symbols: HashSet::new(),
+            diagnostic_sink: None,
+            debug_info: false,
+            fuel: false,
+            bitcode_per_module: false,
+        }
+    }
+
+    /// Sets the optimization pipeline that will be ran on each of the modules built by this
+    /// package. Modules added with [`Self::add_bitcode_module`] are not optimized again. The
+    /// internal functions and globals nothing in their module refers to get removed, unless
+    /// they're kept with [`Self::preserve_function`] or [`Self::preserve_global`].
+    #[must_use]
+    pub fn optimization(mut self, optimization: Optimization) -> Self {
+        self.optimization = optimization;
+        self
+    }
+
+    /// Enables the optimization of the whole package after the modules are linked, which allows
+    /// for inlining across modules. The presets use the LTO variants of the pipelines.
+    ///
+    /// Before the pipeline runs, all the functions and globals are made internal, unless they're
+    /// marked with [`Self::preserve_function`] or [`Self::preserve_global`], so anything that
+    /// will be used from outside the package (e.g. with [`crate::jit::Jit::get_function`]) must
+    /// be preserved.
+    #[must_use]
+    pub fn link_time_optimization(mut self, optimization: Optimization) -> Self {
+        self.link_time_optimization = optimization;
+        self
+    }
+
+    /// Forwards all the diagnostics LLVM reports while the package is built to the sink, including
+    /// the warnings and remarks that don't make the build fail. The diagnostics of a failed build
+    /// are still returned with its error.
+    #[must_use]
+    pub fn diagnostic_sink(mut self, sink: impl DiagnosticSink + 'static) -> Self {
+        self.diagnostic_sink = Some(Arc::new(sink));
+        self
+    }
+
+    /// Makes the modules defined with [`crate::define_module`] emit debug info, with the file they
+    /// are defined in as the source. Modules added with [`Self::add_module`] enable it themselves,
+    /// with [`ModuleBuilder::enable_debug_info`], as only the caller knows their source.
+    #[must_use]
+    pub const fn debug_info(mut self, enabled: bool) -> Self {
+        self.debug_info = enabled;
+        self
+    }
+
+    #[must_use]
+    pub const fn is_debug_info_enabled(&self) -> bool {
+        self.debug_info
+    }
+
+    /// Makes the code consume fuel on every function call and loop iteration, and trap once it
+    /// runs out, to limit how long untrusted code can run, see [`crate::jit::Jit::set_fuel`]. The
+    /// instrumentation is added after the modules are linked, before the link-time optimization.
+    #[must_use]
+    pub const fn fuel(mut self, enabled: bool) -> Self {
+        self.fuel = enabled;
+        self
+    }
+
+    /// Keeps the bitcode of each of the modules, from before they're linked, in
+    /// [`super::Package::bitcode_per_module`], e.g. to cache them.
+    #[must_use]
+    pub const fn bitcode_per_module(mut self, enabled: bool) -> Self {
+        self.bitcode_per_module = enabled;
+        self
+    }
+
+    /// Keeps the function visible outside of the package, e.g. to the JIT, through both the
+    /// optimization of the modules and the link-time optimization, even if nothing in the
+    /// package calls it, and it's internal.
+    pub fn preserve_function(&mut self, function: DeclaredFunctionDescriptor) {
+        self.preserved_symbols
+            .insert(self.context.symbols().resolve(function.name()));
+    }
+
+    /// Keeps the global visible outside of the package, like [`Self::preserve_function`].
+    pub fn preserve_global(&mut self, global: DeclaredGlobalDescriptor) {
+        self.preserved_symbols
+            .insert(self.context.symbols().resolve(global.name()));
+    }
+
+    fn contains_module(&self, name: &str) -> bool {
+        self.modules.iter().any(|module| module.name() == name)
+    }
+
+    /// The graph of imports between the modules added so far. Imports of modules added from
+    /// bitcode are not known, so those modules have no dependencies in the graph.
+    #[must_use]
+    pub fn dependency_graph(&self) -> DependencyGraph {
+        let mut graph = DependencyGraph::default();
+
+        for module in &self.modules {
+            let name = module.name();
+            graph.add_module(name.clone());
+
+            if let PackageModule::Defined(module_builder) = module {
+                for (dependency, symbol) in module_builder.imports() {
+                    graph.add_import(
+                        &name,
+                        Import {
+                            module: dependency,
+                            symbol,
+                        },
+                    );
+                }
+            }
         }
+
+        graph
+    }
+
+    fn check_dependencies(&self) -> Vec<DependencyError> {
+        let graph = self.dependency_graph();
+        let mut errors = vec![];
+
+        for module in graph.modules() {
+            for import in graph.imports(module) {
+                let dependency = self
+                    .modules
+                    .iter()
+                    .find(|dependency| dependency.name() == import.module);
+
+                let reason = match dependency {
+                    None => "is not a part of the package",
+                    Some(dependency) if !dependency.contains_symbol(&import.symbol) => {
+                        "does not contain it"
+                    }
+                    Some(_) => continue,
+                };
+
+                errors.push(DependencyError::UnresolvedImport {
+                    module: module.clone(),
+                    dependency: import.module.clone(),
+                    symbol: import.symbol.clone(),
+                    reason,
+                });
+            }
+        }
+
+        errors.extend(graph.cycles().into_iter().map(DependencyError::Cycle));
+
+        errors
     }
 
     /// # Errors
-    /// Will return an error if the package already contains a module with the name given.
+    /// Will return an error if the package already contains a module with the name given, or the
+    /// name cannot be converted into a C-string.
     pub fn add_module(
         &mut self,
         name: impl Into<String>,
     ) -> Result<&mut ModuleBuilder, AddModuleError> {
         let name: String = name.into();
-        let entry = self.modules.entry(name.clone());
 
-        if let Entry::Occupied(_) = entry {
+        if self.contains_module(&name) {
+            return Err(AddModuleError::AlreadyExists(name));
+        }
+
+        let module_builder = ModuleBuilder::new(&self.context, &name)
+            .map_err(|_| AddModuleError::InvalidName(name))?;
+        self.modules
+            .push(PackageModule::Defined(Box::new(module_builder)));
+
+        let Some(PackageModule::Defined(module_builder)) = self.modules.last_mut() else {
+            unreachable!("the module was just added");
+        };
+
+        Ok(module_builder)
+    }
+
+    /// Returns a module that was previously added with [`Self::add_module`].
+    pub fn module_mut(&mut self, name: &str) -> Option<&mut ModuleBuilder> {
+        self.modules.iter_mut().find_map(|module| match module {
+            PackageModule::Defined(module_builder) if module_builder.name() == name => {
+                Some(&mut **module_builder)
+            }
+            _ => None,
+        })
+    }
+
+    /// Adds a copy of a module built with [`ModuleBuilder::standalone`] to the package. The
+    /// returned mapping translates the module's descriptors into ones that can be used within
+    /// this package, e.g. for imports. The module will be optimized along with the other modules
+    /// of the package.
+    ///
+    /// # Errors
+    /// Will return an error if the package already contains a module with the same name.
+    pub fn attach_module(&mut self, module: &Module) -> Result<ModuleMapping, AddModuleError> {
+        let name = module.name();
+
+        if self.contains_module(&name) {
+            return Err(AddModuleError::AlreadyExists(name));
+        }
+
+        let (module, mapping) = module.attach(&self.context);
+        self.modules.push(PackageModule::Attached(module));
+
+        Ok(mapping)
+    }
+
+    /// Adds an already built module, e.g. one of [`super::Package::bitcode_per_module`], which
+    /// will be linked into the package as-is.
+    ///
+    /// # Errors
+    /// Will return an error if the bitcode cannot be parsed, it calls runtime functions and was
+    /// written by another build of the binary, or the package already contains a module with the
+    /// same name.
+    /// # Safety
+    /// The bitcode must have been written by this library. The addresses of runtime functions
+    /// are stored relative to the binary, along with a fingerprint of the build, which is a hash,
+    /// so the code must not come from an untrusted source.
+    pub unsafe fn add_bitcode_module(&mut self, bitcode: &[u8]) -> Result<(), AddModuleError> {
+        let module = LLVM_CONTEXT.with(|context| {
+            // SAFETY: The caller guarantees the bitcode comes from this binary. The module is in
+            // the thread's context, like the ones created with `Self::add_module`
+            unsafe { Module::read_bitcode(context, &self.context, bitcode) }
+        })?;
+        let name = module.name();
+
+        if self.contains_module(&name) {
             return Err(AddModuleError::AlreadyExists(name));
         }
 
-        Ok(entry.or_insert_with(|| ModuleBuilder::new(&self.context, &name)))
+        self.modules.push(PackageModule::Prebuilt(module));
+
+        Ok(())
+    }
+
+    /// Checks everything that can be checked before the modules are handed over to LLVM.
+    fn check_modules(&self) -> Result<(), PackageBuildError> {
+        if self.modules.is_empty() {
+            return Err(PackageBuildError::Empty);
+        }
+
+        let dependency_errors = self.check_dependencies();
+        if !dependency_errors.is_empty() {
+            return Err(PackageBuildError::Dependencies(dependency_errors));
+        }
+
+        let validation_errors: Vec<_> = self
+            .modules
+            .iter()
+            .filter_map(|module| match module {
+                PackageModule::Defined(module_builder) => module_builder.check_validation().err(),
+                PackageModule::Attached(_) | PackageModule::Prebuilt(_) => None,
+            })
+            .collect();
+        if !validation_errors.is_empty() {
+            return Err(PackageBuildError::Build(validation_errors));
+        }
+
+        Ok(())
     }
 
+    /// Builds the package, optimizing the modules on as many threads as are available. See
+    /// [`Self::build_parallel`].
+    ///
     /// # Errors
-    /// This will error out and return the error for the first module that fails to build.
-    /// # Panics
-    /// If there are no modules in the package
+    /// Will return an error if there are no modules, any of the imports cannot be resolved, the
+    /// modules depend on each other in a cycle, any of the modules fail verification, or
+    /// optimization or linking fail.
     pub fn build(self) -> Result<PackageBuildResult, PackageBuildError> {
-        let module_build_results = self.modules.into_values().map(ModuleBuilder::build);
+        let threads = std::thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
 
+        self.build_parallel(threads)
+    }
+
+    /// Builds the package, verifying the modules on this thread, then optimizing them on up to
+    /// `threads` threads, each with its own LLVM context. The modules are then linked in the
+    /// order they were added in, into the first one, so the result is the same regardless of the
+    /// number of threads.
+    ///
+    /// # Errors
+    /// Will return an error if there are no modules, any of the imports cannot be resolved, or the
+    /// modules depend on each other in a cycle. Otherwise, this will return the errors of all the
+    /// modules that fail to build, the first error of the optimization, or the errors of all the
+    /// modules that fail to link. Moving the modules between the threads as bitcode is not
+    /// expected to fail, but is reported as an error as well.
+    pub fn build_parallel(
+        mut self,
+        threads: NonZeroUsize,
+    ) -> Result<PackageBuildResult, PackageBuildError> {
+        self.check_modules()?;
+
+        let mut jobs = vec![];
         let mut module_build_errors = vec![];
-        let mut built_modules = vec![];
 
-        for module_build_result in module_build_results {
-            match module_build_result {
-                Ok(module) => built_modules.push(module),
+        for module in std::mem::take(&mut self.modules) {
+            let _diagnostic_scope =
+                DiagnosticScope::new(&module.name(), self.diagnostic_sink.clone());
+
+            match module.into_job() {
+                Ok(job) => jobs.push(job),
                 Err(error) => module_build_errors.push(error),
             }
         }
@@ -125,30 +506,155 @@ impl PackageBuilder {
             return Err(PackageBuildError::Build(module_build_errors));
         }
 
-        let ir_per_module: HashMap<String, String> = built_modules
-            .iter()
-            .map(|(_, x)| (x.name(), x.dump_ir()))
-            .collect();
+        let context = Context::new();
+
+        // SAFETY: The modules end up in the package along with the context, or are dropped with
+        // the error before it
+        let results = unsafe { self.process_jobs(&jobs, &context, threads) };
 
-        let (final_module_messages, mut final_module) = built_modules
-            .pop()
-            .expect("package should contain at least a single module");
+        let mut built_modules = vec![];
 
-        let mut messages = HashMap::new();
-        if !final_module_messages.is_empty() {
-            messages.insert(final_module.name(), final_module_messages);
+        for (job, result) in jobs.into_iter().zip(results) {
+            match result {
+                Ok((report, module)) => built_modules.push((job.name, report, module)),
+                Err(ModuleJobError::Optimization(error)) => {
+                    return Err(PackageBuildError::Optimization(error));
+                }
+                Err(ModuleJobError::Bitcode(error)) => {
+                    return Err(PackageBuildError::Bitcode(error));
+                }
+            }
         }
 
-        for (module_messages, module) in built_modules {
-            if !module_messages.is_empty() {
-                messages.insert(module.name(), module_messages);
+        let mut messages = BTreeMap::new();
+        let mut ir_per_module = BTreeMap::new();
+        let mut optimized_ir_per_module = BTreeMap::new();
+        let mut bitcode_per_module = BTreeMap::new();
+
+        let mut final_module: Option<Module> = None;
+        let mut link_errors = vec![];
+
+        for (name, report, module) in built_modules {
+            let _diagnostic_scope = DiagnosticScope::new(&name, self.diagnostic_sink.clone());
+
+            if !report.messages.is_empty() {
+                messages.insert(name.clone(), report.messages);
+            }
+            if let Some(optimized_ir) = report.optimized_ir {
+                optimized_ir_per_module.insert(name.clone(), optimized_ir);
+            }
+            if self.bitcode_per_module {
+                bitcode_per_module.insert(name.clone(), module.write_bitcode());
             }
-            final_module.link(module).map_err(PackageBuildError::Link)?;
+            ir_per_module.insert(name, report.ir);
+
+            match &mut final_module {
+                Some(final_module) => {
+                    if let Err(error) = final_module.link(module) {
+                        link_errors.push(error);
+                    }
+                }
+                None => final_module = Some(module),
+            }
+        }
+
+        if !link_errors.is_empty() {
+            return Err(PackageBuildError::Link(link_errors));
+        }
+
+        let Some(final_module) = final_module else {
+            return Err(PackageBuildError::Empty);
+        };
+
+        let mut preserved_symbols = self.preserved_symbols;
+
+        if self.fuel {
+            fuel::instrument(&final_module);
+            preserved_symbols.insert(fuel::FUEL_GLOBAL_NAME.to_string_lossy().into_owned());
+        }
+
+        if self.link_time_optimization.is_enabled() {
+            let _diagnostic_scope =
+                DiagnosticScope::new(&final_module.name(), self.diagnostic_sink.clone());
+
+            optimization::internalize(&final_module, &preserved_symbols);
+            optimization::optimize(
+                &final_module,
+                &final_module.name(),
+                &self.link_time_optimization,
+                Phase::LinkTime,
+                &preserved_symbols,
+            )
+            .map_err(PackageBuildError::Optimization)?;
         }
 
         Ok(PackageBuildResult {
             messages,
-            package: Package::new(final_module, ir_per_module),
+            // SAFETY: All the modules were parsed in the context, and linked into the final one
+            package: unsafe {
+                Package::new(
+                    final_module,
+                    context,
+                    ir_per_module,
+                    optimized_ir_per_module,
+                    bitcode_per_module,
+                )
+            },
         })
     }
+
+    /// Optimizes the modules, and reads them into the package's `context`.
+    ///
+    /// # Safety
+    /// The returned modules must be dropped before the `context`.
+    unsafe fn process_jobs(
+        &self,
+        jobs: &[ModuleJob],
+        context: &Context,
+        threads: NonZeroUsize,
+    ) -> Vec<Result<(ModuleReport, Module), ModuleJobError>> {
+        // A single thread processes the modules in the package's context, where they get linked,
+        // instead of moving them back from the context of a worker as bitcode
+        if threads.get().min(jobs.len()) <= 1 {
+            jobs.iter()
+                .map(|job| {
+                    let _diagnostic_scope =
+                        DiagnosticScope::new(&job.name, self.diagnostic_sink.clone());
+                    // SAFETY: The bitcode was just written by the package builder, and the caller
+                    // guarantees the module won't outlive the context
+                    let module =
+                        unsafe { Module::read_bitcode(context, &self.context, &job.bitcode) }
+                            .map_err(ModuleJobError::Bitcode)?;
+                    let report = parallel::process_module(
+                        &module,
+                        job,
+                        &self.optimization,
+                        &self.preserved_symbols,
+                    )?;
+
+                    Ok((report, module))
+                })
+                .collect()
+        } else {
+            parallel::run(
+                jobs,
+                &self.optimization,
+                &self.preserved_symbols,
+                self.diagnostic_sink.as_ref(),
+                threads,
+            )
+            .into_iter()
+            .map(|output| {
+                let output = output?;
+                // SAFETY: The bitcode was just written by a worker, and the caller guarantees the
+                // module won't outlive the context
+                let module =
+                    unsafe { Module::read_bitcode(context, &self.context, &output.bitcode) }
+                        .map_err(ModuleJobError::Bitcode)?;
+
+                Ok((output.report, module))
+            })
+            .collect()
+        }
+    }
 }
@@ -1,4 +1,4 @@
-use std::rc::Rc;
+use std::sync::Arc;
 
 use super::id::PackageId;
 use crate::global_symbol::GlobalSymbols;
@@ -6,15 +6,15 @@ use crate::global_symbol::GlobalSymbols;
 #[derive(Clone)]
 pub struct PackageContext {
     id: PackageId,
-    symbols: Rc<GlobalSymbols>,
+    symbols: Arc<GlobalSymbols>,
 }
 
 impl PackageContext {
-    pub const fn new(id: PackageId, symbols: Rc<GlobalSymbols>) -> Self {
+    pub const fn new(id: PackageId, symbols: Arc<GlobalSymbols>) -> Self {
         Self { id, symbols }
     }
 
-    pub fn symbols(&self) -> Rc<GlobalSymbols> {
+    pub fn symbols(&self) -> Arc<GlobalSymbols> {
         self.symbols.clone()
     }
 
@@ -0,0 +1,138 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write as _,
+};
+
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Import {
+    pub module: String,
+    pub symbol: String,
+}
+
+#[derive(Debug, Error)]
+pub enum DependencyError {
+    #[error("Module \"{module}\" imports \"{symbol}\" from \"{dependency}\", which {reason}")]
+    UnresolvedImport {
+        module: String,
+        dependency: String,
+        symbol: String,
+        reason: &'static str,
+    },
+    #[error("Modules have a cyclic dependency: {}", .0.join(" -> "))]
+    Cycle(Vec<String>),
+}
+
+/// The modules of a package, and the functions and globals they import from each other.
+#[derive(Debug, Default, Clone)]
+pub struct DependencyGraph {
+    modules: Vec<String>,
+    imports: BTreeMap<String, BTreeSet<Import>>,
+}
+
+impl DependencyGraph {
+    pub(crate) fn add_module(&mut self, name: String) {
+        self.imports.entry(name.clone()).or_default();
+        self.modules.push(name);
+    }
+
+    pub(crate) fn add_import(&mut self, module: &str, import: Import) {
+        self.imports
+            .entry(module.to_string())
+            .or_default()
+            .insert(import);
+    }
+
+    /// The modules, in the order they were added to the package.
+    #[must_use]
+    pub fn modules(&self) -> &[String] {
+        &self.modules
+    }
+
+    /// All the items imported by the module.
+    pub fn imports(&self, module: &str) -> impl Iterator<Item = &Import> {
+        self.imports.get(module).into_iter().flatten()
+    }
+
+    /// The names of the modules this module imports from.
+    #[must_use]
+    pub fn dependencies(&self, module: &str) -> BTreeSet<&str> {
+        self.imports(module)
+            .map(|import| import.module.as_str())
+            .collect()
+    }
+
+    /// Finds the cycles, each of those is returned as a path starting and ending with the same
+    /// module.
+    #[must_use]
+    pub fn cycles(&self) -> Vec<Vec<String>> {
+        #[derive(Clone, Copy, PartialEq, Eq)]
+        enum State {
+            Visiting,
+            Done,
+        }
+
+        fn visit<'a>(
+            graph: &'a DependencyGraph,
+            module: &'a str,
+            states: &mut BTreeMap<&'a str, State>,
+            path: &mut Vec<&'a str>,
+            cycles: &mut Vec<Vec<String>>,
+        ) {
+            match states.get(module) {
+                Some(State::Done) => return,
+                Some(State::Visiting) => {
+                    let start = path.iter().position(|x| *x == module).unwrap();
+                    let mut cycle: Vec<String> =
+                        path[start..].iter().map(ToString::to_string).collect();
+                    cycle.push(module.to_string());
+                    cycles.push(cycle);
+
+                    return;
+                }
+                None => {}
+            }
+
+            states.insert(module, State::Visiting);
+            path.push(module);
+
+            for dependency in graph.dependencies(module) {
+                visit(graph, dependency, states, path, cycles);
+            }
+
+            path.pop();
+            states.insert(module, State::Done);
+        }
+
+        let mut states = BTreeMap::new();
+        let mut cycles = vec![];
+
+        for module in &self.modules {
+            visit(self, module, &mut states, &mut vec![], &mut cycles);
+        }
+
+        cycles
+    }
+
+    /// Renders the graph in the graphviz DOT format, with an edge from each module to each of its
+    /// dependencies.
+    #[must_use]
+    pub fn to_dot(&self) -> String {
+        let mut result = String::from("digraph package {\n");
+
+        for module in &self.modules {
+            let _ = writeln!(result, "    {module:?};");
+        }
+
+        for module in &self.modules {
+            for dependency in self.dependencies(module) {
+                let _ = writeln!(result, "    {module:?} -> {dependency:?};");
+            }
+        }
+
+        result.push_str("}\n");
+
+        result
+    }
+}
@@ -0,0 +1,159 @@
+use std::ffi::CStr;
+
+use llvm_sys::{
+    LLVMAtomicOrdering, LLVMAtomicRMWBinOp, LLVMIntPredicate, LLVMLinkage,
+    core::{
+        LLVMAddFunction, LLVMAddGlobal, LLVMAppendBasicBlockInContext, LLVMBuildAtomicRMW,
+        LLVMBuildCall2, LLVMBuildCondBr, LLVMBuildICmp, LLVMBuildRetVoid, LLVMBuildUnreachable,
+        LLVMConstInt, LLVMCreateBuilderInContext, LLVMDisposeBuilder, LLVMFunctionType,
+        LLVMGetBasicBlockTerminator, LLVMGetFirstBasicBlock, LLVMGetFirstFunction,
+        LLVMGetFirstInstruction, LLVMGetIntrinsicDeclaration, LLVMGetModuleContext,
+        LLVMGetNextBasicBlock, LLVMGetNextFunction, LLVMGetNumSuccessors, LLVMGetSuccessor,
+        LLVMInt64TypeInContext, LLVMIntrinsicGetType, LLVMIsDeclaration, LLVMLookupIntrinsicID,
+        LLVMPositionBuilderAtEnd, LLVMPositionBuilderBefore, LLVMSetInitializer, LLVMSetLinkage,
+        LLVMVoidTypeInContext,
+    },
+    prelude::{LLVMBasicBlockRef, LLVMBuilderRef, LLVMModuleRef, LLVMTypeRef, LLVMValueRef},
+};
+
+use crate::module::AnyModule;
+
+/// The counter of the remaining fuel, decremented by the instrumented code.
+pub const FUEL_GLOBAL_NAME: &CStr = c"eisheth.fuel";
+
+const CONSUME_FUEL_FUNCTION_NAME: &CStr = c"eisheth.consume_fuel";
+
+/// Makes every function of the module consume a unit of fuel on entry, and on every branch back
+/// to an earlier block, which every loop contains, and trap once the fuel runs out.
+///
+/// The fuel starts out unlimited, and is set through [`FUEL_GLOBAL_NAME`], see
+/// [`crate::jit::Jit::set_fuel`].
+pub fn instrument(module: &dyn AnyModule) {
+    let module = module.as_llvm_ref();
+
+    // SAFETY: The module is valid for as long as it exists, everything is created in its
+    // context, and the builder is disposed once we're done
+    unsafe {
+        let context = LLVMGetModuleContext(module);
+        let builder = LLVMCreateBuilderInContext(context);
+
+        let functions: Vec<_> = std::iter::successors(
+            Some(LLVMGetFirstFunction(module)).filter(|function| !function.is_null()),
+            |function| Some(LLVMGetNextFunction(*function)).filter(|next| !next.is_null()),
+        )
+        .filter(|function| LLVMIsDeclaration(*function) == 0)
+        .collect();
+
+        let consume_fuel_type =
+            LLVMFunctionType(LLVMVoidTypeInContext(context), [].as_mut_ptr(), 0, 0);
+        let consume_fuel = define_consume_fuel(module, builder, consume_fuel_type);
+
+        for function in functions {
+            let blocks: Vec<_> = std::iter::successors(
+                Some(LLVMGetFirstBasicBlock(function)).filter(|block| !block.is_null()),
+                |block| Some(LLVMGetNextBasicBlock(*block)).filter(|next| !next.is_null()),
+            )
+            .collect();
+
+            let Some(entry) = blocks.first() else {
+                continue;
+            };
+
+            LLVMPositionBuilderBefore(builder, LLVMGetFirstInstruction(*entry));
+            call(builder, consume_fuel_type, consume_fuel);
+
+            for (index, block) in blocks.iter().enumerate() {
+                if branches_back(*block, &blocks[..=index]) {
+                    LLVMPositionBuilderBefore(builder, LLVMGetBasicBlockTerminator(*block));
+                    call(builder, consume_fuel_type, consume_fuel);
+                }
+            }
+        }
+
+        LLVMDisposeBuilder(builder);
+    }
+}
+
+/// Defines the fuel counter, and the function decrementing it, which traps once it runs out.
+///
+/// # Safety
+/// The module and the builder must be valid, and belong to the same context.
+unsafe fn define_consume_fuel(
+    module: LLVMModuleRef,
+    builder: LLVMBuilderRef,
+    r#type: LLVMTypeRef,
+) -> LLVMValueRef {
+    // SAFETY: The caller guarantees the module and the builder are valid, everything else is
+    // created here
+    unsafe {
+        let context = LLVMGetModuleContext(module);
+        let i64_type = LLVMInt64TypeInContext(context);
+
+        let fuel = LLVMAddGlobal(module, i64_type, FUEL_GLOBAL_NAME.as_ptr());
+        LLVMSetInitializer(fuel, LLVMConstInt(i64_type, i64::MAX.cast_unsigned(), 0));
+
+        let function = LLVMAddFunction(module, CONSUME_FUEL_FUNCTION_NAME.as_ptr(), r#type);
+        LLVMSetLinkage(function, LLVMLinkage::LLVMInternalLinkage);
+
+        let entry = LLVMAppendBasicBlockInContext(context, function, c"entry".as_ptr());
+        let exhausted = LLVMAppendBasicBlockInContext(context, function, c"exhausted".as_ptr());
+        let done = LLVMAppendBasicBlockInContext(context, function, c"done".as_ptr());
+
+        // The watchdog exhausts the fuel from another thread, so the decrement has to be atomic
+        LLVMPositionBuilderAtEnd(builder, entry);
+        let previous = LLVMBuildAtomicRMW(
+            builder,
+            LLVMAtomicRMWBinOp::LLVMAtomicRMWBinOpSub,
+            fuel,
+            LLVMConstInt(i64_type, 1, 0),
+            LLVMAtomicOrdering::LLVMAtomicOrderingMonotonic,
+            0,
+        );
+        let is_exhausted = LLVMBuildICmp(
+            builder,
+            LLVMIntPredicate::LLVMIntSLE,
+            previous,
+            LLVMConstInt(i64_type, 0, 0),
+            c"is_exhausted".as_ptr(),
+        );
+        LLVMBuildCondBr(builder, is_exhausted, exhausted, done);
+
+        let trap_name = c"llvm.trap";
+        let trap_id = LLVMLookupIntrinsicID(trap_name.as_ptr(), trap_name.count_bytes());
+        let trap = LLVMGetIntrinsicDeclaration(module, trap_id, [].as_mut_ptr(), 0);
+        let trap_type = LLVMIntrinsicGetType(context, trap_id, [].as_mut_ptr(), 0);
+
+        LLVMPositionBuilderAtEnd(builder, exhausted);
+        call(builder, trap_type, trap);
+        LLVMBuildUnreachable(builder);
+
+        LLVMPositionBuilderAtEnd(builder, done);
+        LLVMBuildRetVoid(builder);
+
+        function
+    }
+}
+
+/// Whether the block branches to itself or any of the blocks before it.
+///
+/// # Safety
+/// The block must be valid, and terminated.
+unsafe fn branches_back(block: LLVMBasicBlockRef, earlier_blocks: &[LLVMBasicBlockRef]) -> bool {
+    // SAFETY: The caller guarantees the block is valid and terminated, and the successors are
+    // within the bounds LLVM returns
+    unsafe {
+        let terminator = LLVMGetBasicBlockTerminator(block);
+
+        !terminator.is_null()
+            && (0..LLVMGetNumSuccessors(terminator))
+                .any(|index| earlier_blocks.contains(&LLVMGetSuccessor(terminator, index)))
+    }
+}
+
+/// # Safety
+/// The builder must be positioned, and the function must be of the given type.
+unsafe fn call(builder: LLVMBuilderRef, r#type: LLVMTypeRef, function: LLVMValueRef) {
+    // SAFETY: The caller guarantees the references are valid, and the function takes no
+    // arguments
+    unsafe { LLVMBuildCall2(builder, r#type, function, [].as_mut_ptr(), 0, c"".as_ptr()) };
+}
@@ -1,41 +1,153 @@
 pub mod builder;
 pub(crate) mod context;
+pub mod dependencies;
+pub(crate) mod fuel;
 pub(crate) mod id;
+pub mod optimization;
+pub(crate) mod parallel;
 
-use std::{collections::HashMap, rc::Rc};
+use std::{collections::BTreeMap, sync::Arc};
+
+use llvm_sys::target_machine::LLVMCodeGenFileType;
 
 use super::{global_symbol::GlobalSymbols, module::built::Module};
-use crate::module::AnyModuleExtensions;
+use crate::{
+    context::Context,
+    module::bitcode::BitcodeError,
+    package::{context::PackageContext, id::PACKAGE_ID_GENERATOR},
+    target_machine::{EmitError, TargetMachine},
+};
 
+/// The linked modules, which can be moved to another thread, e.g. to be JIT-ed there.
 #[must_use]
 pub struct Package {
+    // The module has to be dropped before the context it was created in
     module: Module,
-    ir_per_module: HashMap<String, String>,
+    context: Context,
+    ir_per_module: BTreeMap<String, String>,
+    optimized_ir_per_module: BTreeMap<String, String>,
+    bitcode_per_module: BTreeMap<String, Vec<u8>>,
 }
 
+#[expect(
+    clippy::non_send_fields_in_send_ty,
+    reason = "The LLVM handles are only ever used by the thread owning the package"
+)]
+// SAFETY: LLVM objects may be used from any thread, as long as only one thread at a time uses
+// a context and everything created in it. The package exclusively owns its context, which is
+// created by `Context::new` rather than being the thread-local one, so moving the package moves
+// every user of the context along with it:
+// - `context` is only referenced by the package's module, and it's disposed of by the package.
+// - `module` holds the `LLVMModuleRef` and the `LLVMValueRef`s of its functions, which are all
+//   created in `context`, and not reachable from anything outside of the package.
+// - The module's symbols are an `Arc<GlobalSymbols>`, which is `Send` and `Sync`, and its global
+//   mappings, like the per-module IRs and bitcode, are plain owned data.
+// - The diagnostic handler of the context reports to whichever thread is using it.
+unsafe impl Send for Package {}
+
 impl Package {
-    pub(crate) const fn new(module: Module, ir_per_module: HashMap<String, String>) -> Self {
+    /// # Safety
+    /// The `module` must have been created in the `context`.
+    pub(crate) const unsafe fn new(
+        module: Module,
+        context: Context,
+        ir_per_module: BTreeMap<String, String>,
+        optimized_ir_per_module: BTreeMap<String, String>,
+        bitcode_per_module: BTreeMap<String, Vec<u8>>,
+    ) -> Self {
         Self {
             module,
+            context,
             ir_per_module,
+            optimized_ir_per_module,
+            bitcode_per_module,
         }
     }
 
-    pub(crate) fn into_module(self) -> Module {
-        self.module
+    /// Reads a package written by [`Self::write_bitcode`]. The per-module IRs and bitcode are not
+    /// part of the package's bitcode, so they will be empty.
+    ///
+    /// # Errors
+    /// Will return an error if the bitcode cannot be parsed, or it calls runtime functions and
+    /// was written by another build of the binary.
+    /// # Safety
+    /// The bitcode must have been written by [`Self::write_bitcode`]. The addresses of runtime
+    /// functions are stored relative to the binary, along with a fingerprint of the build,
+    /// which is a hash, so the code must not come from an untrusted source.
+    pub unsafe fn read_bitcode(bitcode: &[u8]) -> Result<Self, BitcodeError> {
+        let package_context =
+            PackageContext::new(PACKAGE_ID_GENERATOR.next(), Arc::new(GlobalSymbols::new()));
+        let context = Context::new();
+
+        // SAFETY: The caller guarantees the bitcode comes from this binary, the module is stored
+        // along with its context
+        let module = unsafe { Module::read_bitcode(&context, &package_context, bitcode) }?;
+
+        // SAFETY: The module was just parsed in the context
+        Ok(unsafe {
+            Self::new(
+                module,
+                context,
+                BTreeMap::new(),
+                BTreeMap::new(),
+                BTreeMap::new(),
+            )
+        })
+    }
+
+    /// The module has to be dropped before the context.
+    pub(crate) fn into_parts(self) -> (Module, Context) {
+        (self.module, self.context)
     }
 
-    pub(crate) fn symbols(&self) -> Rc<GlobalSymbols> {
+    pub(crate) fn symbols(&self) -> Arc<GlobalSymbols> {
         self.module.symbols()
     }
 
     #[must_use]
-    pub const fn ir_per_module(&self) -> &HashMap<String, String> {
+    pub const fn ir_per_module(&self) -> &BTreeMap<String, String> {
         &self.ir_per_module
     }
 
+    /// The IR of each of the modules after the optimization pipeline ran, empty if the
+    /// optimization was disabled. [`Self::ir_per_module`] contains the IR from before the
+    /// optimization.
+    #[must_use]
+    pub const fn optimized_ir_per_module(&self) -> &BTreeMap<String, String> {
+        &self.optimized_ir_per_module
+    }
+
+    /// The bitcode of each of the modules, before linking, empty unless it was asked for with
+    /// [`builder::PackageBuilder::bitcode_per_module`]. Each of those can be added to another
+    /// package with [`builder::PackageBuilder::add_bitcode_module`].
+    #[must_use]
+    pub const fn bitcode_per_module(&self) -> &BTreeMap<String, Vec<u8>> {
+        &self.bitcode_per_module
+    }
+
     #[must_use]
     pub fn final_ir(&self) -> String {
         self.module.dump_ir()
     }
+
+    /// The bitcode of the linked package, including the addresses of the runtime functions. Can
+    /// be loaded back with [`Self::read_bitcode`].
+    #[must_use]
+    pub fn write_bitcode(&self) -> Vec<u8> {
+        self.module.write_bitcode()
+    }
+
+    /// # Errors
+    /// Will return an error if the code generation for the target fails.
+    pub fn emit_object(&self, target_machine: &TargetMachine) -> Result<Vec<u8>, EmitError> {
+        target_machine.emit(&self.module, LLVMCodeGenFileType::LLVMObjectFile)
+    }
+
+    /// # Errors
+    /// Will return an error if the code generation for the target fails.
+    pub fn emit_assembly(&self, target_machine: &TargetMachine) -> Result<String, EmitError> {
+        let assembly = target_machine.emit(&self.module, LLVMCodeGenFileType::LLVMAssemblyFile)?;
+
+        String::from_utf8(assembly).map_err(|_| EmitError::InvalidAssembly)
+    }
 }
@@ -0,0 +1,251 @@
+use std::{
+    borrow::Cow,
+    collections::HashSet,
+    ffi::{CStr, CString},
+    str::FromStr as _,
+};
+
+use llvm_sys::{
+    LLVMLinkage,
+    core::{
+        LLVMAddGlobal, LLVMConstArray2, LLVMDeleteGlobal, LLVMGetFirstFunction, LLVMGetFirstGlobal,
+        LLVMGetLinkage, LLVMGetModuleContext, LLVMGetNextFunction, LLVMGetNextGlobal,
+        LLVMGetValueName2, LLVMIsDeclaration, LLVMPointerTypeInContext, LLVMSetInitializer,
+        LLVMSetLinkage, LLVMSetSection, LLVMTypeOf,
+    },
+    error::{LLVMDisposeErrorMessage, LLVMGetErrorMessage},
+    prelude::{LLVMModuleRef, LLVMValueRef},
+    transforms::pass_builder::{
+        LLVMCreatePassBuilderOptions, LLVMDisposePassBuilderOptions, LLVMRunPasses,
+    },
+};
+use thiserror::Error;
+
+use crate::{module::AnyModule, target_machine::TargetMachine};
+
+/// An optimization pipeline, either a preset or a custom one.
+///
+/// It's ran for each of the modules of a package, after it's verified and before it's linked, or
+/// for the whole package after linking (see
+/// [`super::builder::PackageBuilder::link_time_optimization`]). The passes target the host,
+/// whose triple and data layout are set on the modules.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub enum Optimization {
+    /// No passes are ran at all.
+    #[default]
+    Disabled,
+    O0,
+    O1,
+    O2,
+    O3,
+    Os,
+    Oz,
+    /// A pipeline in the `opt -passes=` syntax, e.g. `function(instcombine,simplifycfg)`
+    Custom(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Phase {
+    Module,
+    LinkTime,
+}
+
+impl Optimization {
+    fn pipeline(&self, phase: Phase) -> Option<Cow<'_, str>> {
+        let level = match self {
+            Self::Disabled => return None,
+            Self::Custom(pipeline) => return Some(pipeline.as_str().into()),
+            Self::O0 => "O0",
+            Self::O1 => "O1",
+            Self::O2 => "O2",
+            Self::O3 => "O3",
+            Self::Os => "Os",
+            Self::Oz => "Oz",
+        };
+
+        Some(match phase {
+            Phase::Module => format!("default<{level}>").into(),
+            Phase::LinkTime => format!("lto<{level}>").into(),
+        })
+    }
+
+    #[must_use]
+    pub const fn is_enabled(&self) -> bool {
+        !matches!(self, Self::Disabled)
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("Failed to optimize the module \"{module_name}\" with \"{pipeline}\": {message}")]
+pub struct OptimizationError {
+    module_name: String,
+    pipeline: String,
+    message: String,
+}
+
+/// Runs the pipeline on the module in-place, does nothing if the optimization is disabled. The
+/// `preserved` functions and globals are not removed, even if nothing refers to them.
+pub(crate) fn optimize(
+    module: &dyn AnyModule,
+    module_name: &str,
+    optimization: &Optimization,
+    phase: Phase,
+    preserved: &HashSet<String>,
+) -> Result<(), OptimizationError> {
+    let Some(pipeline) = optimization.pipeline(phase) else {
+        return Ok(());
+    };
+
+    let error = |message: String| OptimizationError {
+        module_name: module_name.to_string(),
+        pipeline: pipeline.to_string(),
+        message,
+    };
+
+    let pipeline_raw = CString::from_str(&pipeline)
+        .map_err(|_| error("the pipeline is not a valid C-string".to_string()))?;
+
+    // The passes query the target for the costs of instructions and the available vector
+    // registers, the code is JIT-ed for the host
+    let target_machine = TargetMachine::host().map_err(|e| error(e.to_string()))?;
+
+    // SAFETY: The module is valid for as long as it exists
+    unsafe { target_machine.configure(module.as_llvm_ref()) };
+
+    // The preserved definitions may be internal, which the pipeline would otherwise remove once
+    // nothing in the module refers to them, even though they are looked up in the JIT
+    let used = keep_alive(module, |value| {
+        // SAFETY: The value is a valid function or global from the module
+        preserved.contains(&unsafe { value_name(value) })
+    });
+
+    // SAFETY: The function has no preconditions, we own the returned options
+    let options = unsafe { LLVMCreatePassBuilderOptions() };
+
+    // SAFETY: The module and the target machine are valid for as long as they exist, and the
+    // pipeline is a valid C-string
+    let result = unsafe {
+        LLVMRunPasses(
+            module.as_llvm_ref(),
+            pipeline_raw.as_ptr(),
+            target_machine.as_llvm_ref(),
+            options,
+        )
+    };
+
+    // SAFETY: The passes were ran, nothing references the options anymore
+    unsafe { LLVMDisposePassBuilderOptions(options) };
+
+    if let Some(used) = used {
+        // SAFETY: The global was added to the module by `keep_alive`, and nothing else refers to
+        // it
+        unsafe { LLVMDeleteGlobal(used) };
+    }
+
+    if result.is_null() {
+        return Ok(());
+    }
+
+    // SAFETY: The error is not null, so it's a valid error, which gets consumed by the call
+    let message_raw = unsafe { LLVMGetErrorMessage(result) };
+    // SAFETY: LLVM returns a valid null-terminated string
+    let message = unsafe { CStr::from_ptr(message_raw) }
+        .to_string_lossy()
+        .into_owned();
+    // SAFETY: We've copied the message, so it won't be used anymore
+    unsafe { LLVMDisposeErrorMessage(message_raw) };
+
+    Err(error(message))
+}
+
+/// Adds the functions and globals defined in the module which match the filter to
+/// `llvm.compiler.used`, so that the pipeline doesn't remove them, even if nothing in the module
+/// refers to them, as they can still be looked up in the JIT. Returns the added global, if any of
+/// the definitions matched.
+fn keep_alive(
+    module: &dyn AnyModule,
+    filter: impl Fn(LLVMValueRef) -> bool,
+) -> Option<LLVMValueRef> {
+    let module = module.as_llvm_ref();
+
+    let mut definitions: Vec<_> = definitions(module).filter(|value| filter(*value)).collect();
+
+    if definitions.is_empty() {
+        return None;
+    }
+
+    // SAFETY: The module is valid, the definitions belong to it, and are alive for the duration
+    // of the calls. The name and the section are null-terminated
+    unsafe {
+        let pointer = LLVMPointerTypeInContext(LLVMGetModuleContext(module), 0);
+        let initializer =
+            LLVMConstArray2(pointer, definitions.as_mut_ptr(), definitions.len() as u64);
+        let used = LLVMAddGlobal(
+            module,
+            LLVMTypeOf(initializer),
+            c"llvm.compiler.used".as_ptr(),
+        );
+
+        LLVMSetInitializer(used, initializer);
+        LLVMSetLinkage(used, LLVMLinkage::LLVMAppendingLinkage);
+        LLVMSetSection(used, c"llvm.metadata".as_ptr());
+
+        Some(used)
+    }
+}
+
+/// Gives internal linkage to all the functions and globals defined in the module, except for the
+/// `preserved` ones, so that the link-time optimization is free to inline and remove them.
+pub(crate) fn internalize(module: &dyn AnyModule, preserved: &HashSet<String>) {
+    for value in definitions(module.as_llvm_ref()) {
+        // SAFETY: The value is a valid function or global from the module
+        if unsafe { LLVMGetLinkage(value) } != LLVMLinkage::LLVMExternalLinkage {
+            continue;
+        }
+
+        // SAFETY: The value is a valid function or global from the module
+        if preserved.contains(&unsafe { value_name(value) }) {
+            continue;
+        }
+
+        // SAFETY: The value is a valid function or global from the module
+        unsafe { LLVMSetLinkage(value, LLVMLinkage::LLVMInternalLinkage) };
+    }
+}
+
+/// The functions and globals defined, not just declared, in the module.
+fn definitions(module: LLVMModuleRef) -> impl Iterator<Item = LLVMValueRef> {
+    // SAFETY: The module is valid for as long as it exists, and the functions returned by LLVM
+    // belong to it
+    let functions = std::iter::successors(
+        Some(unsafe { LLVMGetFirstFunction(module) }).filter(|function| !function.is_null()),
+        // SAFETY: The function is a valid value from the module
+        |function| Some(unsafe { LLVMGetNextFunction(*function) }).filter(|next| !next.is_null()),
+    );
+    // SAFETY: The module is valid for as long as it exists, and the globals returned by LLVM
+    // belong to it
+    let globals = std::iter::successors(
+        Some(unsafe { LLVMGetFirstGlobal(module) }).filter(|global| !global.is_null()),
+        // SAFETY: The global is a valid value from the module
+        |global| Some(unsafe { LLVMGetNextGlobal(*global) }).filter(|next| !next.is_null()),
+    );
+
+    functions.chain(globals).filter(|value| {
+        // SAFETY: The value is a valid function or global from the module
+        unsafe { LLVMIsDeclaration(*value) == 0 }
+    })
+}
+
+/// # Safety
+/// The value must be valid.
+unsafe fn value_name(value: LLVMValueRef) -> String {
+    let mut length = 0;
+
+    // SAFETY: The caller guarantees the value is valid, the returned string has the given length
+    // and is owned by the value
+    unsafe {
+        let name = LLVMGetValueName2(value, &raw mut length);
+
+        String::from_utf8_lossy(std::slice::from_raw_parts(name.cast::<u8>(), length)).into_owned()
+    }
+}
@@ -0,0 +1,166 @@
+use std::{
+    collections::HashSet,
+    num::NonZeroUsize,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+use llvm_sys::{core::LLVMDisposeModule, prelude::LLVMModuleRef};
+
+use super::optimization::{self, Optimization, OptimizationError, Phase};
+use crate::{
+    context::{
+        Context,
+        diagnostic::{DiagnosticScope, DiagnosticSink},
+    },
+    module::{
+        AnyModule, AnyModuleExtensions,
+        bitcode::{self, BitcodeError},
+    },
+};
+
+/// A module handed over to a worker thread. LLVM modules cannot leave the context they were
+/// created in, so they're moved between the threads as bitcode. Only verified modules are
+/// written, as parsing the bitcode of a broken module with debug info aborts the process.
+pub struct ModuleJob {
+    pub name: String,
+    pub bitcode: Vec<u8>,
+    /// What the verifier reported about the module before it was written.
+    pub messages: String,
+    pub optimize: bool,
+}
+
+/// What came out of verifying and optimizing a module.
+pub struct ModuleReport {
+    pub messages: String,
+    pub ir: String,
+    pub optimized_ir: Option<String>,
+}
+
+/// A module processed by a worker, which has to be moved back as bitcode.
+pub struct ModuleOutput {
+    pub report: ModuleReport,
+    pub bitcode: Vec<u8>,
+}
+
+pub enum ModuleJobError {
+    Optimization(OptimizationError),
+    Bitcode(BitcodeError),
+}
+
+/// A module parsed into a context owned by the worker, disposed when dropped.
+struct WorkerModule(LLVMModuleRef);
+
+impl AnyModule for WorkerModule {
+    fn as_llvm_ref(&self) -> LLVMModuleRef {
+        self.0
+    }
+}
+
+impl Drop for WorkerModule {
+    fn drop(&mut self) {
+        // SAFETY: The module was parsed by the worker, and nothing else references it
+        unsafe { LLVMDisposeModule(self.0) };
+    }
+}
+
+/// Optimizes the module of the job in-place, in whichever context it was read into.
+pub fn process_module(
+    module: &impl AnyModule,
+    job: &ModuleJob,
+    optimization: &Optimization,
+    preserved: &HashSet<String>,
+) -> Result<ModuleReport, ModuleJobError> {
+    let ir = module.dump_ir();
+
+    if job.optimize {
+        optimization::optimize(module, &job.name, optimization, Phase::Module, preserved)
+            .map_err(ModuleJobError::Optimization)?;
+    }
+
+    let optimized_ir = optimization.is_enabled().then(|| module.dump_ir());
+
+    Ok(ModuleReport {
+        messages: job.messages.clone(),
+        ir,
+        optimized_ir,
+    })
+}
+
+fn process(
+    job: &ModuleJob,
+    optimization: &Optimization,
+    preserved: &HashSet<String>,
+    diagnostic_sink: Option<&Arc<dyn DiagnosticSink>>,
+) -> Result<ModuleOutput, ModuleJobError> {
+    let _diagnostic_scope = DiagnosticScope::new(&job.name, diagnostic_sink.cloned());
+    let context = Context::new();
+
+    // SAFETY: The bitcode was written by this process, and the module is declared after the
+    // context, so it's dropped first
+    let read = unsafe { bitcode::read(&context, &job.bitcode) }.map_err(ModuleJobError::Bitcode)?;
+    let module = WorkerModule(read.reference);
+
+    let report = process_module(&module, job, optimization, preserved)?;
+
+    Ok(ModuleOutput {
+        report,
+        bitcode: bitcode::write(&module, &read.global_mappings),
+    })
+}
+
+/// Optimizes the modules on up to `threads` threads, each with its own LLVM
+/// context. The results are in the same order as the jobs. With a single thread, the modules
+/// should be processed with [`process_module`] in the context they will be linked in instead,
+/// which saves moving them back as bitcode.
+pub fn run(
+    jobs: &[ModuleJob],
+    optimization: &Optimization,
+    preserved: &HashSet<String>,
+    diagnostic_sink: Option<&Arc<dyn DiagnosticSink>>,
+    threads: NonZeroUsize,
+) -> Vec<Result<ModuleOutput, ModuleJobError>> {
+    let threads = threads.get().min(jobs.len());
+
+    if threads <= 1 {
+        return jobs
+            .iter()
+            .map(|job| process(job, optimization, preserved, diagnostic_sink))
+            .collect();
+    }
+
+    let next_job = AtomicUsize::new(0);
+
+    let mut results: Vec<_> = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut results = vec![];
+
+                    loop {
+                        let index = next_job.fetch_add(1, Ordering::Relaxed);
+                        let Some(job) = jobs.get(index) else {
+                            break results;
+                        };
+
+                        results.push((
+                            index,
+                            process(job, optimization, preserved, diagnostic_sink),
+                        ));
+                    }
+                })
+            })
+            .collect();
+
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap())
+            .collect()
+    });
+
+    results.sort_by_key(|(index, _)| *index);
+
+    results.into_iter().map(|(_, result)| result).collect()
+}
@@ -0,0 +1,170 @@
+use std::{
+    ffi::{CStr, CString, c_char},
+    str::FromStr as _,
+};
+
+use llvm_sys::{
+    core::LLVMDisposeMessage,
+    target_machine::{
+        LLVMCreateTargetMachine, LLVMGetDefaultTargetTriple, LLVMGetHostCPUFeatures,
+        LLVMGetHostCPUName, LLVMGetTargetFromTriple,
+    },
+};
+use thiserror::Error;
+
+use super::{CodeGenerationLevel, CodeModel, RelocationModel, TARGET_SETUP, TargetMachine};
+
+#[derive(Debug, Error)]
+pub enum TargetMachineError {
+    #[error("\"{0}\" cannot be converted into a C-string")]
+    InvalidString(String),
+    #[error("No target found for triple \"{triple}\": {message}")]
+    TargetNotFound { triple: String, message: String },
+    #[error("Failed to create a target machine for triple \"{0}\"")]
+    CreationFailed(String),
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct TargetMachineBuilder {
+    triple: Option<String>,
+    cpu: Option<String>,
+    features: Option<String>,
+    relocation_model: RelocationModel,
+    code_model: CodeModel,
+    level: CodeGenerationLevel,
+}
+
+impl TargetMachineBuilder {
+    /// Creates a builder for the host machine. Unless overriden, the host's triple, CPU and CPU
+    /// features will be used.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the target triple. If the triple is set, the CPU and features default to a generic
+    /// CPU, instead of the host's.
+    #[must_use]
+    pub fn triple(mut self, triple: impl Into<String>) -> Self {
+        self.triple = Some(triple.into());
+        self
+    }
+
+    #[must_use]
+    pub fn cpu(mut self, cpu: impl Into<String>) -> Self {
+        self.cpu = Some(cpu.into());
+        self
+    }
+
+    /// The features are a comma-separated list, e.g. `+avx2,-sse4a`
+    #[must_use]
+    pub fn features(mut self, features: impl Into<String>) -> Self {
+        self.features = Some(features.into());
+        self
+    }
+
+    #[must_use]
+    pub const fn relocation_model(mut self, relocation_model: RelocationModel) -> Self {
+        self.relocation_model = relocation_model;
+        self
+    }
+
+    #[must_use]
+    pub const fn code_model(mut self, code_model: CodeModel) -> Self {
+        self.code_model = code_model;
+        self
+    }
+
+    #[must_use]
+    pub const fn code_generation_level(mut self, level: CodeGenerationLevel) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// # Errors
+    /// Will return an error if there's no target for the triple, or the target machine cannot
+    /// be created.
+    pub fn build(self) -> Result<TargetMachine, TargetMachineError> {
+        let _ = *TARGET_SETUP;
+
+        let (triple, cpu, features) = match self.triple {
+            Some(triple) => (
+                triple,
+                self.cpu.unwrap_or_default(),
+                self.features.unwrap_or_default(),
+            ),
+            None => (
+                // SAFETY: The function has no preconditions and returns an owned message
+                unsafe { take_message(LLVMGetDefaultTargetTriple()) },
+                self.cpu
+                    // SAFETY: The function has no preconditions and returns an owned message
+                    .unwrap_or_else(|| unsafe { take_message(LLVMGetHostCPUName()) }),
+                self.features
+                    // SAFETY: The function has no preconditions and returns an owned message
+                    .unwrap_or_else(|| unsafe { take_message(LLVMGetHostCPUFeatures()) }),
+            ),
+        };
+
+        let c_triple = to_c_string(&triple)?;
+        let c_cpu = to_c_string(&cpu)?;
+        let c_features = to_c_string(&features)?;
+
+        let mut target = std::ptr::null_mut();
+        let mut error_raw = std::ptr::null_mut();
+
+        // SAFETY: The triple is a valid null-terminated string, target and error_raw are valid
+        // pointers that will be filled in by the call
+        if unsafe {
+            LLVMGetTargetFromTriple(c_triple.as_ptr(), &raw mut target, &raw mut error_raw)
+        } != 0
+        {
+            // SAFETY: On failure, LLVM sets the error message to an owned string
+            let message = unsafe { take_message(error_raw) };
+
+            return Err(TargetMachineError::TargetNotFound { triple, message });
+        }
+
+        // SAFETY: We have a valid target, all the strings are null-terminated and the enums are
+        // converted to valid LLVM values
+        let reference = unsafe {
+            LLVMCreateTargetMachine(
+                target,
+                c_triple.as_ptr(),
+                c_cpu.as_ptr(),
+                c_features.as_ptr(),
+                self.level.into(),
+                self.relocation_model.into(),
+                self.code_model.into(),
+            )
+        };
+
+        if reference.is_null() {
+            return Err(TargetMachineError::CreationFailed(triple));
+        }
+
+        // SAFETY: We just created the target machine, and nobody else owns it
+        Ok(unsafe { TargetMachine::new(reference, triple, cpu, features) })
+    }
+}
+
+fn to_c_string(value: &str) -> Result<CString, TargetMachineError> {
+    CString::from_str(value).map_err(|_| TargetMachineError::InvalidString(value.to_string()))
+}
+
+/// # Safety
+/// The message must be a valid, owned, null-terminated string returned by LLVM
+pub(super) unsafe fn take_message(message: *mut c_char) -> String {
+    if message.is_null() {
+        return String::new();
+    }
+
+    // SAFETY: The caller guarantees the message is a valid null-terminated string
+    let result = unsafe { CStr::from_ptr(message) }
+        .to_string_lossy()
+        .into_owned();
+
+    // SAFETY: We made a copy of the message, so it won't be used anymore
+    unsafe { LLVMDisposeMessage(message) };
+
+    result
+}
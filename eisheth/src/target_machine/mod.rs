@@ -0,0 +1,243 @@
+pub mod builder;
+
+use std::{ffi::CString, str::FromStr as _, sync::LazyLock};
+
+use llvm_sys::{
+    core::{
+        LLVMCloneModule, LLVMDisposeMemoryBuffer, LLVMDisposeModule, LLVMGetBufferSize,
+        LLVMGetBufferStart, LLVMSetTarget,
+    },
+    prelude::LLVMModuleRef,
+    target::{
+        LLVM_InitializeAllAsmPrinters, LLVM_InitializeAllTargetInfos, LLVM_InitializeAllTargetMCs,
+        LLVM_InitializeAllTargets, LLVMDisposeTargetData, LLVMSetModuleDataLayout,
+    },
+    target_machine::{
+        LLVMCodeGenFileType, LLVMCodeGenOptLevel, LLVMCodeModel, LLVMCreateTargetDataLayout,
+        LLVMDisposeTargetMachine, LLVMRelocMode, LLVMTargetMachineEmitToMemoryBuffer,
+        LLVMTargetMachineRef,
+    },
+};
+use thiserror::Error;
+
+use crate::module::AnyModule;
+
+#[derive(Clone, Copy)]
+struct TargetToken;
+
+static TARGET_SETUP: LazyLock<TargetToken> = LazyLock::new(|| {
+    // SAFETY: These functions don't have any prerequisites, and are safe to call more than once
+    unsafe {
+        LLVM_InitializeAllTargetInfos();
+        LLVM_InitializeAllTargets();
+        LLVM_InitializeAllTargetMCs();
+        LLVM_InitializeAllAsmPrinters();
+    };
+
+    TargetToken
+});
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RelocationModel {
+    #[default]
+    Default,
+    Static,
+    PositionIndependent,
+    DynamicNoPositionIndependent,
+}
+
+impl From<RelocationModel> for LLVMRelocMode {
+    fn from(value: RelocationModel) -> Self {
+        match value {
+            RelocationModel::Default => Self::LLVMRelocDefault,
+            RelocationModel::Static => Self::LLVMRelocStatic,
+            RelocationModel::PositionIndependent => Self::LLVMRelocPIC,
+            RelocationModel::DynamicNoPositionIndependent => Self::LLVMRelocDynamicNoPic,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CodeModel {
+    #[default]
+    Default,
+    Tiny,
+    Small,
+    Kernel,
+    Medium,
+    Large,
+}
+
+impl From<CodeModel> for LLVMCodeModel {
+    fn from(value: CodeModel) -> Self {
+        match value {
+            CodeModel::Default => Self::LLVMCodeModelDefault,
+            CodeModel::Tiny => Self::LLVMCodeModelTiny,
+            CodeModel::Small => Self::LLVMCodeModelSmall,
+            CodeModel::Kernel => Self::LLVMCodeModelKernel,
+            CodeModel::Medium => Self::LLVMCodeModelMedium,
+            CodeModel::Large => Self::LLVMCodeModelLarge,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CodeGenerationLevel {
+    None,
+    Less,
+    #[default]
+    Default,
+    Aggressive,
+}
+
+impl From<CodeGenerationLevel> for LLVMCodeGenOptLevel {
+    fn from(value: CodeGenerationLevel) -> Self {
+        match value {
+            CodeGenerationLevel::None => Self::LLVMCodeGenLevelNone,
+            CodeGenerationLevel::Less => Self::LLVMCodeGenLevelLess,
+            CodeGenerationLevel::Default => Self::LLVMCodeGenLevelDefault,
+            CodeGenerationLevel::Aggressive => Self::LLVMCodeGenLevelAggressive,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum EmitError {
+    #[error("Failed to emit code for {triple}: {message}")]
+    CodeGeneration { triple: String, message: String },
+    #[error("The emitted assembly is not valid UTF-8")]
+    InvalidAssembly,
+}
+
+pub struct TargetMachine {
+    reference: LLVMTargetMachineRef,
+    triple: String,
+    cpu: String,
+    features: String,
+}
+
+impl TargetMachine {
+    /// # Safety
+    /// The `reference` must be a valid target machine, which will be owned by the created object
+    const unsafe fn new(
+        reference: LLVMTargetMachineRef,
+        triple: String,
+        cpu: String,
+        features: String,
+    ) -> Self {
+        Self {
+            reference,
+            triple,
+            cpu,
+            features,
+        }
+    }
+
+    /// Creates a target machine for the host, with the default options.
+    ///
+    /// # Errors
+    /// Will return an error if LLVM cannot create a target machine for the host.
+    pub fn host() -> Result<Self, builder::TargetMachineError> {
+        builder::TargetMachineBuilder::new().build()
+    }
+
+    #[must_use]
+    pub fn triple(&self) -> &str {
+        &self.triple
+    }
+
+    #[must_use]
+    pub fn cpu(&self) -> &str {
+        &self.cpu
+    }
+
+    #[must_use]
+    pub fn features(&self) -> &str {
+        &self.features
+    }
+
+    pub(crate) const fn as_llvm_ref(&self) -> LLVMTargetMachineRef {
+        self.reference
+    }
+
+    /// Sets the module's triple and data layout to the ones of this target machine.
+    ///
+    /// # Safety
+    /// The module must be valid.
+    pub(crate) unsafe fn configure(&self, module: LLVMModuleRef) {
+        // The triple was checked for interior nulls when the target machine got built
+        let triple = CString::from_str(&self.triple).unwrap();
+
+        // SAFETY: The caller guarantees the module is valid, we own the target machine, and the
+        // triple is a valid C-string
+        unsafe {
+            LLVMSetTarget(module, triple.as_ptr());
+
+            let data_layout = LLVMCreateTargetDataLayout(self.reference);
+            LLVMSetModuleDataLayout(module, data_layout);
+            LLVMDisposeTargetData(data_layout);
+        };
+    }
+
+    /// Runs the code generation for a copy of the module, so the module itself is left
+    /// untouched and can still be used afterwards (e.g. for JIT-ing).
+    pub(crate) fn emit(
+        &self,
+        module: &dyn AnyModule,
+        file_type: LLVMCodeGenFileType,
+    ) -> Result<Vec<u8>, EmitError> {
+        // SAFETY: The module reference is valid for as long as the module exists
+        let module = unsafe { LLVMCloneModule(module.as_llvm_ref()) };
+
+        // SAFETY: We own the cloned module
+        unsafe { self.configure(module) };
+
+        let mut buffer = std::ptr::null_mut();
+        let mut error_raw = std::ptr::null_mut();
+
+        // SAFETY: The target machine and module are valid, the error and the buffer are
+        // out-pointers that will be filled in by LLVM
+        let is_failed = unsafe {
+            LLVMTargetMachineEmitToMemoryBuffer(
+                self.reference,
+                module,
+                file_type,
+                &raw mut error_raw,
+                &raw mut buffer,
+            )
+        } != 0;
+
+        // SAFETY: The clone is not referenced by anything else, we're free to dispose it
+        unsafe { LLVMDisposeModule(module) };
+
+        if is_failed {
+            return Err(EmitError::CodeGeneration {
+                triple: self.triple.clone(),
+                // SAFETY: On failure LLVM sets the message to an owned string
+                message: unsafe { builder::take_message(error_raw) },
+            });
+        }
+
+        // SAFETY: The emit succeeded, so the buffer is valid, and the start and size describe
+        // its contents
+        let result = unsafe {
+            std::slice::from_raw_parts(
+                LLVMGetBufferStart(buffer).cast::<u8>(),
+                LLVMGetBufferSize(buffer),
+            )
+        }
+        .to_vec();
+
+        // SAFETY: We've copied the contents, the buffer is not used anymore
+        unsafe { LLVMDisposeMemoryBuffer(buffer) };
+
+        Ok(result)
+    }
+}
+
+impl Drop for TargetMachine {
+    fn drop(&mut self) {
+        // SAFETY: We own the target machine, and modules don't keep references to it
+        unsafe { LLVMDisposeTargetMachine(self.reference) };
+    }
+}
@@ -0,0 +1,153 @@
+use std::{fs, path::Path};
+
+/// The environment variable which, when set, makes [`assert_ir_snapshot`] overwrite the snapshots
+/// instead of comparing against them.
+pub const UPDATE_SNAPSHOTS_VARIABLE: &str = "EISHETH_UPDATE_SNAPSHOTS";
+
+/// Makes the IR comparable regardless of the order the functions and globals were added in, and
+/// the numbering LLVM picked for attribute groups and metadata.
+///
+/// The module header (ID, source file name, data layout and target triple) and comments are
+/// removed, attribute group and metadata references are replaced with `#_` and `!_`, and the
+/// top-level items, with functions kept whole, are sorted.
+#[must_use]
+pub fn normalize_ir(ir: &str) -> String {
+    let mut items: Vec<String> = vec![];
+    let mut function: Option<String> = None;
+
+    for line in ir.lines().map(str::trim_end) {
+        // Block labels are followed by comments listing the predecessors
+        let line = match line.split_once(';') {
+            Some((label, _)) if label.trim_end().ends_with(':') => label.trim_end(),
+            _ => line,
+        };
+
+        if line.is_empty()
+            || line.starts_with(';')
+            || line.starts_with("source_filename")
+            || line.starts_with("target ")
+        {
+            continue;
+        }
+
+        let line = strip_ids(line);
+
+        if let Some(body) = &mut function {
+            body.push('\n');
+            body.push_str(&line);
+
+            if line == "}" {
+                items.extend(function.take());
+            }
+        } else if line.starts_with("define ") && line.ends_with('{') {
+            function = Some(line);
+        } else {
+            items.push(line);
+        }
+    }
+
+    items.extend(function);
+    items.sort();
+    items.dedup();
+
+    let mut result = items.join("\n");
+    result.push('\n');
+
+    result
+}
+
+fn strip_ids(line: &str) -> String {
+    let mut result = String::with_capacity(line.len());
+    let mut chars = line.chars().peekable();
+
+    while let Some(char) = chars.next() {
+        result.push(char);
+
+        if matches!(char, '#' | '!') && chars.peek().is_some_and(char::is_ascii_digit) {
+            result.push('_');
+
+            while chars.next_if(char::is_ascii_digit).is_some() {}
+        }
+    }
+
+    result
+}
+
+/// Compares the normalized IR against the snapshot at `path`, see [`normalize_ir`].
+///
+/// Snapshots are meant to be checked in, usually as `.ll` files next to the tests, e.g.
+/// `concat!(env!("CARGO_MANIFEST_DIR"), "/tests/snapshots/module.ll")`.
+///
+/// When the [`UPDATE_SNAPSHOTS_VARIABLE`] environment variable is set, the snapshot is written
+/// instead, creating it if it doesn't exist yet.
+///
+/// # Panics
+/// If the snapshot does not match or does not exist, or it cannot be read or written.
+#[track_caller]
+pub fn assert_ir_snapshot(path: impl AsRef<Path>, ir: &str) {
+    let path = path.as_ref();
+    let actual = normalize_ir(ir);
+
+    if std::env::var_os(UPDATE_SNAPSHOTS_VARIABLE).is_some() {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).unwrap();
+        }
+
+        fs::write(path, actual).unwrap();
+        return;
+    }
+
+    let Ok(expected) = fs::read_to_string(path) else {
+        panic!(
+            "The IR snapshot {} does not exist, run with {UPDATE_SNAPSHOTS_VARIABLE}=1 to create it",
+            path.display()
+        );
+    };
+
+    assert!(
+        expected == actual,
+        "The IR does not match the snapshot {}, run with {UPDATE_SNAPSHOTS_VARIABLE}=1 to update \
+         it\n{}",
+        path.display(),
+        diff(&expected, &actual)
+    );
+}
+
+/// A line diff of the snapshots, with the removed lines prefixed with `-`, and the added ones
+/// with `+`.
+fn diff(expected: &str, actual: &str) -> String {
+    let expected: Vec<_> = expected.lines().collect();
+    let actual: Vec<_> = actual.lines().collect();
+
+    // The lengths of the longest common subsequences of the suffixes
+    let mut common = vec![vec![0usize; actual.len() + 1]; expected.len() + 1];
+    for i in (0..expected.len()).rev() {
+        for j in (0..actual.len()).rev() {
+            common[i][j] = if expected[i] == actual[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut result = vec![];
+    let (mut i, mut j) = (0, 0);
+
+    while i < expected.len() || j < actual.len() {
+        if i < expected.len() && j < actual.len() && expected[i] == actual[j] {
+            result.push(format!("  {}", expected[i]));
+            i += 1;
+            j += 1;
+        } else if j < actual.len() && (i == expected.len() || common[i][j + 1] >= common[i + 1][j])
+        {
+            result.push(format!("+ {}", actual[j]));
+            j += 1;
+        } else {
+            result.push(format!("- {}", expected[i]));
+            i += 1;
+        }
+    }
+
+    result.join("\n")
+}
@@ -1,9 +1,10 @@
 use llvm_sys::{
-    core::{LLVMArrayType2, LLVMConstArray2},
+    core::{LLVMArrayType2, LLVMConstArray2, LLVMGetArrayLength2},
     prelude::LLVMTypeRef,
 };
 
 use crate::{
+    error::Error,
     types::Type,
     value::{ConstValue, Value},
 };
@@ -24,8 +25,30 @@ impl<T: Type> Array<T> {
         }
     }
 
-    pub(crate) fn const_values(&self, initializer_values: &[ConstValue]) -> ConstValue {
-        let mut values: Vec<_> = initializer_values.iter().map(Value::as_llvm_ref).collect();
+    /// # Panics
+    /// This will panic if the elements do not match the array, see [`Self::try_const_value`].
+    pub fn const_value(&self, elements: &[ConstValue]) -> ConstValue {
+        self.try_const_value(elements).unwrap()
+    }
+
+    /// # Errors
+    /// Will return an error if the number of elements does not match the length of the array, or
+    /// an element is not of the element type.
+    /// # Panics
+    /// If there are more elements than fit in an u64
+    pub fn try_const_value(&self, elements: &[ConstValue]) -> Result<ConstValue, Error> {
+        if self.len() != elements.len() {
+            return Err(Error::ElementCount {
+                expected: self.len(),
+                actual: elements.len(),
+            });
+        }
+
+        for (index, element) in elements.iter().enumerate() {
+            element.expect_element_type(index, self.element_type.as_llvm_ref())?;
+        }
+
+        let mut values: Vec<_> = elements.iter().map(Value::as_llvm_ref).collect();
 
         // SAFETY: The values are of correct type and valid pointers, the length matches, and
         // element_type is a vaid pointer
@@ -38,7 +61,14 @@ impl<T: Type> Array<T> {
         };
 
         // SAFETY: We just created the result, it is valid
-        unsafe { ConstValue::new(result) }
+        Ok(unsafe { ConstValue::new(result) })
+    }
+
+    fn len(&self) -> usize {
+        // SAFETY: We know that the array reference is valid
+        let len = unsafe { LLVMGetArrayLength2(self.reference) };
+
+        usize::try_from(len).unwrap_or(usize::MAX)
     }
 }
 
@@ -45,6 +45,15 @@ impl Function {
         }
     }
 
+    /// # Safety
+    /// The `reference` must be a valid function type.
+    pub(crate) const unsafe fn from_llvm_ref(reference: LLVMTypeRef) -> Self {
+        Self {
+            reference,
+            _context: PhantomData,
+        }
+    }
+
     pub(crate) fn arguments_count(&self) -> usize {
         // SAFETY: We know that reference is valid till self is dropped
         (unsafe { LLVMCountParamTypes(self.reference) }) as usize
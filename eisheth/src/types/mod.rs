@@ -11,8 +11,10 @@ pub use array::Array;
 pub use function::Function;
 pub use integer::Integer;
 use llvm_sys::{
-    core::{LLVMConstBitCast, LLVMSizeOf},
-    prelude::LLVMTypeRef,
+    core::{
+        LLVMConstBitCast, LLVMConstNull, LLVMGetPoison, LLVMGetUndef, LLVMSizeOf, LLVMTypeIsSized,
+    },
+    prelude::{LLVMTypeRef, LLVMValueRef},
 };
 pub use pointer::Pointer;
 pub use r#struct::Struct;
@@ -40,6 +42,24 @@ impl<T: RepresentedAs<RepresentationType = TRepresentation> + Copy, TRepresentat
 
 pub trait TypeExtensions {
     fn sizeof(&self) -> ConstValue;
+
+    /// The value with all bits zeroed, e.g. to zero-initialize a global.
+    ///
+    /// # Panics
+    /// Will panic if the type has no size, like `void` or functions.
+    fn const_zero(&self) -> ConstValue;
+
+    /// A value the optimizer may assume to be anything.
+    ///
+    /// # Panics
+    /// Will panic if the type has no size, like `void` or functions.
+    fn const_undef(&self) -> ConstValue;
+
+    /// A value that makes whatever depends on it undefined behavior.
+    ///
+    /// # Panics
+    /// Will panic if the type has no size, like `void` or functions.
+    fn const_poison(&self) -> ConstValue;
 }
 
 impl<T: Type> TypeExtensions for T {
@@ -53,6 +73,33 @@ impl<T: Type> TypeExtensions for T {
         // SAFETY: We just created the result, it is a valid pointer
         unsafe { ConstValue::new(result) }
     }
+
+    fn const_zero(&self) -> ConstValue {
+        sized_constant(self.as_llvm_ref(), LLVMConstNull)
+    }
+
+    fn const_undef(&self) -> ConstValue {
+        sized_constant(self.as_llvm_ref(), LLVMGetUndef)
+    }
+
+    fn const_poison(&self) -> ConstValue {
+        sized_constant(self.as_llvm_ref(), LLVMGetPoison)
+    }
+}
+
+fn sized_constant(
+    r#type: LLVMTypeRef,
+    create: unsafe extern "C" fn(LLVMTypeRef) -> LLVMValueRef,
+) -> ConstValue {
+    // SAFETY: The type reference comes from a safe wrapper
+    let is_sized = unsafe { LLVMTypeIsSized(r#type) } != 0;
+    assert!(is_sized, "constants need a sized type");
+
+    // SAFETY: The type is valid and sized, which every constant of it can be created for
+    let result = unsafe { create(r#type) };
+
+    // SAFETY: We just created the result, it is valid
+    unsafe { ConstValue::new(result) }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
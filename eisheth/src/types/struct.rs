@@ -1,9 +1,9 @@
-use std::{ffi::CString, marker::PhantomData, str::FromStr};
+use std::marker::PhantomData;
 
 use llvm_sys::{
     core::{
         LLVMBuildGEP2, LLVMConstNamedStruct, LLVMCountStructElementTypes, LLVMStructCreateNamed,
-        LLVMStructSetBody,
+        LLVMStructGetTypeAtIndex, LLVMStructSetBody,
     },
     prelude::LLVMTypeRef,
 };
@@ -11,6 +11,7 @@ use llvm_sys::{
 use super::Type;
 use crate::{
     context::{Context, LLVM_CONTEXT},
+    error::{Error, set_local_name, to_c_string},
     function::instruction_builder::InstructionBuilder,
     types::{OpaqueType, RepresentedAs},
     value::{ConstValue, DynamicValue, Value},
@@ -24,10 +25,19 @@ pub struct Struct {
 
 impl Struct {
     /// # Panics
-    /// This function will panic if the name can't be converted into a `CString`
+    /// This function will panic if the name can't be converted into a `CString`, see
+    /// [`Self::try_new`].
     #[must_use]
     pub fn new(name: &str, fields: &[OpaqueType]) -> Self {
-        let name = CString::from_str(name).unwrap();
+        Self::try_new(name, fields).unwrap()
+    }
+
+    /// # Errors
+    /// Will return an error if the name can't be converted into a C-string.
+    /// # Panics
+    /// If there are more fields than fit in an u32
+    pub fn try_new(name: &str, fields: &[OpaqueType]) -> Result<Self, Error> {
+        let name = to_c_string(name)?;
         let reference = LLVM_CONTEXT
             // SAFETY: The context is &'static so must always be valid, the name is a valid pointer
             // for the duration of the call
@@ -45,16 +55,39 @@ impl Struct {
             );
         };
 
-        Self {
+        Ok(Self {
             reference,
             _context: PhantomData,
-        }
+        })
     }
 
     /// # Panics
-    /// This will panic if the number of field values does not match the number of defined fields.
+    /// This will panic if the number of field values does not match the number of defined
+    /// fields, see [`Self::try_const_value`].
     pub fn const_value(&self, fields: &[ConstValue]) -> ConstValue {
-        assert!(self.fields_count() == fields.len());
+        self.try_const_value(fields).unwrap()
+    }
+
+    /// # Errors
+    /// Will return an error if the number of field values does not match the number of defined
+    /// fields, or a value is not of the type of its field.
+    /// # Panics
+    /// If there are more fields than fit in an u32
+    pub fn try_const_value(&self, fields: &[ConstValue]) -> Result<ConstValue, Error> {
+        if self.fields_count() != fields.len() {
+            return Err(Error::FieldCount {
+                expected: self.fields_count(),
+                actual: fields.len(),
+            });
+        }
+
+        for (index, field) in fields.iter().enumerate() {
+            // SAFETY: The index is within the bounds checked above
+            let field_type =
+                unsafe { LLVMStructGetTypeAtIndex(self.reference, u32::try_from(index).unwrap()) };
+
+            field.expect_element_type(index, field_type)?;
+        }
 
         let mut values: Vec<_> = fields.iter().map(Value::as_llvm_ref).collect();
 
@@ -69,7 +102,7 @@ impl Struct {
         };
 
         // SAFETY: We just created the value so it's a valid one
-        unsafe { ConstValue::new(value) }
+        Ok(unsafe { ConstValue::new(value) })
     }
 
     /// # Panics
@@ -86,8 +119,6 @@ impl Struct {
             return None;
         }
 
-        let name = CString::new(name).unwrap();
-
         let mut indices = vec![
             u32::representation().const_value(0).as_llvm_ref(),
             u32::representation()
@@ -104,9 +135,11 @@ impl Struct {
                 pointer.as_llvm_ref(),
                 indices.as_mut_ptr(),
                 u32::try_from(indices.len()).unwrap(),
-                name.as_ptr(),
+                c"".as_ptr(),
             )
         };
+        // SAFETY: We just created the value, so it is valid
+        unsafe { set_local_name(value, name) };
 
         // SAFETY: We just created the value, so it is a valid pointer
         Some(unsafe { DynamicValue::new(value) })
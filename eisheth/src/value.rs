@@ -1,6 +1,20 @@
-use llvm_sys::{core::LLVMIsConstant, prelude::LLVMValueRef};
+use llvm_sys::{
+    LLVMTypeKind,
+    core::{
+        LLVMConstGEP2, LLVMConstIntGetZExtValue, LLVMConstPtrToInt, LLVMConstStringInContext2,
+        LLVMCountStructElementTypes, LLVMGetElementType, LLVMGetTypeKind, LLVMIsAConstantInt,
+        LLVMIsConstant, LLVMStructGetTypeAtIndex,
+    },
+    prelude::{LLVMTypeRef, LLVMValueRef},
+};
 
-use crate::module::builder::ModuleBuilder;
+use crate::{
+    context::LLVM_CONTEXT,
+    error::Error,
+    function::validation,
+    module::builder::ModuleBuilder,
+    types::{Integer, OpaqueType, Type},
+};
 
 pub trait Value: Copy {
     fn as_llvm_ref(&self) -> LLVMValueRef;
@@ -62,6 +76,182 @@ impl ConstValue {
     }
 }
 
+/// Constants for the initial data of globals, see
+/// [`crate::module::builder::ModuleBuilder::define_global`]. The aggregates are built by their
+/// types, e.g. [`crate::types::Array::const_value`], and zeroed or undefined values by
+/// [`crate::types::TypeExtensions`].
+impl ConstValue {
+    /// The type of the constant, e.g. to define a global initialized with it.
+    #[must_use]
+    pub fn r#type(&self) -> OpaqueType {
+        // SAFETY: The value is valid, and so is its type
+        unsafe { OpaqueType::new(validation::type_of(self.reference)) }
+    }
+
+    /// The string followed by a null byte, as an `[N x i8]` array of its length plus one, e.g.
+    /// for a string literal passed to C functions.
+    pub fn null_terminated_string(value: &str) -> Self {
+        Self::string(value.as_bytes(), true)
+    }
+
+    /// The bytes as an `[N x i8]` array of the same length.
+    pub fn bytes(value: &[u8]) -> Self {
+        Self::string(value, false)
+    }
+
+    fn string(value: &[u8], null_terminate: bool) -> Self {
+        // SAFETY: The context is valid, and the bytes are passed along with their length
+        let result = LLVM_CONTEXT.with(|context| unsafe {
+            LLVMConstStringInContext2(
+                context.as_llvm_ref(),
+                value.as_ptr().cast(),
+                value.len(),
+                i32::from(!null_terminate),
+            )
+        });
+
+        // SAFETY: We just created the result, it is valid
+        unsafe { Self::new(result) }
+    }
+
+    /// The address of an element of the `type` the pointer points at, like the
+    /// `getelementptr` instruction, e.g. of an entry in a global lookup table.
+    ///
+    /// # Panics
+    /// Will panic if the value is not a pointer, or the indices do not select an element, see
+    /// [`Self::try_element_pointer`].
+    pub fn element_pointer<T: Type>(&self, r#type: T, indices: &[Self]) -> Self {
+        self.try_element_pointer(r#type, indices).unwrap()
+    }
+
+    /// # Errors
+    /// Will return an error if the value is not a pointer, an index is not an integer, or does
+    /// not select an element of the aggregate it indexes into. Struct fields must be selected
+    /// by `u32` literals.
+    /// # Panics
+    /// If there are more indices than fit in an u32
+    pub fn try_element_pointer<T: Type>(&self, r#type: T, indices: &[Self]) -> Result<Self, Error> {
+        self.expect_kind(LLVMTypeKind::LLVMPointerTypeKind, "ptr")?;
+
+        let mut current = r#type.as_llvm_ref();
+        for (index, value) in indices.iter().enumerate() {
+            // SAFETY: The types and the values come from safe wrappers, and the struct indices
+            // are checked to be integer literals within the bounds
+            unsafe {
+                let index_type = validation::type_of(value.reference);
+
+                if LLVMGetTypeKind(index_type) != LLVMTypeKind::LLVMIntegerTypeKind {
+                    return Err(Error::ElementType {
+                        index,
+                        expected: "an integer".to_string(),
+                        actual: validation::type_name(index_type),
+                    });
+                }
+
+                // The first index steps over the pointer itself, so any type can be indexed
+                if index == 0 {
+                    continue;
+                }
+
+                current = match LLVMGetTypeKind(current) {
+                    LLVMTypeKind::LLVMArrayTypeKind => LLVMGetElementType(current),
+                    LLVMTypeKind::LLVMStructTypeKind
+                        if !LLVMIsAConstantInt(value.reference).is_null()
+                            && LLVMConstIntGetZExtValue(value.reference)
+                                < u64::from(LLVMCountStructElementTypes(current)) =>
+                    {
+                        LLVMStructGetTypeAtIndex(
+                            current,
+                            u32::try_from(LLVMConstIntGetZExtValue(value.reference)).unwrap(),
+                        )
+                    }
+                    _ => {
+                        return Err(Error::ElementIndex {
+                            index,
+                            aggregate: validation::type_name(current),
+                        });
+                    }
+                };
+            }
+        }
+
+        let mut indices: Vec<_> = indices.iter().map(Value::as_llvm_ref).collect();
+
+        // SAFETY: The pointer and the indices were checked above, and the indices vector is
+        // alive for the duration of the call
+        let result = unsafe {
+            LLVMConstGEP2(
+                r#type.as_llvm_ref(),
+                self.reference,
+                indices.as_mut_ptr(),
+                u32::try_from(indices.len()).unwrap(),
+            )
+        };
+
+        // SAFETY: We just created the result, it is valid
+        Ok(unsafe { Self::new(result) })
+    }
+
+    /// The address the pointer holds, as an integer.
+    ///
+    /// # Panics
+    /// Will panic if the value is not a pointer, see [`Self::try_pointer_to_int`].
+    pub fn pointer_to_int<T: Copy>(&self, r#type: Integer<T>) -> Self {
+        self.try_pointer_to_int(r#type).unwrap()
+    }
+
+    /// # Errors
+    /// Will return an error if the value is not a pointer.
+    pub fn try_pointer_to_int<T: Copy>(&self, r#type: Integer<T>) -> Result<Self, Error> {
+        self.expect_kind(LLVMTypeKind::LLVMPointerTypeKind, "ptr")?;
+
+        // SAFETY: The value was checked to be a pointer, and the type comes from a safe wrapper
+        let result = unsafe { LLVMConstPtrToInt(self.reference, r#type.as_llvm_ref()) };
+
+        // SAFETY: We just created the result, it is valid
+        Ok(unsafe { Self::new(result) })
+    }
+
+    /// Checks the constant can be placed where a value of the type is expected, e.g. as an
+    /// element of an aggregate.
+    pub(crate) fn expect_element_type(
+        &self,
+        index: usize,
+        expected: LLVMTypeRef,
+    ) -> Result<(), Error> {
+        // SAFETY: The value is valid, and the caller passes a type from a safe wrapper
+        unsafe {
+            let actual = validation::type_of(self.reference);
+
+            if actual == expected {
+                Ok(())
+            } else {
+                Err(Error::ElementType {
+                    index,
+                    expected: validation::type_name(expected),
+                    actual: validation::type_name(actual),
+                })
+            }
+        }
+    }
+
+    fn expect_kind(&self, kind: LLVMTypeKind, expected: &str) -> Result<(), Error> {
+        // SAFETY: The value is valid, and so is its type
+        unsafe {
+            let actual = validation::type_of(self.reference);
+
+            if LLVMGetTypeKind(actual) == kind {
+                Ok(())
+            } else {
+                Err(Error::ConstantType {
+                    expected: expected.to_string(),
+                    actual: validation::type_name(actual),
+                })
+            }
+        }
+    }
+}
+
 impl Value for ConstValue {
     fn as_llvm_ref(&self) -> LLVMValueRef {
         self.reference
@@ -0,0 +1,93 @@
+use eisheth::{
+    Visibility,
+    function::declaration::FunctionSignature,
+    jit::Jit,
+    module::{
+        DeclaredFunctionDescriptor,
+        builder::{ModuleBuilder, comdat::ComdatSelection},
+    },
+    package::builder::PackageBuilder,
+    types::{self, RepresentedAs},
+    value::{ConstValue, ValueReference},
+};
+
+fn define_constant(
+    module: &mut ModuleBuilder,
+    name: &str,
+    value: u64,
+) -> DeclaredFunctionDescriptor {
+    module.define_function(
+        &FunctionSignature::new(
+            name,
+            types::Function::new(u64::representation().into(), &[]),
+            Visibility::Export,
+        ),
+        |function| {
+            let entry = function.create_block("entry");
+            let value: ConstValue = value.into();
+
+            entry.build(|i| i.r#return(value));
+        },
+    )
+}
+
+#[test]
+pub fn aliases_and_comdats_resolve_to_one_definition() {
+    let mut package_builder = PackageBuilder::new();
+
+    let first = package_builder.add_module("first").unwrap();
+    let answer = define_constant(first, "answer", 42);
+    let answer_v1 = first.define_alias(answer, "answer_v1", Visibility::Export);
+    let shared = define_constant(first, "shared", 7);
+    let comdat = first.define_comdat("shared", ComdatSelection::Any);
+    first.add_to_comdat(comdat, shared);
+
+    let second = package_builder.add_module("second").unwrap();
+    let shared = define_constant(second, "shared", 7);
+    let comdat = second.define_comdat("shared", ComdatSelection::Any);
+    second.add_to_comdat(comdat, shared);
+
+    let package = package_builder.build().unwrap().into_package();
+    let jit = Jit::new(package).unwrap();
+    let answer = unsafe { jit.get_function::<unsafe extern "C" fn() -> u64>(answer) };
+    let answer_v1 = unsafe { jit.get_function::<unsafe extern "C" fn() -> u64>(answer_v1) };
+    let shared = unsafe { jit.get_function::<unsafe extern "C" fn() -> u64>(shared) };
+
+    assert_eq!(42, unsafe { answer.call() });
+    assert_eq!(42, unsafe { answer_v1.call() });
+    assert_eq!(7, unsafe { shared.call() });
+}
+
+#[test]
+pub fn ifuncs_are_resolved_by_their_resolver() {
+    let mut module = ModuleBuilder::standalone("ifuncs");
+
+    let answer = define_constant(&mut module, "answer", 42);
+    let resolver = module.define_function(
+        &FunctionSignature::new(
+            "resolve_answer",
+            types::Function::new(<*const u8>::representation().into(), &[]),
+            Visibility::Internal,
+        ),
+        |function| {
+            let entry = function.create_block("entry");
+
+            entry.build(|i| i.r#return(answer.value(i.module())));
+        },
+    );
+    let _ = module.define_ifunc(
+        &FunctionSignature::new(
+            "fast_answer",
+            types::Function::new(u64::representation().into(), &[]),
+            Visibility::Export,
+        ),
+        resolver,
+    );
+
+    assert!(
+        module
+            .dump_ir()
+            .contains("@fast_answer = ifunc i64 (), ptr @resolve_answer")
+    );
+    assert!(module.build().is_ok());
+}
@@ -0,0 +1,87 @@
+use eisheth::{
+    Visibility,
+    function::declaration::FunctionSignature,
+    metadata::{AliasScopeDomain, MemoryAnnotation, Metadata, TbaaType},
+    module::builder::ModuleBuilder,
+    types::{self, RepresentedAs},
+};
+
+fn define_copy_length(module: &mut ModuleBuilder, annotations: &[MemoryAnnotation]) {
+    let _ = module.define_function(
+        &FunctionSignature::new(
+            "copy_length",
+            types::Function::new(
+                <()>::representation().into(),
+                &[
+                    <*mut u64>::representation().into(),
+                    <*mut u64>::representation().into(),
+                ],
+            ),
+            Visibility::Export,
+        ),
+        |function| {
+            let source = function.get_argument(0).unwrap();
+            let destination = function.get_argument(1).unwrap();
+            let entry = function.create_block("entry");
+
+            entry.build(|i| {
+                let length = i.load(&source, u64::representation(), "length");
+                for annotation in annotations {
+                    i.annotate(&length, annotation);
+                }
+
+                let store = i.store(&destination, &length);
+                i.annotate(&store, &annotations[0]);
+
+                i.return_void()
+            });
+        },
+    );
+}
+
+#[test]
+pub fn annotations_are_attached_to_memory_accesses() {
+    let mut module = ModuleBuilder::standalone("annotations");
+
+    let length = TbaaType::root("ligeia").scalar("length");
+    let domain = AliasScopeDomain::new("copy");
+    let source = domain.scope("source");
+    let destination = domain.scope("destination");
+
+    define_copy_length(
+        &mut module,
+        &[
+            MemoryAnnotation::Tbaa(length.access_tag()),
+            MemoryAnnotation::AliasScopes(vec![source]),
+            MemoryAnnotation::NoAlias(vec![destination]),
+            MemoryAnnotation::Range {
+                low: 0u64.into(),
+                high: 1024u64.into(),
+            },
+            MemoryAnnotation::InvariantLoad,
+        ],
+    );
+    module.add_named_metadata("ligeia.version", Metadata::node(&[Metadata::string("1")]));
+
+    let ir = module.dump_ir();
+    for kind in [
+        "!tbaa",
+        "!alias.scope",
+        "!noalias",
+        "!range",
+        "!invariant.load",
+    ] {
+        assert!(ir.contains(kind), "{kind} missing from {ir}");
+    }
+    assert!(ir.contains("!ligeia.version"));
+    assert!(module.build().is_ok());
+}
+
+#[test]
+pub fn invalid_annotations_fail_the_build() {
+    let mut module = ModuleBuilder::standalone("invalid_annotations");
+
+    define_copy_length(&mut module, &[MemoryAnnotation::NonNull]);
+
+    assert!(module.build().is_err());
+}
@@ -0,0 +1,106 @@
+use eisheth::{
+    jit::Jit,
+    package::{Package, builder::PackageBuilder},
+};
+
+mod test_module {
+    use eisheth::define_module;
+
+    define_module!(
+        module test_module {
+            add_magic : runtime (value: u64) -> u64;
+            compute : builder (^add_magic) -> u64;
+        }
+    );
+
+    mod runtime {
+        pub(super) const unsafe extern "C" fn add_magic(value: u64) -> u64 {
+            value + 1000
+        }
+    }
+
+    mod builder {
+        use eisheth::{
+            function::builder::FunctionBuilder, module::DeclaredFunctionDescriptor,
+            value::ConstValue,
+        };
+
+        pub(super) fn compute(function: &FunctionBuilder, add_magic: DeclaredFunctionDescriptor) {
+            let entry = function.create_block("entry");
+
+            entry.build(|i| {
+                let value: ConstValue = 234u64.into();
+                let result = i.direct_call(add_magic, &[&value], "result");
+
+                i.r#return(result)
+            });
+        }
+    }
+}
+
+fn call_compute(package: Package) -> u64 {
+    let jit = Jit::new(package).unwrap();
+
+    let compute = unsafe {
+        jit.get_function_by_name::<unsafe extern "C" fn() -> u64>("compute")
+            .unwrap()
+    };
+
+    unsafe { compute.call() }
+}
+
+#[test]
+pub fn package_bitcode_round_trip() {
+    let mut package_builder = PackageBuilder::new();
+    let _ = test_module::define(&mut package_builder);
+    let package = package_builder.build().unwrap().into_package();
+
+    let bitcode = package.write_bitcode();
+    let read_package = unsafe { Package::read_bitcode(&bitcode) }.unwrap();
+
+    assert_eq!(1234, call_compute(read_package));
+}
+
+#[test]
+pub fn package_bitcode_round_trips_repeatedly() {
+    let mut package_builder = PackageBuilder::new();
+    let _ = test_module::define(&mut package_builder);
+    let package = package_builder.build().unwrap().into_package();
+
+    let read_package = unsafe { Package::read_bitcode(&package.write_bitcode()) }.unwrap();
+    let bitcode = read_package.write_bitcode();
+    let read_package = unsafe { Package::read_bitcode(&bitcode) }.unwrap();
+
+    assert_eq!(1234, call_compute(read_package));
+}
+
+#[test]
+pub fn module_bitcode_round_trip() {
+    let mut package_builder = PackageBuilder::new().bitcode_per_module(true);
+    let _ = test_module::define(&mut package_builder);
+    let package = package_builder.build().unwrap().into_package();
+
+    let bitcode = &package.bitcode_per_module()["test_module"];
+
+    let mut package_builder = PackageBuilder::new();
+    unsafe { package_builder.add_bitcode_module(bitcode) }.unwrap();
+    assert!(unsafe { package_builder.add_bitcode_module(bitcode) }.is_err());
+
+    let package = package_builder.build().unwrap().into_package();
+
+    assert_eq!(1234, call_compute(package));
+}
+
+#[test]
+pub fn module_bitcode_is_opt_in() {
+    let mut package_builder = PackageBuilder::new();
+    let _ = test_module::define(&mut package_builder);
+    let package = package_builder.build().unwrap().into_package();
+
+    assert!(package.bitcode_per_module().is_empty());
+}
+
+#[test]
+pub fn invalid_bitcode() {
+    assert!(unsafe { Package::read_bitcode(b"definitely not bitcode") }.is_err());
+}
@@ -0,0 +1,91 @@
+use std::ffi::{CStr, c_char};
+
+use eisheth::{
+    Visibility,
+    function::declaration::FunctionSignature,
+    jit::Jit,
+    package::builder::PackageBuilder,
+    types::{self, RepresentedAs, TypeExtensions},
+    value::ConstValue,
+};
+
+#[test]
+pub fn globals_hold_aggregate_constants() {
+    let mut package_builder = PackageBuilder::new();
+    let module = package_builder.add_module("constants").unwrap();
+
+    let table_type = types::Array::new(u64::representation(), 4);
+    let table = module.define_global(
+        Visibility::Internal,
+        "table",
+        table_type,
+        Some(&table_type.const_value(&[1u64.into(), 2u64.into(), 3u64.into(), 5u64.into()])),
+    );
+
+    let greeting = "hello";
+    let greeting_type = types::Array::new(u8::representation(), greeting.len() + 1);
+    let greeting = module.define_global(
+        Visibility::Internal,
+        "greeting",
+        greeting_type,
+        Some(&ConstValue::null_terminated_string(greeting)),
+    );
+
+    let point = types::Struct::new(
+        "point",
+        &[u32::representation().into(), u32::representation().into()],
+    );
+    let segment = types::Struct::new("segment", &[point.into(), point.into()]);
+    let segment_value = segment.const_value(&[
+        point.const_value(&[1u32.into(), 2u32.into()]),
+        point.const_zero(),
+    ]);
+    let _ = module.define_global(
+        Visibility::Internal,
+        "segment",
+        segment,
+        Some(&segment_value),
+    );
+
+    assert!(table_type.try_const_value(&[1u64.into()]).is_err());
+    assert!(table_type.try_const_value(&[1u32.into(); 4]).is_err());
+
+    let table: ConstValue = module.get_global(table).into();
+    let third_entry = table.element_pointer(table_type, &[0u64.into(), 2u64.into()]);
+
+    let get_third = module.define_function(
+        &FunctionSignature::new(
+            "get_third",
+            types::Function::new(u64::representation().into(), &[]),
+            Visibility::Export,
+        ),
+        |function| {
+            let entry = function.create_block("entry");
+
+            entry.build(|i| i.r#return(i.load(&third_entry, u64::representation(), "third")));
+        },
+    );
+
+    let greeting: ConstValue = module.get_global(greeting).into();
+    let get_greeting = module.define_function(
+        &FunctionSignature::new(
+            "get_greeting",
+            types::Function::new(<*const c_char>::representation().into(), &[]),
+            Visibility::Export,
+        ),
+        |function| {
+            let entry = function.create_block("entry");
+
+            entry.build(|i| i.r#return(greeting));
+        },
+    );
+
+    let package = package_builder.build().unwrap().into_package();
+    let jit = Jit::new(package).unwrap();
+    let get_third = unsafe { jit.get_function::<unsafe extern "C" fn() -> u64>(get_third) };
+    let get_greeting =
+        unsafe { jit.get_function::<unsafe extern "C" fn() -> *const c_char>(get_greeting) };
+
+    assert_eq!(3, unsafe { get_third.call() });
+    assert_eq!(c"hello", unsafe { CStr::from_ptr(get_greeting.call()) });
+}
@@ -0,0 +1,90 @@
+use eisheth::{
+    Visibility,
+    debug_info::DebugTypeEncoding,
+    function::declaration::FunctionSignature,
+    module::builder::ModuleBuilder,
+    package::builder::PackageBuilder,
+    types::{self, RepresentedAs},
+    value::ConstValue,
+};
+
+mod constant {
+    use eisheth::define_module;
+
+    define_module!(
+        module constant {
+            value : builder () -> u64;
+        }
+    );
+
+    mod builder {
+        use eisheth::{function::builder::FunctionBuilder, value::ConstValue};
+
+        pub(super) fn value(function: &FunctionBuilder) {
+            let entry = function.create_block("entry");
+
+            entry.build(|i| {
+                let value: ConstValue = 1u64.into();
+
+                i.r#return(value)
+            });
+        }
+    }
+}
+
+#[test]
+pub fn functions_and_variables_get_debug_info() {
+    let mut module = ModuleBuilder::standalone("debug");
+    module.enable_debug_info("answer.lig", "/src");
+
+    let u64_type = module.debug_basic_type("u64", 64, DebugTypeEncoding::Unsigned);
+    let signature = FunctionSignature::new(
+        "increment",
+        types::Function::new(
+            u64::representation().into(),
+            &[u64::representation().into()],
+        ),
+        Visibility::Export,
+    )
+    .at_line(3);
+
+    let _ = module.define_function(&signature, |function| {
+        let argument = function.debug_argument(0, "x", u64_type, 3);
+        let entry = function.create_block("entry");
+
+        entry.build(|i| {
+            let x = function.get_argument(0).unwrap();
+            i.debug_value(argument, &x);
+
+            i.set_location(4, 5);
+            let one: ConstValue = 1u64.into();
+            let sum = i.add(&x, &one, "sum");
+
+            i.r#return(sum)
+        });
+    });
+
+    let (_, module) = module.build().unwrap();
+    let ir = module.dump_ir();
+
+    assert!(ir.contains("!DIFile(filename: \"answer.lig\", directory: \"/src\")"));
+    assert!(ir.contains("distinct !DISubprogram(name: \"increment\""));
+    assert!(ir.contains("!DILocalVariable(name: \"x\", arg: 1"));
+    assert!(ir.contains("#dbg_value(i64 %0"));
+    assert!(ir.contains("!DILocation(line: 4, column: 5"));
+}
+
+#[test]
+pub fn defined_modules_use_their_source_file() {
+    let mut package_builder = PackageBuilder::new().debug_info(true);
+    let _ = constant::define(&mut package_builder);
+
+    let package = package_builder.build().unwrap().into_package();
+
+    assert!(package.final_ir().contains("debug_info.rs"));
+    assert!(
+        package
+            .final_ir()
+            .contains("distinct !DISubprogram(name: \"value\"")
+    );
+}
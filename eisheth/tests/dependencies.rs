@@ -0,0 +1,147 @@
+use eisheth::{
+    Visibility,
+    function::{builder::FunctionBuilder, declaration::FunctionSignature},
+    module::builder::errors::ImportError,
+    package::{
+        builder::{PackageBuildError, PackageBuilder},
+        dependencies::DependencyError,
+    },
+    types::{self, RepresentedAs},
+    value::ConstValue,
+};
+
+mod side {
+    use eisheth::define_module;
+
+    define_module!(
+        module side {
+            forty : builder () -> u64;
+        }
+    );
+
+    mod builder {
+        use eisheth::{function::builder::FunctionBuilder, value::ConstValue};
+
+        pub(super) fn forty(function: &FunctionBuilder) {
+            let entry = function.create_block("entry");
+
+            entry.build(|i| {
+                let value: ConstValue = 40u64.into();
+
+                i.r#return(value)
+            });
+        }
+    }
+}
+
+mod main {
+    use eisheth::define_module;
+
+    define_module!(
+        module main import (super::side) {
+            answer : builder (^side.forty) -> u64;
+        }
+    );
+
+    mod builder {
+        use eisheth::{function::builder::FunctionBuilder, module::DeclaredFunctionDescriptor};
+
+        pub(super) fn answer(function: &FunctionBuilder, forty: DeclaredFunctionDescriptor) {
+            let entry = function.create_block("entry");
+
+            entry.build(|i| {
+                let forty = i.direct_call(forty, &[], "forty");
+
+                i.r#return(forty)
+            });
+        }
+    }
+}
+
+fn return_constant(function: &FunctionBuilder) {
+    let entry = function.create_block("entry");
+
+    entry.build(|i| {
+        let value: ConstValue = 1u64.into();
+
+        i.r#return(value)
+    });
+}
+
+fn signature(name: &str) -> FunctionSignature {
+    FunctionSignature::new(
+        name,
+        types::Function::new(u64::representation().into(), &[]),
+        Visibility::Export,
+    )
+}
+
+#[test]
+pub fn graph_from_imports() {
+    let mut package_builder = PackageBuilder::new();
+    let side = side::define(&mut package_builder);
+    let _ = main::define(&mut package_builder, &side);
+
+    let graph = package_builder.dependency_graph();
+
+    assert_eq!(["side", "main"], graph.modules());
+    assert_eq!(
+        vec!["side"],
+        graph.dependencies("main").into_iter().collect::<Vec<_>>()
+    );
+    assert!(graph.dependencies("side").is_empty());
+    assert!(graph.cycles().is_empty());
+    assert_eq!(
+        "digraph package {\n    \"side\";\n    \"main\";\n    \"main\" -> \"side\";\n}\n",
+        graph.to_dot()
+    );
+
+    assert!(package_builder.build().is_ok());
+}
+
+#[test]
+pub fn import_from_different_package() {
+    let mut other_package_builder = PackageBuilder::new();
+    let forty = side::define(&mut other_package_builder)
+        .into_freestanding()
+        .get_forty();
+
+    let mut package_builder = PackageBuilder::new();
+    let module = package_builder.add_module("module").unwrap();
+
+    assert!(matches!(
+        module.import_function(forty),
+        Err(ImportError::DifferentPackage)
+    ));
+}
+
+#[test]
+pub fn cyclic_imports() {
+    let mut package_builder = PackageBuilder::new();
+
+    let first = package_builder
+        .add_module("first")
+        .unwrap()
+        .define_function(&signature("first_function"), return_constant);
+
+    let second_module = package_builder.add_module("second").unwrap();
+    let _ = second_module.import_function(first).unwrap();
+    let second = second_module.define_function(&signature("second_function"), return_constant);
+
+    let _ = package_builder
+        .module_mut("first")
+        .unwrap()
+        .import_function(second)
+        .unwrap();
+
+    assert_eq!(
+        vec![vec!["first", "second", "first"]],
+        package_builder.dependency_graph().cycles()
+    );
+
+    let Err(PackageBuildError::Dependencies(errors)) = package_builder.build() else {
+        panic!("expected the build to fail");
+    };
+
+    assert!(matches!(&errors[..], [DependencyError::Cycle(_)]));
+}
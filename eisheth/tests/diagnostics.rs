@@ -0,0 +1,89 @@
+use std::sync::{Arc, Mutex};
+
+use eisheth::{
+    context::diagnostic::{Diagnostic, DiagnosticSeverity},
+    module::built::LinkError,
+    package::builder::{PackageBuildError, PackageBuilder},
+};
+
+macro_rules! constant_module {
+    ($name:ident, $value:literal) => {
+        mod $name {
+            use eisheth::define_module;
+
+            define_module!(
+                module $name {
+                    value : builder () -> u64;
+                }
+            );
+
+            mod builder {
+                use eisheth::{function::builder::FunctionBuilder, value::ConstValue};
+
+                pub(super) fn value(function: &FunctionBuilder) {
+                    let entry = function.create_block("entry");
+
+                    entry.build(|i| {
+                        let value: ConstValue = $value.into();
+
+                        i.r#return(value)
+                    });
+                }
+            }
+        }
+    };
+}
+
+constant_module!(first, 1u64);
+constant_module!(second, 2u64);
+constant_module!(third, 3u64);
+
+#[test]
+pub fn link_diagnostics_are_attributed_to_the_module() {
+    let reported: Arc<Mutex<Vec<Diagnostic>>> = Arc::default();
+    let sink = reported.clone();
+
+    let mut package_builder = PackageBuilder::new().diagnostic_sink(move |x: &Diagnostic| {
+        sink.lock().unwrap().push(x.clone());
+    });
+    let _ = first::define(&mut package_builder)
+        .into_freestanding()
+        .get_value();
+    let _ = second::define(&mut package_builder)
+        .into_freestanding()
+        .get_value();
+
+    let Err(PackageBuildError::Link(errors)) = package_builder.build() else {
+        panic!("both modules define the same function, so linking should fail");
+    };
+    let error = &errors[0];
+
+    let diagnostic = &error.diagnostics()[0];
+    assert_eq!(DiagnosticSeverity::Error, diagnostic.severity());
+    assert_eq!(Some("second"), diagnostic.module());
+    assert!(diagnostic.message().contains("value"));
+
+    assert_eq!(error.diagnostics(), reported.lock().unwrap().as_slice());
+}
+
+#[test]
+pub fn all_link_errors_are_reported() {
+    let mut package_builder = PackageBuilder::new();
+    let _ = first::define(&mut package_builder)
+        .into_freestanding()
+        .get_value();
+    let _ = second::define(&mut package_builder)
+        .into_freestanding()
+        .get_value();
+    let _ = third::define(&mut package_builder)
+        .into_freestanding()
+        .get_value();
+
+    let Err(PackageBuildError::Link(errors)) = package_builder.build() else {
+        panic!("all the modules define the same function, so linking should fail");
+    };
+
+    let failed: Vec<_> = errors.iter().map(LinkError::source_module).collect();
+    assert_eq!(vec!["second", "third"], failed);
+    assert_eq!(vec!["value"], errors[1].symbols());
+}
@@ -0,0 +1,43 @@
+use eisheth::{package::builder::PackageBuilder, target_machine::TargetMachine};
+
+mod test_module {
+    use eisheth::define_module;
+
+    define_module!(
+        module test_module {
+            answer : builder () -> u64;
+        }
+    );
+
+    mod builder {
+        use eisheth::{function::builder::FunctionBuilder, value::ConstValue};
+
+        pub(super) fn answer(function: &FunctionBuilder) {
+            let entry = function.create_block("entry");
+
+            entry.build(|i| {
+                let answer: ConstValue = 42u64.into();
+
+                i.r#return(answer)
+            });
+        }
+    }
+}
+
+#[test]
+pub fn emit_for_host() {
+    let mut package_builder = PackageBuilder::new();
+    let _ = test_module::define(&mut package_builder);
+    let package = package_builder.build().unwrap().into_package();
+
+    let target_machine = TargetMachine::host().unwrap();
+
+    let assembly = package.emit_assembly(&target_machine).unwrap();
+    assert!(assembly.contains("answer"));
+
+    let object = package.emit_object(&target_machine).unwrap();
+    assert!(!object.is_empty());
+
+    // The emission works on a copy, so the package can still be emitted again
+    assert_eq!(assembly, package.emit_assembly(&target_machine).unwrap());
+}
@@ -0,0 +1,127 @@
+use eisheth::{
+    Error, Visibility,
+    function::declaration::FunctionSignature,
+    module::builder::ModuleBuilder,
+    package::builder::{PackageBuildError, PackageBuilder},
+    types::{self, RepresentedAs},
+    value::ConstValue,
+};
+
+#[test]
+pub fn invalid_names_are_reported() {
+    assert!(matches!(
+        ModuleBuilder::try_standalone("nul\0module"),
+        Err(Error::InvalidName(_))
+    ));
+
+    let mut module = ModuleBuilder::standalone("names");
+    let signature = FunctionSignature::new(
+        "nul\0function",
+        types::Function::new(u64::representation().into(), &[]),
+        Visibility::Export,
+    );
+
+    let result = module.try_define_function(&signature, |function| {
+        let entry = function.create_block("entry\0block");
+
+        entry.build(|i| {
+            let value: ConstValue = 1u64.into();
+
+            i.r#return(value)
+        });
+    });
+
+    assert!(matches!(result, Err(Error::InvalidName(name)) if name == "nul\0function"));
+    assert!(matches!(
+        types::Struct::try_new("nul\0struct", &[]),
+        Err(Error::InvalidName(_))
+    ));
+}
+
+#[test]
+pub fn struct_field_count_is_checked() {
+    let r#struct = types::Struct::new("pair", &[u64::representation().into(); 2]);
+    let value: ConstValue = 1u64.into();
+
+    assert!(matches!(
+        r#struct.try_const_value(&[value]),
+        Err(Error::FieldCount {
+            expected: 2,
+            actual: 1
+        })
+    ));
+}
+
+#[test]
+pub fn empty_package_fails_to_build() {
+    let result = PackageBuilder::new().build();
+
+    assert!(matches!(result, Err(PackageBuildError::Empty)));
+
+    let error: Error = result.err().unwrap().into();
+    assert!(matches!(
+        error,
+        Error::PackageBuild(PackageBuildError::Empty)
+    ));
+}
+
+#[test]
+pub fn verifier_errors_are_attributed_to_functions() {
+    let mut module = ModuleBuilder::standalone("verifier");
+    let signature = |name| {
+        FunctionSignature::new(
+            name,
+            types::Function::new(u64::representation().into(), &[]),
+            Visibility::Export,
+        )
+    };
+
+    let _ = module.define_function(&signature("valid"), |function| {
+        let entry = function.create_block("entry");
+
+        entry.build(|i| {
+            let value: ConstValue = 1u64.into();
+
+            i.r#return(value)
+        });
+    });
+    let _ = module.define_function(&signature("unterminated"), |function| {
+        let _ = function.create_block("entry");
+    });
+
+    let Err(error) = module.build() else {
+        panic!("the block has no terminator, so verification should fail");
+    };
+
+    assert_eq!(1, error.verifier_errors().len());
+    assert_eq!(
+        Some("unterminated"),
+        error.verifier_errors()[0].function.as_deref()
+    );
+
+    let report = error.compact().to_string();
+    assert!(report.contains("define i64 @unterminated()"));
+    assert!(!report.contains("define i64 @valid()"));
+}
+
+#[test]
+pub fn invalid_modules_with_debug_info_fail_to_build() {
+    let mut package_builder = PackageBuilder::new().debug_info(true);
+    let module = package_builder.add_module("broken").unwrap();
+
+    let _ = module.define_function(
+        &FunctionSignature::new(
+            "unterminated",
+            types::Function::new(u64::representation().into(), &[]),
+            Visibility::Export,
+        ),
+        |function| {
+            let _ = function.create_block("entry");
+        },
+    );
+
+    assert!(matches!(
+        package_builder.build(),
+        Err(PackageBuildError::Build(errors)) if errors.len() == 1
+    ));
+}
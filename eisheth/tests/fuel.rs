@@ -0,0 +1,130 @@
+use std::time::Duration;
+
+use eisheth::{
+    jit::{Jit, JitCallError, function::JitFunction},
+    package::builder::PackageBuilder,
+};
+
+mod limited {
+    use eisheth::define_module;
+
+    define_module!(
+        module limited {
+            wait : runtime ();
+            one : builder () -> u64;
+            three : builder (^one) -> u64;
+            slow : builder (^wait, ^one) -> u64;
+        }
+    );
+
+    mod runtime {
+        pub(super) unsafe extern "C" fn wait() {
+            std::thread::sleep(std::time::Duration::from_millis(500));
+        }
+    }
+
+    mod builder {
+        use eisheth::{
+            function::builder::FunctionBuilder, module::DeclaredFunctionDescriptor,
+            value::ConstValue,
+        };
+
+        pub(super) fn one(function: &FunctionBuilder) {
+            let entry = function.create_block("entry");
+
+            entry.build(|i| {
+                let value: ConstValue = 1u64.into();
+
+                i.r#return(value)
+            });
+        }
+
+        pub(super) fn three(function: &FunctionBuilder, one: DeclaredFunctionDescriptor) {
+            let entry = function.create_block("entry");
+
+            entry.build(|i| {
+                let first = i.direct_call(one, &[], "first");
+                let second = i.direct_call(one, &[], "second");
+                let third = i.direct_call(one, &[], "third");
+                let sum = i.add(&first, &second, "sum");
+                let sum = i.add(&sum, &third, "sum");
+
+                i.r#return(sum)
+            });
+        }
+
+        pub(super) fn slow(
+            function: &FunctionBuilder,
+            wait: DeclaredFunctionDescriptor,
+            one: DeclaredFunctionDescriptor,
+        ) {
+            let entry = function.create_block("entry");
+
+            entry.build(|i| {
+                let _ = i.direct_call(wait, &[], "");
+                let result = i.direct_call(one, &[], "result");
+
+                i.r#return(result)
+            });
+        }
+    }
+}
+
+fn jit(fuel: bool) -> (Jit, limited::ImportedDefinition) {
+    let mut package_builder = PackageBuilder::new().fuel(fuel);
+    let limited = limited::define(&mut package_builder).into_freestanding();
+    let package = package_builder.build().unwrap().into_package();
+
+    (Jit::new(package).unwrap(), limited)
+}
+
+#[test]
+pub fn fuel_runs_out() {
+    let (jit, limited) = jit(true);
+    let three: JitFunction<unsafe extern "C-unwind" fn() -> u64> =
+        unsafe { jit.get_function(limited.get_three()) };
+
+    jit.set_fuel(10);
+    assert_eq!(Ok(3), unsafe { three.call_guarded() });
+    assert_eq!(Some(6), jit.remaining_fuel());
+
+    jit.set_fuel(2);
+    assert_eq!(Err(JitCallError::OutOfFuel), unsafe {
+        three.call_guarded()
+    });
+    assert_eq!(Some(0), jit.remaining_fuel());
+}
+
+#[test]
+pub fn fuel_is_optional() {
+    let (jit, limited) = jit(false);
+    let three: JitFunction<unsafe extern "C-unwind" fn() -> u64> =
+        unsafe { jit.get_function(limited.get_three()) };
+
+    jit.set_fuel(0);
+    assert_eq!(None, jit.remaining_fuel());
+    assert_eq!(Ok(3), unsafe { three.call_guarded() });
+}
+
+#[test]
+pub fn watchdog_stops_slow_calls() {
+    let (jit, limited) = jit(true);
+    let slow: JitFunction<unsafe extern "C-unwind" fn() -> u64> =
+        unsafe { jit.get_function(limited.get_slow()) };
+
+    let timeout = Duration::from_millis(50);
+    assert_eq!(Err(JitCallError::Timeout(timeout)), unsafe {
+        slow.call_guarded_with_timeout(timeout)
+    });
+}
+
+#[test]
+pub fn late_calls_return_their_result() {
+    let (jit, limited) = jit(false);
+    let slow: JitFunction<unsafe extern "C-unwind" fn() -> u64> =
+        unsafe { jit.get_function(limited.get_slow()) };
+
+    assert_eq!(Ok(1), unsafe {
+        slow.call_guarded_with_timeout(Duration::from_millis(50))
+    });
+}
@@ -0,0 +1,74 @@
+use eisheth::{
+    jit::{Jit, JitCallError},
+    package::builder::PackageBuilder,
+};
+
+mod faulty {
+    use eisheth::define_module;
+
+    define_module!(
+        module faulty {
+            fail : runtime extern "C-unwind" () -> u64;
+            call_fail : builder (^fail) -> u64;
+            read : builder (pointer: *mut u64) -> u64;
+        }
+    );
+
+    mod runtime {
+        pub(super) unsafe extern "C-unwind" fn fail() -> u64 {
+            panic!("runtime failure");
+        }
+    }
+
+    mod builder {
+        use eisheth::{
+            function::builder::FunctionBuilder, module::DeclaredFunctionDescriptor,
+            types::RepresentedAs, value::DynamicValue,
+        };
+
+        pub(super) fn call_fail(function: &FunctionBuilder, fail: DeclaredFunctionDescriptor) {
+            let entry = function.create_block("entry");
+
+            entry.build(|i| {
+                let result = i.direct_call(fail, &[], "result");
+
+                i.r#return(result)
+            });
+        }
+
+        pub(super) fn read(function: &FunctionBuilder, pointer: DynamicValue) {
+            let entry = function.create_block("entry");
+
+            entry.build(|i| {
+                let value = i.load(&pointer, u64::representation(), "value");
+
+                i.r#return(value)
+            });
+        }
+    }
+}
+
+#[test]
+pub fn panics_and_faults_are_returned() {
+    let mut package_builder = PackageBuilder::new();
+    let faulty = faulty::define(&mut package_builder).into_freestanding();
+    let package = package_builder.build().unwrap().into_package();
+
+    let jit = Jit::new(package).unwrap();
+    let call_fail =
+        unsafe { jit.get_function::<unsafe extern "C-unwind" fn() -> u64>(faulty.get_call_fail()) };
+    let read = unsafe {
+        jit.get_function::<unsafe extern "C-unwind" fn(*mut u64) -> u64>(faulty.get_read())
+    };
+
+    assert_eq!(
+        Err(JitCallError::Panic("runtime failure".to_string())),
+        unsafe { call_fail.call_guarded() }
+    );
+    assert_eq!(Err(JitCallError::Fault(libc::SIGSEGV)), unsafe {
+        read.call_guarded(std::ptr::null_mut())
+    });
+
+    let mut value = 42;
+    assert_eq!(Ok(42), unsafe { read.call_guarded(&raw mut value) });
+}
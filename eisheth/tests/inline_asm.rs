@@ -0,0 +1,67 @@
+#![cfg(target_arch = "x86_64")]
+
+use eisheth::{
+    Visibility,
+    function::{declaration::FunctionSignature, inline_asm::InlineAssembly},
+    jit::Jit,
+    package::builder::PackageBuilder,
+    types::{self, RepresentedAs},
+};
+
+#[test]
+pub fn assembly_runs_inline_and_at_module_level() {
+    let mut package_builder = PackageBuilder::new();
+    let module = package_builder.add_module("assembly").unwrap();
+
+    module.append_module_asm(
+        ".globl eisheth_test_answer\neisheth_test_answer:\n  movq $42, %rax\n  retq\n",
+    );
+    let answer = module.declare_external_function(&FunctionSignature::new(
+        "eisheth_test_answer",
+        types::Function::new(u64::representation().into(), &[]),
+        Visibility::Export,
+    ));
+
+    let add_answer = module.define_function(
+        &FunctionSignature::new(
+            "add_answer",
+            types::Function::new(
+                u64::representation().into(),
+                &[u64::representation().into()],
+            ),
+            Visibility::Export,
+        ),
+        |function| {
+            let value = function.get_argument(0).unwrap();
+            let entry = function.create_block("entry");
+
+            entry.build(|i| {
+                let _ = i.inline_asm(
+                    &InlineAssembly::new("pause", "").side_effects(true),
+                    types::Function::new(<()>::representation().into(), &[]),
+                    &[],
+                    "",
+                );
+
+                let answer = i.direct_call(answer, &[], "answer");
+                let sum = i.inline_asm(
+                    &InlineAssembly::new("leaq ($1,$2), $0", "=r,r,r"),
+                    types::Function::new(
+                        u64::representation().into(),
+                        &[u64::representation().into(), u64::representation().into()],
+                    ),
+                    &[&value, &answer],
+                    "sum",
+                );
+
+                i.r#return(sum)
+            });
+        },
+    );
+
+    let package = package_builder.build().unwrap().into_package();
+    let jit = Jit::new(package).unwrap();
+    let add_answer = unsafe { jit.get_function::<unsafe extern "C" fn(u64) -> u64>(add_answer) };
+
+    assert_eq!(50, unsafe { add_answer.call(8) });
+}
@@ -0,0 +1,106 @@
+use eisheth::{jit::Jit, package::builder::PackageBuilder};
+
+mod arithmetic {
+    use eisheth::define_module;
+
+    define_module!(
+        module arithmetic {
+            checked_add : builder (left: u64, right: u64, overflowed: *mut u8) -> u64;
+            count_ones : builder (value: u64) -> u64;
+            largest : builder (left: u64, right: u64) -> u64;
+            copy : builder (destination: *mut u8, source: *mut u8, length: u64) -> u64;
+        }
+    );
+
+    mod builder {
+        use eisheth::{
+            function::{builder::FunctionBuilder, intrinsics::Signedness},
+            value::{ConstValue, DynamicValue},
+        };
+
+        pub(super) fn checked_add(
+            function: &FunctionBuilder,
+            left: DynamicValue,
+            right: DynamicValue,
+            overflowed: DynamicValue,
+        ) {
+            let entry = function.create_block("entry");
+
+            entry.build(|i| {
+                let sum = i.add_with_overflow(&left, &right, Signedness::Unsigned, "sum");
+                i.store(&overflowed, &sum.overflowed);
+
+                i.r#return(sum.value)
+            });
+        }
+
+        pub(super) fn count_ones(function: &FunctionBuilder, value: DynamicValue) {
+            let entry = function.create_block("entry");
+
+            entry.build(|i| i.r#return(i.count_ones(&value, "ones")));
+        }
+
+        pub(super) fn largest(function: &FunctionBuilder, left: DynamicValue, right: DynamicValue) {
+            let entry = function.create_block("entry");
+
+            entry.build(|i| i.r#return(i.max(&left, &right, Signedness::Unsigned, "largest")));
+        }
+
+        pub(super) fn copy(
+            function: &FunctionBuilder,
+            destination: DynamicValue,
+            source: DynamicValue,
+            length: DynamicValue,
+        ) {
+            let entry = function.create_block("entry");
+
+            entry.build(|i| {
+                i.memcpy(&destination, &source, &length);
+
+                let zero: ConstValue = 0u64.into();
+                i.r#return(zero)
+            });
+        }
+    }
+}
+
+#[test]
+pub fn intrinsics_compute_the_expected_values() {
+    let mut package_builder = PackageBuilder::new();
+    let arithmetic = arithmetic::define(&mut package_builder).into_freestanding();
+    let package = package_builder.build().unwrap().into_package();
+    let jit = Jit::new(package).unwrap();
+
+    let checked_add = unsafe {
+        jit.get_function::<unsafe extern "C" fn(u64, u64, *mut u8) -> u64>(
+            arithmetic.get_checked_add(),
+        )
+    };
+    let count_ones = unsafe {
+        jit.get_function::<unsafe extern "C" fn(u64) -> u64>(arithmetic.get_count_ones())
+    };
+    let largest = unsafe {
+        jit.get_function::<unsafe extern "C" fn(u64, u64) -> u64>(arithmetic.get_largest())
+    };
+    let copy = unsafe {
+        jit.get_function::<unsafe extern "C" fn(*mut u8, *mut u8, u64) -> u64>(
+            arithmetic.get_copy(),
+        )
+    };
+
+    let mut overflowed = 0;
+    assert_eq!(3, unsafe { checked_add.call(1, 2, &raw mut overflowed) });
+    assert_eq!(0, overflowed);
+    assert_eq!(0, unsafe {
+        checked_add.call(u64::MAX, 1, &raw mut overflowed)
+    });
+    assert_eq!(1, overflowed);
+
+    assert_eq!(3, unsafe { count_ones.call(0b1011) });
+    assert_eq!(u64::MAX, unsafe { largest.call(u64::MAX, 1) });
+
+    let mut source = *b"hello";
+    let mut destination = [0; 5];
+    let _ = unsafe { copy.call(destination.as_mut_ptr(), source.as_mut_ptr(), 5) };
+    assert_eq!(source, destination);
+}
@@ -0,0 +1,57 @@
+use eisheth::{
+    jit::{Jit, options::JitOptions},
+    package::builder::PackageBuilder,
+};
+
+mod answer {
+    use eisheth::define_module;
+
+    define_module!(
+        module answer {
+            answer : builder () -> u64;
+        }
+    );
+
+    mod builder {
+        use eisheth::{function::builder::FunctionBuilder, value::ConstValue};
+
+        pub(super) fn answer(function: &FunctionBuilder) {
+            let entry = function.create_block("entry");
+
+            entry.build(|i| {
+                let value: ConstValue = 42u64.into();
+
+                i.r#return(value)
+            });
+        }
+    }
+}
+
+#[test]
+pub fn perf_map_lists_the_compiled_functions() {
+    let mut package_builder = PackageBuilder::new();
+    let answer = answer::define(&mut package_builder)
+        .into_freestanding()
+        .get_answer();
+    let package = package_builder.build().unwrap().into_package();
+
+    let jit = Jit::with_options(
+        package,
+        &JitOptions::new().frame_pointers(true).perf_map(true),
+    )
+    .unwrap();
+
+    let function = unsafe { jit.get_function::<unsafe extern "C" fn() -> u64>(answer) };
+    assert_eq!(42, unsafe { function.call() });
+
+    let map = std::fs::read_to_string(
+        std::env::temp_dir().join(format!("perf-{}.map", std::process::id())),
+    )
+    .unwrap();
+
+    let address = format!("{:x} ", function.address());
+    assert!(
+        map.lines()
+            .any(|line| line.starts_with(&address) && line.ends_with(" answer"))
+    );
+}
@@ -0,0 +1,116 @@
+use eisheth::{
+    Visibility,
+    function::declaration::FunctionSignature,
+    jit::Jit,
+    package::{builder::PackageBuilder, optimization::Optimization},
+    types::{self, RepresentedAs},
+    value::ConstValue,
+};
+
+mod side {
+    use eisheth::define_module;
+
+    define_module!(
+        module side {
+            forty : builder () -> u64;
+        }
+    );
+
+    mod builder {
+        use eisheth::{function::builder::FunctionBuilder, value::ConstValue};
+
+        pub(super) fn forty(function: &FunctionBuilder) {
+            let entry = function.create_block("entry");
+
+            entry.build(|i| {
+                let value: ConstValue = 40u64.into();
+
+                i.r#return(value)
+            });
+        }
+    }
+}
+
+mod main {
+    use eisheth::define_module;
+
+    define_module!(
+        module main import (super::side) {
+            answer : builder (^side.forty) -> u64;
+        }
+    );
+
+    mod builder {
+        use eisheth::{
+            function::builder::FunctionBuilder, module::DeclaredFunctionDescriptor,
+            value::ConstValue,
+        };
+
+        pub(super) fn answer(function: &FunctionBuilder, forty: DeclaredFunctionDescriptor) {
+            let entry = function.create_block("entry");
+
+            entry.build(|i| {
+                let forty = i.direct_call(forty, &[], "forty");
+                let two: ConstValue = 2u64.into();
+                let sum = i.add(&forty, &two, "sum");
+
+                i.r#return(sum)
+            });
+        }
+    }
+}
+
+#[test]
+pub fn inline_across_modules() {
+    let mut package_builder = PackageBuilder::new().link_time_optimization(Optimization::O2);
+    let side = side::define(&mut package_builder);
+    let answer = main::define(&mut package_builder, &side)
+        .into_freestanding()
+        .get_answer();
+
+    package_builder.preserve_function(answer);
+
+    let package = package_builder.build().unwrap().into_package();
+    let final_ir = package.final_ir();
+
+    assert!(final_ir.contains("ret i64 42"));
+    // The function that was not preserved got internalized, and removed after inlining
+    assert!(!final_ir.contains("@forty"));
+
+    let jit = Jit::new(package).unwrap();
+    let answer = unsafe { jit.get_function::<unsafe extern "C" fn() -> u64>(answer) };
+
+    assert_eq!(42, unsafe { answer.call() });
+    assert!(
+        unsafe { jit.get_function_by_name::<unsafe extern "C" fn() -> u64>("forty") }.is_none()
+    );
+}
+
+#[test]
+pub fn preserved_internal_functions_are_kept() {
+    let mut package_builder = PackageBuilder::new()
+        .optimization(Optimization::O2)
+        .link_time_optimization(Optimization::O2);
+    let module = package_builder.add_module("internal").unwrap();
+    let answer = module.define_function(
+        &FunctionSignature::new(
+            "answer",
+            types::Function::new(u64::representation().into(), &[]),
+            Visibility::Internal,
+        ),
+        |function| {
+            let entry = function.create_block("entry");
+            let value: ConstValue = 42u64.into();
+
+            entry.build(|i| i.r#return(value));
+        },
+    );
+
+    package_builder.preserve_function(answer);
+
+    let package = package_builder.build().unwrap().into_package();
+    let jit = Jit::new(package).unwrap();
+    let answer = unsafe { jit.get_function::<unsafe extern "C" fn() -> u64>(answer) };
+
+    assert_eq!(42, unsafe { answer.call() });
+}
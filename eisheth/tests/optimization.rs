@@ -0,0 +1,133 @@
+use eisheth::{
+    Visibility,
+    function::declaration::FunctionSignature,
+    jit::Jit,
+    package::{
+        builder::{PackageBuildError, PackageBuilder},
+        optimization::Optimization,
+    },
+    types::{self, RepresentedAs},
+    value::ConstValue,
+};
+
+mod test_module {
+    use eisheth::define_module;
+
+    define_module!(
+        module test_module {
+            internal add : builder (left: u64, right: u64) -> u64;
+            answer : builder (^add) -> u64;
+        }
+    );
+
+    mod builder {
+        use eisheth::{
+            function::builder::FunctionBuilder,
+            module::DeclaredFunctionDescriptor,
+            value::{ConstValue, DynamicValue},
+        };
+
+        pub(super) fn add(function: &FunctionBuilder, left: DynamicValue, right: DynamicValue) {
+            let entry = function.create_block("entry");
+
+            entry.build(|i| {
+                let sum = i.add(&left, &right, "sum");
+
+                i.r#return(sum)
+            });
+        }
+
+        pub(super) fn answer(function: &FunctionBuilder, add: DeclaredFunctionDescriptor) {
+            let entry = function.create_block("entry");
+
+            entry.build(|i| {
+                let left: ConstValue = 40u64.into();
+                let right: ConstValue = 2u64.into();
+                let result = i.direct_call(add, &[&left, &right], "result");
+
+                i.r#return(result)
+            });
+        }
+    }
+}
+
+#[test]
+pub fn optimize_with_preset() {
+    let mut package_builder = PackageBuilder::new().optimization(Optimization::O2);
+    let module = test_module::define(&mut package_builder).into_freestanding();
+    let answer = module.get_answer();
+
+    let package = package_builder.build().unwrap().into_package();
+
+    assert!(package.ir_per_module()["test_module"].contains("call"));
+    // The internal function gets inlined, and the whole call folded into a constant
+    assert!(!package.optimized_ir_per_module()["test_module"].contains("call"));
+    assert!(package.optimized_ir_per_module()["test_module"].contains("ret i64 42"));
+
+    let jit = Jit::new(package).unwrap();
+    let answer = unsafe { jit.get_function::<unsafe extern "C" fn() -> u64>(answer) };
+
+    assert_eq!(42, unsafe { answer.call() });
+}
+
+#[test]
+pub fn unused_internal_functions_are_removed() {
+    let mut package_builder = PackageBuilder::new().optimization(Optimization::O2);
+    let _ = test_module::define(&mut package_builder);
+
+    let package = package_builder.build().unwrap().into_package();
+
+    // Nothing calls `add` once it's inlined, and nothing outside of the module can
+    assert!(!package.optimized_ir_per_module()["test_module"].contains("@add"));
+}
+
+#[test]
+pub fn preserved_internal_functions_are_kept() {
+    let mut package_builder = PackageBuilder::new().optimization(Optimization::O2);
+    let module = package_builder.add_module("internal").unwrap();
+    let answer = module.define_function(
+        &FunctionSignature::new(
+            "answer",
+            types::Function::new(u64::representation().into(), &[]),
+            Visibility::Internal,
+        ),
+        |function| {
+            let entry = function.create_block("entry");
+            let value: ConstValue = 42u64.into();
+
+            entry.build(|i| i.r#return(value));
+        },
+    );
+
+    package_builder.preserve_function(answer);
+
+    let package = package_builder.build().unwrap().into_package();
+    assert!(package.optimized_ir_per_module()["internal"].contains("@answer"));
+
+    let jit = Jit::new(package).unwrap();
+    let answer = unsafe { jit.get_function::<unsafe extern "C" fn() -> u64>(answer) };
+
+    assert_eq!(42, unsafe { answer.call() });
+}
+
+#[test]
+pub fn invalid_custom_pipeline() {
+    let mut package_builder =
+        PackageBuilder::new().optimization(Optimization::Custom("not-a-pass".to_string()));
+    let _ = test_module::define(&mut package_builder);
+
+    assert!(matches!(
+        package_builder.build(),
+        Err(PackageBuildError::Optimization(_))
+    ));
+}
+
+#[test]
+pub fn disabled_by_default() {
+    let mut package_builder = PackageBuilder::new();
+    let _ = test_module::define(&mut package_builder);
+
+    let package = package_builder.build().unwrap().into_package();
+
+    assert!(package.optimized_ir_per_module().is_empty());
+}
@@ -0,0 +1,119 @@
+use std::num::NonZeroUsize;
+
+use eisheth::{
+    jit::Jit,
+    package::{Package, builder::PackageBuilder, optimization::Optimization},
+};
+
+mod side {
+    use eisheth::define_module;
+
+    define_module!(
+        module side {
+            forty : builder () -> u64;
+            two : runtime () -> u64;
+        }
+    );
+
+    mod runtime {
+        pub(super) const unsafe extern "C" fn two() -> u64 {
+            2
+        }
+    }
+
+    mod builder {
+        use eisheth::{function::builder::FunctionBuilder, value::ConstValue};
+
+        pub(super) fn forty(function: &FunctionBuilder) {
+            let entry = function.create_block("entry");
+
+            entry.build(|i| {
+                let value: ConstValue = 40u64.into();
+
+                i.r#return(value)
+            });
+        }
+    }
+}
+
+mod main {
+    use eisheth::define_module;
+
+    define_module!(
+        module main import (super::side) {
+            answer : builder (^side.forty, ^side.two) -> u64;
+        }
+    );
+
+    mod builder {
+        use eisheth::{function::builder::FunctionBuilder, module::DeclaredFunctionDescriptor};
+
+        pub(super) fn answer(
+            function: &FunctionBuilder,
+            forty: DeclaredFunctionDescriptor,
+            two: DeclaredFunctionDescriptor,
+        ) {
+            let entry = function.create_block("entry");
+
+            entry.build(|i| {
+                let forty = i.direct_call(forty, &[], "forty");
+                let two = i.direct_call(two, &[], "two");
+                let sum = i.add(&forty, &two, "sum");
+
+                i.r#return(sum)
+            });
+        }
+    }
+}
+
+fn build(threads: usize) -> Package {
+    let mut package_builder = PackageBuilder::new().optimization(Optimization::O2);
+    let side = side::define(&mut package_builder);
+    let _ = main::define(&mut package_builder, &side);
+
+    package_builder
+        .build_parallel(NonZeroUsize::new(threads).unwrap())
+        .unwrap()
+        .into_package()
+}
+
+#[test]
+pub fn parallel_build_matches_serial_build() {
+    let serial = build(1);
+    let parallel = build(4);
+
+    assert_eq!(serial.final_ir(), parallel.final_ir());
+    assert_eq!(serial.ir_per_module(), parallel.ir_per_module());
+    assert_eq!(
+        serial.optimized_ir_per_module(),
+        parallel.optimized_ir_per_module()
+    );
+}
+
+#[test]
+pub fn package_can_be_jitted_on_another_thread() {
+    let package = build(2);
+
+    let answer = std::thread::spawn(move || {
+        let jit = Jit::new(package).unwrap();
+
+        let answer =
+            unsafe { jit.get_function_by_name::<unsafe extern "C" fn() -> u64>("answer") }.unwrap();
+
+        unsafe { answer.call() }
+    })
+    .join()
+    .unwrap();
+
+    assert_eq!(42, answer);
+}
+
+#[test]
+pub fn parallel_build_maps_runtime_functions() {
+    let jit = Jit::new(build(4)).unwrap();
+
+    let answer =
+        unsafe { jit.get_function_by_name::<unsafe extern "C" fn() -> u64>("answer") }.unwrap();
+
+    assert_eq!(42, unsafe { answer.call() });
+}
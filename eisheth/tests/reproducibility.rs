@@ -0,0 +1,69 @@
+use eisheth::package::builder::PackageBuilder;
+
+macro_rules! constant_module {
+    ($name:ident, $function:ident, $value:literal) => {
+        mod $name {
+            use eisheth::define_module;
+
+            define_module!(
+                module $name {
+                    $function : builder () -> u64;
+                }
+            );
+
+            mod builder {
+                use eisheth::{function::builder::FunctionBuilder, value::ConstValue};
+
+                pub(super) fn $function(function: &FunctionBuilder) {
+                    let entry = function.create_block("entry");
+
+                    entry.build(|i| {
+                        let value: ConstValue = $value.into();
+
+                        i.r#return(value)
+                    });
+                }
+            }
+        }
+    };
+}
+
+constant_module!(first, get_first, 1u64);
+constant_module!(second, get_second, 2u64);
+constant_module!(third, get_third, 3u64);
+constant_module!(fourth, get_fourth, 4u64);
+
+fn build_final_ir() -> String {
+    let mut package_builder = PackageBuilder::new();
+
+    let _ = first::define(&mut package_builder);
+    let _ = second::define(&mut package_builder);
+    let _ = third::define(&mut package_builder);
+    let _ = fourth::define(&mut package_builder);
+
+    let package = package_builder.build().unwrap().into_package();
+
+    assert_eq!(
+        vec!["first", "fourth", "second", "third"],
+        package.ir_per_module().keys().collect::<Vec<_>>()
+    );
+
+    package.final_ir()
+}
+
+#[test]
+pub fn final_ir_is_reproducible() {
+    let expected = build_final_ir();
+
+    // The modules are linked into the first one, in the order they were added in
+    assert!(expected.contains("; ModuleID = 'first'"));
+    let positions: Vec<_> = ["@get_first", "@get_second", "@get_third", "@get_fourth"]
+        .iter()
+        .map(|name| expected.find(name).unwrap())
+        .collect();
+    assert!(positions.is_sorted());
+
+    for _ in 0..8 {
+        assert_eq!(expected, build_final_ir());
+    }
+}
@@ -0,0 +1,63 @@
+use eisheth::{
+    Visibility,
+    function::declaration::FunctionSignature,
+    module::builder::ModuleBuilder,
+    testing::{assert_ir_snapshot, normalize_ir},
+    types::{self, RepresentedAs},
+    value::ConstValue,
+};
+
+fn signature(name: &str) -> FunctionSignature {
+    FunctionSignature::new(
+        name,
+        types::Function::new(u64::representation().into(), &[]),
+        Visibility::Export,
+    )
+}
+
+#[test]
+pub fn module_ir_matches_snapshot() {
+    let mut module = ModuleBuilder::standalone("answer");
+
+    let forty = module.define_function(&signature("forty"), |function| {
+        let entry = function.create_block("entry");
+
+        entry.build(|i| {
+            let value: ConstValue = 40u64.into();
+
+            i.r#return(value)
+        });
+    });
+
+    let _ = module.define_function(&signature("answer"), |function| {
+        let entry = function.create_block("entry");
+
+        entry.build(|i| {
+            let forty = i.direct_call(forty, &[], "forty");
+            let two: ConstValue = 2u64.into();
+            let sum = i.add(&forty, &two, "sum");
+
+            i.r#return(sum)
+        });
+    });
+
+    assert!(module.get_function(forty).dump_ir().contains("ret i64 40"));
+    assert_ir_snapshot(
+        concat!(env!("CARGO_MANIFEST_DIR"), "/tests/snapshots/answer.ll"),
+        &module.dump_ir(),
+    );
+}
+
+#[test]
+pub fn normalization_ignores_order_and_ids() {
+    let first = "; ModuleID = 'first'\n\
+                 @b = global i64 0, !dbg !3\n\
+                 @a = global i64 0, !dbg !7\n\
+                 attributes #2 = { nounwind }\n";
+    let second = "; ModuleID = 'second'\n\
+                  @a = global i64 0, !dbg !0\n\
+                  @b = global i64 0, !dbg !1\n\
+                  attributes #0 = { nounwind }\n";
+
+    assert_eq!(normalize_ir(first), normalize_ir(second));
+}
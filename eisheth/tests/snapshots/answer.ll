@@ -0,0 +1,10 @@
+define i64 @answer() {
+entry:
+  %forty = call i64 @forty()
+  %sum = add i64 %forty, 2
+  ret i64 %sum
+}
+define i64 @forty() {
+entry:
+  ret i64 40
+}
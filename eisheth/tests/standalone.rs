@@ -0,0 +1,95 @@
+use eisheth::{jit::Jit, package::builder::PackageBuilder};
+
+mod library {
+    use eisheth::define_module;
+
+    define_module!(
+        module library {
+            internal global offset : u64 = 1000;
+            add_offset : builder (^offset, value: u64) -> u64;
+        }
+    );
+
+    mod builder {
+        use eisheth::{
+            function::builder::FunctionBuilder, module::DeclaredGlobalDescriptor,
+            types::RepresentedAs, value::DynamicValue,
+        };
+
+        pub(super) fn add_offset(
+            function: &FunctionBuilder,
+            offset: DeclaredGlobalDescriptor,
+            value: DynamicValue,
+        ) {
+            let entry = function.create_block("entry");
+
+            entry.build(|i| {
+                let offset = i.load(&offset, u64::representation(), "offset");
+                let sum = i.add(&value, &offset, "sum");
+
+                i.r#return(sum)
+            });
+        }
+    }
+}
+
+mod main {
+    use eisheth::define_module;
+
+    define_module!(
+        module main import (super::library) {
+            compute : builder (^library.add_offset, value: u64) -> u64;
+        }
+    );
+
+    mod builder {
+        use eisheth::{
+            function::builder::FunctionBuilder, module::DeclaredFunctionDescriptor,
+            value::DynamicValue,
+        };
+
+        pub(super) fn compute(
+            function: &FunctionBuilder,
+            add_offset: DeclaredFunctionDescriptor,
+            value: DynamicValue,
+        ) {
+            let entry = function.create_block("entry");
+
+            entry.build(|i| {
+                let result = i.direct_call(add_offset, &[&value], "result");
+
+                i.r#return(result)
+            });
+        }
+    }
+}
+
+#[test]
+pub fn attach_to_many_packages() {
+    let library = library::define_standalone().unwrap();
+
+    for input in [1, 2] {
+        let mut package_builder = PackageBuilder::new();
+        let library = library.attach(&mut package_builder);
+        let compute = main::define(&mut package_builder, &library)
+            .into_freestanding()
+            .get_compute();
+
+        let package = package_builder.build().unwrap().into_package();
+
+        let jit = Jit::new(package).unwrap();
+        let compute = unsafe { jit.get_function::<unsafe extern "C" fn(u64) -> u64>(compute) };
+
+        assert_eq!(1000 + input, unsafe { compute.call(input) });
+    }
+}
+
+#[test]
+pub fn attach_twice() {
+    let library = library::define_standalone().unwrap();
+
+    let mut package_builder = PackageBuilder::new();
+    let _ = library.attach(&mut package_builder);
+
+    assert!(package_builder.attach_module(library.module()).is_err());
+}
@@ -0,0 +1,66 @@
+use std::ffi::{CStr, c_char};
+
+use eisheth::{
+    Visibility,
+    function::declaration::FunctionSignature,
+    jit::Jit,
+    package::builder::PackageBuilder,
+    types::{Function, RepresentedAs},
+};
+
+#[test]
+pub fn string_constants_are_deduplicated() {
+    let mut package_builder = PackageBuilder::new();
+    let module = package_builder.add_module("strings").unwrap();
+
+    let greeting = module.define_function(
+        &FunctionSignature::new(
+            "greeting",
+            Function::new(<*const c_char>::representation().into(), &[]),
+            Visibility::Export,
+        ),
+        |function| {
+            let entry = function.create_block("entry");
+
+            entry.build(|i| i.r#return(i.string_literal("hello").0));
+        },
+    );
+    let greeting_length = module.define_function(
+        &FunctionSignature::new(
+            "greeting_length",
+            Function::new(u64::representation().into(), &[]),
+            Visibility::Export,
+        ),
+        |function| {
+            let entry = function.create_block("entry");
+
+            entry.build(|i| i.r#return(i.string_literal("hello").1));
+        },
+    );
+
+    let same_greeting = module.define_string_constant("hello");
+    let same_greeting = module.define_function(
+        &FunctionSignature::new(
+            "same_greeting",
+            Function::new(<*const c_char>::representation().into(), &[]),
+            Visibility::Export,
+        ),
+        |function| {
+            let entry = function.create_block("entry");
+
+            entry.build(|i| i.r#return(same_greeting.pointer()));
+        },
+    );
+
+    let package = package_builder.build().unwrap().into_package();
+    let jit = Jit::new(package).unwrap();
+    let greeting = unsafe { jit.get_function::<unsafe extern "C" fn() -> *const c_char>(greeting) };
+    let greeting_length =
+        unsafe { jit.get_function::<unsafe extern "C" fn() -> u64>(greeting_length) };
+    let same_greeting =
+        unsafe { jit.get_function::<unsafe extern "C" fn() -> *const c_char>(same_greeting) };
+
+    assert_eq!(c"hello", unsafe { CStr::from_ptr(greeting.call()) });
+    assert_eq!(5, unsafe { greeting_length.call() });
+    assert_eq!(unsafe { greeting.call() }, unsafe { same_greeting.call() });
+}
@@ -0,0 +1,106 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use eisheth::{
+    Visibility,
+    function::declaration::FunctionSignature,
+    jit::Jit,
+    package::builder::PackageBuilder,
+    types::{self, RepresentedAs},
+    value::ConstValue,
+};
+
+static CLEANED_UP: AtomicBool = AtomicBool::new(false);
+
+unsafe extern "C-unwind" fn fail(value: u64) -> u64 {
+    panic!("failed with {value}");
+}
+
+unsafe extern "C-unwind" fn clean_up() {
+    CLEANED_UP.store(true, Ordering::SeqCst);
+}
+
+#[test]
+pub fn panics_unwind_through_jit_code() {
+    let mut package_builder = PackageBuilder::new();
+    let module = package_builder.add_module("unwinding").unwrap();
+
+    // The personality of C code compiled with `-fexceptions` from libgcc, which the standard
+    // library links for unwinding. It only runs cleanups, which is all the landing pad does.
+    let personality = module.declare_external_function(&FunctionSignature::new(
+        "__gcc_personality_v0",
+        types::Function::new(
+            u32::representation().into(),
+            &[
+                u32::representation().into(),
+                u32::representation().into(),
+                u64::representation().into(),
+                <*mut u8>::representation().into(),
+                <*mut u8>::representation().into(),
+            ],
+        ),
+        Visibility::Export,
+    ));
+    let fail = unsafe {
+        module.define_runtime_function(
+            &FunctionSignature::new(
+                "fail",
+                types::Function::new(
+                    u64::representation().into(),
+                    &[u64::representation().into()],
+                ),
+                Visibility::Internal,
+            ),
+            fail as unsafe extern "C-unwind" fn(u64) -> u64 as usize,
+        )
+    };
+    let clean_up = unsafe {
+        module.define_runtime_function(
+            &FunctionSignature::new(
+                "clean_up",
+                types::Function::new(<()>::representation().into(), &[]),
+                Visibility::Internal,
+            ),
+            clean_up as unsafe extern "C-unwind" fn() as usize,
+        )
+    };
+
+    let compute = module.define_function(
+        &FunctionSignature::new(
+            "compute",
+            types::Function::new(u64::representation().into(), &[]),
+            Visibility::Export,
+        ),
+        |function| {
+            function.set_personality(personality);
+
+            let entry = function.create_block("entry");
+            let normal = function.create_block("normal");
+            let unwind = function.create_block("unwind");
+
+            let mut result = None;
+            entry.build(|i| {
+                let value: ConstValue = 42u64.into();
+                let (value, terminator) = i.invoke(fail, &[&value], &normal, &unwind, "result");
+                result = Some(value);
+
+                terminator
+            });
+            normal.build(|i| i.r#return(result.unwrap()));
+            unwind.build(|i| {
+                let exception = i.landing_pad(&[], true, "exception");
+                let _ = i.direct_call(clean_up, &[], "");
+
+                i.resume(&exception)
+            });
+        },
+    );
+
+    let package = package_builder.build().unwrap().into_package();
+    let jit = Jit::new(package).unwrap();
+    let compute = unsafe { jit.get_function::<unsafe extern "C-unwind" fn() -> u64>(compute) };
+
+    let result = std::panic::catch_unwind(|| unsafe { compute.call() });
+
+    assert!(result.is_err());
+    assert!(CLEANED_UP.load(Ordering::SeqCst));
+}
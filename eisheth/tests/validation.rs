@@ -0,0 +1,93 @@
+use eisheth::{
+    Visibility,
+    function::{
+        declaration::FunctionSignature,
+        validation::{ValidationError, ValidationErrorKind},
+    },
+    module::builder::ModuleBuilder,
+    types::{self, RepresentedAs},
+    value::ConstValue,
+};
+
+fn signature(name: &str, arguments: &[types::OpaqueType]) -> FunctionSignature {
+    FunctionSignature::new(
+        name,
+        types::Function::new(u64::representation().into(), arguments),
+        Visibility::Export,
+    )
+}
+
+#[test]
+pub fn invalid_instructions_fail_before_verification() {
+    let mut module = ModuleBuilder::standalone("invalid");
+
+    let callee = module.define_function(
+        &signature("callee", &[u64::representation().into()]),
+        |function| {
+            let entry = function.create_block("entry");
+
+            entry.build(|i| i.r#return(function.get_argument(0).unwrap()));
+        },
+    );
+
+    let _ = module.define_function(&signature("caller", &[]), |function| {
+        let entry = function.create_block("entry");
+
+        entry.build(|i| {
+            let narrow: ConstValue = 1u32.into();
+            let wide: ConstValue = 1u64.into();
+
+            let _ = i.add(&narrow, &wide, "sum");
+            let result = i.direct_call(callee, &[], "result");
+
+            i.r#return(result)
+        });
+    });
+
+    let _ = module.define_function(&signature("narrow", &[]), |function| {
+        let entry = function.create_block("entry");
+
+        entry.build(|i| {
+            let narrow: ConstValue = 1u32.into();
+
+            i.r#return(narrow)
+        });
+    });
+
+    let Err(error) = module.build() else {
+        panic!("the module contains invalid instructions");
+    };
+    let kinds: Vec<_> = error.validation_errors().iter().map(|x| &x.kind).collect();
+
+    assert_eq!(
+        vec![
+            &ValidationErrorKind::OperandTypeMismatch {
+                left: "i32".to_string(),
+                right: "i64".to_string(),
+            },
+            &ValidationErrorKind::ArgumentCount {
+                expected: 1,
+                actual: 0,
+            },
+            &ValidationErrorKind::ReturnType {
+                expected: "i64".to_string(),
+                actual: "i32".to_string(),
+            },
+        ],
+        kinds
+    );
+
+    let functions: Vec<_> = error
+        .validation_errors()
+        .iter()
+        .map(|x: &ValidationError| (x.function.as_str(), x.block.as_str()))
+        .collect();
+    assert_eq!(
+        vec![
+            ("caller", "entry"),
+            ("caller", "entry"),
+            ("narrow", "entry")
+        ],
+        functions
+    );
+}
@@ -3,13 +3,34 @@ use std::collections::HashMap;
 use eisheth::{
     function::{declaration::FunctionSignature, instruction_builder::InstructionBuilder},
     module::DeclaredFunctionDescriptor,
-    package::{Package, builder::PackageBuilder},
+    package::{Package, builder::PackageBuilder, optimization::Optimization},
     types::{self, OpaqueType, RepresentedAs},
     value::{ConstOrDynamicValue, ConstValue},
 };
 
 use crate::parser::ast::{self, Expression, FunctionBody, Identifier, SourceFile, Statement};
 
+/// The symbol under which the program's `main` is exported when compiling for native output.
+pub const NATIVE_ENTRY_POINT: &str = "ligeia_main";
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OutputKind {
+    #[default]
+    Jit,
+    /// The program's `main` gets exported as [`NATIVE_ENTRY_POINT`], so that it does not clash
+    /// with the C `main` of the executable it will be linked into.
+    Native,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct CompileOptions {
+    pub output_kind: OutputKind,
+    pub optimization: Optimization,
+    /// Runs the link-time optimization with the same level as `optimization`, so functions can
+    /// be inlined across files. Only `main` stays visible outside of the program.
+    pub link_time_optimization: bool,
+}
+
 #[must_use]
 pub struct CompiledProgram {
     package: Package,
@@ -29,30 +50,38 @@ impl CompiledProgram {
 /// # Panics
 /// Will panic if the program fails to compile. This means a bug in the compiler, as all not
 /// well-formed programs should be declined at the analysis stage.
-pub fn compile(files: Vec<SourceFile>) -> CompiledProgram {
-    let mut package_builder = PackageBuilder::new();
+pub fn compile(files: Vec<SourceFile>, options: &CompileOptions) -> CompiledProgram {
+    let mut package_builder = PackageBuilder::new().optimization(options.optimization.clone());
+
+    if options.link_time_optimization {
+        package_builder = package_builder.link_time_optimization(options.optimization.clone());
+    }
 
     let mut main = None;
 
     for file in files {
-        if let Some(found_main) = compile_file(file, &mut package_builder) {
+        if let Some(found_main) = compile_file(file, &mut package_builder, options.output_kind) {
             main = Some(found_main);
         }
     }
 
+    let main = main.unwrap();
+    package_builder.preserve_function(main);
+
     let build_result = package_builder.build().unwrap();
 
     eprintln!("{:?}", build_result.messages());
 
     CompiledProgram {
         package: build_result.into_package(),
-        main: main.unwrap(),
+        main,
     }
 }
 
 fn compile_file(
     file: SourceFile,
     package_builder: &mut PackageBuilder,
+    output_kind: OutputKind,
 ) -> Option<DeclaredFunctionDescriptor> {
     let module = package_builder.add_module(file.name).unwrap();
 
@@ -62,12 +91,18 @@ fn compile_file(
         match declaration {
             ast::Declaration::Function(function) => {
                 let is_main = function.name.0 == "main";
+                let (name, visibility) = if is_main && output_kind == OutputKind::Native {
+                    (NATIVE_ENTRY_POINT.to_string(), eisheth::Visibility::Export)
+                } else {
+                    (function.name.0.clone(), function.visibility.into())
+                };
+
                 // TODO get the Visibility from source
                 let function_id = module.define_function(
                     &FunctionSignature::new(
-                        function.name.0.clone(),
+                        name,
                         make_function_type(function.return_type, &function.arguments),
-                        function.visibility.into(),
+                        visibility,
                     ),
                     |f| {
                         compile_function_body(function, f);
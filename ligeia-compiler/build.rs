@@ -0,0 +1,41 @@
+use std::{env, path::PathBuf, process::Command};
+
+/// Builds the runtime static library linked into native programs. It's a separate package, so
+/// that it's built without the `eisheth` feature the compiler enables on the runtime, which would
+/// otherwise get unified into it.
+fn main() {
+    println!("cargo::rerun-if-changed=../ligeia-runtime");
+    println!("cargo::rerun-if-changed=../ligeia-runtime-native");
+
+    let manifest = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap())
+        .join("../ligeia-runtime-native/Cargo.toml");
+    let target = env::var("TARGET").unwrap();
+    let profile = env::var("PROFILE").unwrap();
+    let target_directory = PathBuf::from(env::var("OUT_DIR").unwrap()).join("runtime");
+
+    let mut command = Command::new(env::var("CARGO").unwrap());
+    command
+        .arg("build")
+        .arg("--manifest-path")
+        .arg(&manifest)
+        .arg("--target")
+        .arg(&target)
+        .arg("--target-dir")
+        .arg(&target_directory);
+
+    if profile == "release" {
+        command.arg("--release");
+    }
+
+    let status = command.status().unwrap();
+    assert!(status.success(), "Failed to build the runtime library");
+
+    println!(
+        "cargo::rustc-env=LIGEIA_RUNTIME_LIBRARY={}",
+        target_directory
+            .join(target)
+            .join(profile)
+            .join("libligeia_runtime.a")
+            .display()
+    );
+}
@@ -0,0 +1,166 @@
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use eisheth::{
+    package::Package,
+    target_machine::{
+        EmitError, RelocationModel,
+        builder::{TargetMachineBuilder, TargetMachineError},
+    },
+};
+use ligeia_compiler_lib::compiler::NATIVE_ENTRY_POINT;
+use thiserror::Error;
+
+pub const RUNTIME_LIBRARY_NAME: &str = "libligeia_runtime.a";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArtifactKind {
+    Executable,
+    /// A `.a` archive containing the program and the C `main` wrapper. The runtime library gets
+    /// copied next to it, as it has to be linked in as well.
+    StaticLibrary,
+}
+
+#[derive(Debug, Error)]
+pub enum AotError {
+    #[error(transparent)]
+    TargetMachine(#[from] TargetMachineError),
+    #[error(transparent)]
+    Emit(#[from] EmitError),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("The runtime library was not found at {0}")]
+    RuntimeNotFound(PathBuf),
+    #[error("Command `{command}` failed:\n{stderr}")]
+    CommandFailed { command: String, stderr: String },
+    #[error("Linking executables is not supported on this platform, build a static library")]
+    UnsupportedPlatform,
+}
+
+/// The system libraries the runtime library depends on, as printed by
+/// `rustc --print native-static-libs` for the platform the compiler is built for.
+const fn native_libraries() -> Option<&'static [&'static str]> {
+    if cfg!(all(target_os = "linux", target_env = "gnu")) {
+        Some(&[
+            "-lgcc_s",
+            "-lutil",
+            "-lrt",
+            "-lpthread",
+            "-lm",
+            "-ldl",
+            "-lc",
+        ])
+    } else if cfg!(all(target_os = "linux", target_env = "musl")) {
+        Some(&["-lc"])
+    } else if cfg!(target_os = "macos") {
+        Some(&["-lSystem", "-lc", "-lm"])
+    } else {
+        None
+    }
+}
+
+/// The tool set in the environment variable, like `CC` for the C compiler, or the default one.
+fn tool(variable: &str, default: &str) -> Command {
+    Command::new(std::env::var_os(variable).unwrap_or_else(|| default.into()))
+}
+
+/// Emits the package as a native object, and links it with a generated C entry point and the
+/// runtime library, using the system C compiler and archiver (`cc` and `ar`, unless overriden
+/// with `CC` and `AR`).
+pub fn build(
+    package: &Package,
+    kind: ArtifactKind,
+    output: &Path,
+    runtime_library: &Path,
+) -> Result<(), AotError> {
+    if !runtime_library.is_file() {
+        return Err(AotError::RuntimeNotFound(runtime_library.to_path_buf()));
+    }
+
+    let target_machine = TargetMachineBuilder::new()
+        .relocation_model(RelocationModel::PositionIndependent)
+        .build()?;
+
+    let working_directory = tempfile::Builder::new().prefix("ligeia-").tempdir()?;
+
+    let object_path = working_directory.path().join("program.o");
+    std::fs::write(&object_path, package.emit_object(&target_machine)?)?;
+
+    let entry_path = working_directory.path().join("entry.c");
+    std::fs::write(&entry_path, entry_point_source())?;
+
+    match kind {
+        ArtifactKind::Executable => {
+            let native_libraries = native_libraries().ok_or(AotError::UnsupportedPlatform)?;
+
+            run(tool("CC", "cc")
+                .arg("-o")
+                .arg(output)
+                .arg(&object_path)
+                .arg(&entry_path)
+                .arg(runtime_library)
+                .args(native_libraries))?;
+        }
+        ArtifactKind::StaticLibrary => {
+            let entry_object_path = working_directory.path().join("entry.o");
+
+            run(tool("CC", "cc")
+                .arg("-c")
+                .arg("-fPIC")
+                .arg("-o")
+                .arg(&entry_object_path)
+                .arg(&entry_path))?;
+
+            if output.exists() {
+                std::fs::remove_file(output)?;
+            }
+
+            run(tool("AR", "ar")
+                .arg("rcs")
+                .arg(output)
+                .arg(&object_path)
+                .arg(&entry_object_path))?;
+
+            let runtime_output = output.with_file_name(RUNTIME_LIBRARY_NAME);
+            std::fs::copy(runtime_library, runtime_output)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn entry_point_source() -> String {
+    format!(
+        r#"#include <inttypes.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+uint64_t {NATIVE_ENTRY_POINT}(uint64_t);
+
+int main(int argc, char **argv) {{
+    uint64_t input = argc > 1 ? strtoull(argv[1], NULL, 10) : 0;
+    uint64_t result = {NATIVE_ENTRY_POINT}(input);
+
+    printf("Result: %" PRIu64 "\n", result);
+
+    return 0;
+}}
+"#
+    )
+}
+
+fn run(command: &mut Command) -> Result<(), AotError> {
+    let output = command.output()?;
+
+    if output.status.success() {
+        return Ok(());
+    }
+
+    Err(AotError::CommandFailed {
+        command: format!("{command:?}"),
+        stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+    })
+}
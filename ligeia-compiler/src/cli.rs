@@ -0,0 +1,229 @@
+use std::path::PathBuf;
+
+use eisheth::package::optimization::Optimization;
+use thiserror::Error;
+
+use crate::aot::ArtifactKind;
+
+pub enum Command {
+    /// Runs the built-in demo programs in the JIT.
+    Demo,
+    Build {
+        source: PathBuf,
+        output: PathBuf,
+        kind: ArtifactKind,
+        runtime_library: PathBuf,
+        optimization: Optimization,
+        link_time_optimization: bool,
+    },
+}
+
+#[derive(Debug, Error)]
+pub enum ArgumentsError {
+    #[error("Unknown command \"{0}\"")]
+    UnknownCommand(String),
+    #[error("Unexpected argument \"{0}\"")]
+    UnexpectedArgument(String),
+    #[error("Missing value for {0}")]
+    MissingValue(&'static str),
+    #[error("Missing the source file")]
+    MissingSource,
+    #[error("Missing the output path (-o)")]
+    MissingOutput,
+    #[error("Unknown optimization level \"{0}\"")]
+    UnknownOptimizationLevel(String),
+}
+
+pub const USAGE: &str = "Usage:
+    ligeia
+    ligeia build <source.lig> -o <output> [--static-library] [--runtime <libligeia_runtime.a>]
+        [-O0|-O1|-O2|-O3|-Os|-Oz|--passes <pipeline>] [--lto]
+
+Set LIGEIA_PERF_MAP=1 to make the JIT-ed code of the demo visible to perf.";
+
+pub const PERF_MAP_VARIABLE: &str = "LIGEIA_PERF_MAP";
+
+pub fn parse(mut arguments: impl Iterator<Item = String>) -> Result<Command, ArgumentsError> {
+    let Some(command) = arguments.next() else {
+        return Ok(Command::Demo);
+    };
+
+    if command != "build" {
+        return Err(ArgumentsError::UnknownCommand(command));
+    }
+
+    let mut source = None;
+    let mut output = None;
+    let mut kind = ArtifactKind::Executable;
+    let mut runtime_library = None;
+    let mut optimization = Optimization::O2;
+    let mut link_time_optimization = false;
+
+    while let Some(argument) = arguments.next() {
+        match argument.as_str() {
+            "-o" => {
+                output = Some(PathBuf::from(
+                    arguments.next().ok_or(ArgumentsError::MissingValue("-o"))?,
+                ));
+            }
+            "--static-library" => kind = ArtifactKind::StaticLibrary,
+            "--runtime" => {
+                runtime_library = Some(PathBuf::from(
+                    arguments
+                        .next()
+                        .ok_or(ArgumentsError::MissingValue("--runtime"))?,
+                ));
+            }
+            "--lto" => link_time_optimization = true,
+            "--passes" => {
+                optimization = Optimization::Custom(
+                    arguments
+                        .next()
+                        .ok_or(ArgumentsError::MissingValue("--passes"))?,
+                );
+            }
+            level if level.starts_with("-O") => {
+                optimization = parse_optimization_level(level)?;
+            }
+            _ if source.is_none() && !argument.starts_with('-') => {
+                source = Some(PathBuf::from(argument));
+            }
+            _ => return Err(ArgumentsError::UnexpectedArgument(argument)),
+        }
+    }
+
+    Ok(Command::Build {
+        source: source.ok_or(ArgumentsError::MissingSource)?,
+        output: output.ok_or(ArgumentsError::MissingOutput)?,
+        kind,
+        runtime_library: runtime_library.unwrap_or_else(default_runtime_library),
+        optimization,
+        link_time_optimization,
+    })
+}
+
+fn parse_optimization_level(argument: &str) -> Result<Optimization, ArgumentsError> {
+    Ok(match argument {
+        "-O0" => Optimization::O0,
+        "-O1" => Optimization::O1,
+        "-O2" => Optimization::O2,
+        "-O3" => Optimization::O3,
+        "-Os" => Optimization::Os,
+        "-Oz" => Optimization::Oz,
+        _ => {
+            return Err(ArgumentsError::UnknownOptimizationLevel(
+                argument.to_string(),
+            ));
+        }
+    })
+}
+
+/// The runtime library built along with the compiler, see `build.rs`.
+fn default_runtime_library() -> PathBuf {
+    PathBuf::from(env!("LIGEIA_RUNTIME_LIBRARY"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aot::RUNTIME_LIBRARY_NAME;
+
+    fn parse_arguments(arguments: &[&str]) -> Result<Command, ArgumentsError> {
+        parse(arguments.iter().map(ToString::to_string))
+    }
+
+    #[test]
+    fn demo_without_arguments() {
+        assert!(matches!(parse_arguments(&[]), Ok(Command::Demo)));
+    }
+
+    #[test]
+    fn build_with_defaults() {
+        let Ok(Command::Build {
+            source,
+            output,
+            kind,
+            runtime_library,
+            optimization,
+            link_time_optimization,
+        }) = parse_arguments(&["build", "main.lig", "-o", "main"])
+        else {
+            panic!("expected a build command");
+        };
+
+        assert_eq!(PathBuf::from("main.lig"), source);
+        assert_eq!(PathBuf::from("main"), output);
+        assert_eq!(ArtifactKind::Executable, kind);
+        assert!(runtime_library.ends_with(RUNTIME_LIBRARY_NAME));
+        assert_eq!(Optimization::O2, optimization);
+        assert!(!link_time_optimization);
+    }
+
+    #[test]
+    fn build_with_options() {
+        let Ok(Command::Build {
+            kind,
+            runtime_library,
+            optimization,
+            link_time_optimization,
+            ..
+        }) = parse_arguments(&[
+            "build",
+            "-o",
+            "libmain.a",
+            "main.lig",
+            "--static-library",
+            "--runtime",
+            "runtime.a",
+            "-O3",
+            "--lto",
+        ])
+        else {
+            panic!("expected a build command");
+        };
+
+        assert_eq!(ArtifactKind::StaticLibrary, kind);
+        assert_eq!(PathBuf::from("runtime.a"), runtime_library);
+        assert_eq!(Optimization::O3, optimization);
+        assert!(link_time_optimization);
+
+        let Ok(Command::Build { optimization, .. }) =
+            parse_arguments(&["build", "main.lig", "-o", "main", "--passes", "instcombine"])
+        else {
+            panic!("expected a build command");
+        };
+
+        assert_eq!(
+            Optimization::Custom("instcombine".to_string()),
+            optimization
+        );
+    }
+
+    #[test]
+    fn invalid_arguments() {
+        assert!(matches!(
+            parse_arguments(&["run"]),
+            Err(ArgumentsError::UnknownCommand(_))
+        ));
+        assert!(matches!(
+            parse_arguments(&["build", "-o", "main"]),
+            Err(ArgumentsError::MissingSource)
+        ));
+        assert!(matches!(
+            parse_arguments(&["build", "main.lig"]),
+            Err(ArgumentsError::MissingOutput)
+        ));
+        assert!(matches!(
+            parse_arguments(&["build", "main.lig", "-o"]),
+            Err(ArgumentsError::MissingValue("-o"))
+        ));
+        assert!(matches!(
+            parse_arguments(&["build", "main.lig", "-o", "main", "-O4"]),
+            Err(ArgumentsError::UnknownOptimizationLevel(_))
+        ));
+        assert!(matches!(
+            parse_arguments(&["build", "main.lig", "other.lig", "-o", "main"]),
+            Err(ArgumentsError::UnexpectedArgument(_))
+        ));
+    }
+}
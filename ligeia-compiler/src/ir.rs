@@ -7,5 +7,13 @@ pub fn print_to_files(name: &str, package: &Package) {
         std::fs::write(format!("./output/{name}/modules/{module_name}.ll"), raw_ir).unwrap();
     }
 
+    for (module_name, raw_ir) in package.optimized_ir_per_module() {
+        std::fs::write(
+            format!("./output/{name}/modules/{module_name}.optimized.ll"),
+            raw_ir,
+        )
+        .unwrap();
+    }
+
     std::fs::write(format!("./output/{name}/linked.ll"), package.final_ir()).unwrap();
 }
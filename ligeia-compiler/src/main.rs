@@ -1,15 +1,94 @@
+mod aot;
+mod cli;
 mod ir;
 mod test_program;
 mod value;
 mod vector;
 
+use std::{path::Path, process::ExitCode};
+
 use eisheth::{
-    jit::{Jit, function::JitFunction},
-    package::builder::PackageBuilder,
+    jit::{Jit, function::JitFunction, options::JitOptions},
+    package::{builder::PackageBuilder, optimization::Optimization},
+};
+use ligeia_compiler_lib::{
+    analysis::analyse,
+    compiler::{self, CompileOptions, OutputKind},
+    parser,
 };
-use ligeia_compiler_lib::{analysis::analyse, compiler, parser};
 
-fn main() {
+fn main() -> ExitCode {
+    match cli::parse(std::env::args().skip(1)) {
+        Ok(cli::Command::Demo) => {
+            run_demo();
+
+            ExitCode::SUCCESS
+        }
+        Ok(cli::Command::Build {
+            source,
+            output,
+            kind,
+            runtime_library,
+            optimization,
+            link_time_optimization,
+        }) => match build(
+            &source,
+            &output,
+            kind,
+            &runtime_library,
+            &CompileOptions {
+                output_kind: OutputKind::Native,
+                optimization,
+                link_time_optimization,
+            },
+        ) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(error) => {
+                eprintln!("{error}");
+
+                ExitCode::FAILURE
+            }
+        },
+        Err(error) => {
+            eprintln!("{error}\n{}", cli::USAGE);
+
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn build(
+    source: &Path,
+    output: &Path,
+    kind: aot::ArtifactKind,
+    runtime_library: &Path,
+    options: &CompileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let code = std::fs::read_to_string(source)?;
+    let file_name = source
+        .file_name()
+        .map_or_else(|| "main.lig".into(), |name| name.to_string_lossy());
+
+    let files = vec![parser::parse(&file_name, &code)];
+
+    analyse(&files)?;
+
+    let package = compiler::compile(files, options).into_package();
+
+    aot::build(&package, kind, output, runtime_library)?;
+
+    Ok(())
+}
+
+/// Profiling the JIT-ed code with `perf` needs the perf map, and the frame pointers to walk the
+/// stack, which are only kept when asked for with the environment variable.
+fn jit_options() -> JitOptions {
+    let profile = std::env::var_os(cli::PERF_MAP_VARIABLE).is_some();
+
+    JitOptions::new().frame_pointers(profile).perf_map(profile)
+}
+
+fn run_demo() {
     let result = parser::parse(
         "main.lig",
         "fn main(input: u64) -> u64 { return input + 1024; }",
@@ -20,14 +99,21 @@ fn main() {
 
     analyse(&files).unwrap();
 
-    let program = compiler::compile(files);
+    let program = compiler::compile(
+        files,
+        &CompileOptions {
+            output_kind: OutputKind::Jit,
+            optimization: Optimization::O2,
+            link_time_optimization: false,
+        },
+    );
 
     let main = program.main();
     let package = program.into_package();
 
     ir::print_to_files("compiled", &package);
 
-    let jit = Jit::new(package).unwrap();
+    let jit = Jit::with_options(package, &jit_options()).unwrap();
 
     // SAFETY: The function signature matches the one declared in our program
     let main = unsafe { jit.get_function::<unsafe extern "C" fn(u64) -> u64>(main) };
@@ -39,8 +125,12 @@ fn main() {
     let mut package_builder = PackageBuilder::new();
 
     let side = test_program::side::define(&mut package_builder);
-    let value = value::define(&mut package_builder);
-    let vector = vector::define(&mut package_builder);
+    let value = value::define_standalone()
+        .unwrap()
+        .attach(&mut package_builder);
+    let vector = vector::define_standalone()
+        .unwrap()
+        .attach(&mut package_builder);
     let exported_globals = test_program::exported_globals::define(&mut package_builder);
     let imports = test_program::imports::define(&mut package_builder, &value);
 
@@ -74,15 +164,16 @@ fn main() {
 
     ir::print_to_files("built", &package);
 
-    let jit = Jit::new(package).unwrap();
+    let jit = Jit::with_options(package, &jit_options()).unwrap();
 
     // SAFETY: The signature matches the signature of the declaration
-    let callable: JitFunction<unsafe extern "C" fn(u64) -> u64> =
+    let callable: JitFunction<unsafe extern "C-unwind" fn(u64) -> u64> =
         unsafe { jit.get_function(main_function) };
 
     // SAFETY: The JITted code is correct and memory safe, right? I'm sure there aren't any bugs
-    // lurking
-    let result = unsafe { callable.call(12) };
-
-    println!("Result: {result}");
+    // lurking, and if there are, the guarded call reports them instead of crashing
+    match unsafe { callable.call_guarded(12) } {
+        Ok(result) => println!("Result: {result}"),
+        Err(error) => eprintln!("The test program failed: {error}"),
+    }
 }
@@ -3,10 +3,10 @@ use eisheth::define_module;
 define_module!(
     module imports import(crate::value) {
         internal global my_val : crate::value::ffi::Value;
-        init_my_val : builder (^my_val, ^value.initialize_pointer);
+        init_my_val : builder (^my_val, ^value.ligeia_value_initialize_pointer);
         fini_my_val : builder (^my_val);
 
-        show_info : builder (^my_val, ^value.debug_print);
+        show_info : builder (^my_val, ^value.ligeia_value_debug_print);
 
         global_initializer: 512, init_my_val, my_val;
         global_finalizer: 512, fini_my_val, my_val;
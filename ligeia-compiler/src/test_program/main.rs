@@ -8,14 +8,14 @@ define_module!(
         internal global test_type : u64 = 1;
 
         types_initializer : builder (
-            ^vector.initializer,
-            ^vector.push_uninitialized,
-            ^value.initialize_pointer,
-            ^value.debug_print,
+            ^vector.ligeia_vector_initializer,
+            ^vector.ligeia_vector_push_uninitialized,
+            ^value.ligeia_value_initialize_pointer,
+            ^value.ligeia_value_debug_print,
             ^types,
             ^test_type,
         );
-        types_finalizer : builder (^vector.finalizer, ^types);
+        types_finalizer : builder (^vector.ligeia_vector_finalizer, ^types);
         internal other : builder (value: u64) -> u64;
         main : builder (^imports.show_info, ^exported_globals.important_number, ^side.side_fn, ^other, input: u64) -> u64;
 
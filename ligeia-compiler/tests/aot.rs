@@ -0,0 +1,42 @@
+use std::process::Command;
+
+#[test]
+fn build_and_run_executable() {
+    let directory = tempfile::tempdir().unwrap();
+    let source = directory.path().join("main.lig");
+    let executable = directory.path().join("main");
+
+    std::fs::write(
+        &source,
+        "fn main(input: u64) -> u64 { return input + 1024; }",
+    )
+    .unwrap();
+
+    let build = Command::new(env!("CARGO_BIN_EXE_ligeia"))
+        .arg("build")
+        .arg(&source)
+        .arg("-o")
+        .arg(&executable)
+        .output()
+        .unwrap();
+    assert!(
+        build.status.success(),
+        "{}",
+        String::from_utf8_lossy(&build.stderr)
+    );
+
+    // The runtime library is built without the eisheth representations of its types, so none of
+    // LLVM gets linked into the program
+    let contents = std::fs::read(&executable).unwrap();
+    let symbol = b"LLVMContextCreate";
+    assert!(
+        !contents
+            .windows(symbol.len())
+            .any(|window| window == symbol)
+    );
+
+    let run = Command::new(&executable).arg("1").output().unwrap();
+
+    assert!(run.status.success());
+    assert_eq!("Result: 1025\n", String::from_utf8_lossy(&run.stdout));
+}
@@ -0,0 +1,4 @@
+//! The runtime as a static library, which native programs are linked with. It's not a part of the
+//! workspace, so it's built without the features the compiler enables on the runtime.
+
+pub use runtime::*;
@@ -2,9 +2,7 @@ pub mod pointer;
 
 use std::fmt::Debug;
 
-use eisheth::{ffi_enum, ffi_struct};
-
-#[ffi_enum]
+#[cfg_attr(feature = "eisheth", eisheth::ffi_enum)]
 #[repr(u8)]
 #[allow(unused)]
 #[derive(Debug, PartialEq, Eq)]
@@ -17,7 +15,7 @@ pub enum Type {
     Pointer = 64,
 }
 
-#[ffi_struct]
+#[cfg_attr(feature = "eisheth", eisheth::ffi_struct)]
 #[repr(C)]
 // TODO Pointers should have a type id that points at the type table, which contains the pointed-to
 // type
@@ -12,6 +12,8 @@ impl Debug for PointerValue {
 }
 
 impl PointerValue {
+    /// # Safety
+    /// `value` must point to a valid, writable `Value`.
     pub unsafe fn initialize(value: *mut Value, target: *mut u8) -> *mut Self {
         // SAFETY: it's up to the caller to provide valid pointers, as long as those are right, the
         // value will initialize correctly
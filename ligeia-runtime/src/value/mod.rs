@@ -0,0 +1,32 @@
+pub mod ffi;
+
+use crate::value::ffi::{Value, pointer::PointerValue};
+
+/// # Safety
+/// The `value` must point at memory valid for writing a `Value`
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ligeia_value_initialize_pointer(
+    value: *mut Value,
+    target_pointer: *mut u8,
+) {
+    // SAFETY: It's up to the user to provide a a valid pointer to a value and a valid
+    // target_pointer. As long as those are correct, the created Value will be valid
+    unsafe {
+        PointerValue::initialize(value, target_pointer);
+    }
+}
+
+/// # Safety
+/// The `value` must point at a valid, initialized `Value`
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ligeia_value_debug_print(value: *mut Value) {
+    // SAFETY: It's caller's responsibility to provide a valid pointer, the cast is correct
+    // because PointerValue is repr(transparent) with a Value inside
+    if let Some(pointer) = unsafe { PointerValue::ptr_from(value) } {
+        // SAFETY: It's caller's responsibility to provide a valid pointer
+        let value = unsafe { &mut *pointer };
+        println!("{value:?}");
+    } else {
+        todo!();
+    }
+}
@@ -1,6 +1,4 @@
-use eisheth::ffi_struct;
-
-#[ffi_struct]
+#[cfg_attr(feature = "eisheth", eisheth::ffi_struct)]
 #[repr(C)]
 #[derive(Debug)]
 pub struct Vector {
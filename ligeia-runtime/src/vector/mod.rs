@@ -0,0 +1,24 @@
+pub mod ffi;
+
+use crate::vector::ffi::Vector;
+
+/// # Safety
+/// The `pointer` must point at memory valid for writing a `Vector`
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ligeia_vector_initializer(pointer: *mut Vector, element_size: u64) {
+    Vector::initialize(pointer, element_size);
+}
+
+/// # Safety
+/// The `vector` must point at an initialized `Vector`
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ligeia_vector_push_uninitialized(vector: *mut Vector) -> *mut u8 {
+    Vector::push_uninitialized(vector)
+}
+
+/// # Safety
+/// The `vector` must point at an initialized `Vector`, which won't be used afterwards
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ligeia_vector_finalizer(vector: *mut Vector) {
+    Vector::finalize(vector);
+}